@@ -1,20 +1,28 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
 
+use crate::animations::SharedClock;
 use crate::post_message_w;
-use crate::utils::WM_APP_ANIMATE;
+use crate::utils::AppMessage;
 
 #[derive(Debug, Clone)]
 pub struct AnimationTimer {
     stop_flag: Arc<Mutex<bool>>,
+    // Set while an Animate message is waiting in the border's queue. We only post a new one once
+    // the border has picked up the last, so a busy border doesn't get a backlog of ticks that it
+    // then processes in a burst.
+    is_tick_pending: Arc<AtomicBool>,
 }
 
 impl AnimationTimer {
-    pub fn start(hwnd: HWND, interval_ms: u64) -> Self {
+    pub fn start(hwnd: HWND, interval_ms: u64, clock: SharedClock) -> Self {
         let stop_flag = Arc::new(Mutex::new(false));
         let stop_flag_clone = stop_flag.clone();
+        let is_tick_pending = Arc::new(AtomicBool::new(false));
+        let is_tick_pending_clone = is_tick_pending.clone();
 
         let hwnd_isize = hwnd.0 as isize;
 
@@ -22,21 +30,37 @@ impl AnimationTimer {
         thread::spawn(move || {
             let hwnd = HWND(hwnd_isize as _);
             let interval = Duration::from_millis(interval_ms);
+            let mut next_tick = clock.now();
 
             while !*stop_flag_clone.lock().unwrap() {
-                if let Err(e) = post_message_w(hwnd, WM_APP_ANIMATE, WPARAM(0), LPARAM(0)) {
-                    error!(
-                        "could not send animation timer message for {:?}: {}",
-                        hwnd, e
-                    );
-                    break;
+                if !is_tick_pending_clone.swap(true, Ordering::AcqRel) {
+                    if let Err(e) =
+                        post_message_w(hwnd, AppMessage::Animate.into(), WPARAM(0), LPARAM(0))
+                    {
+                        error!(
+                            "could not send animation timer message for {:?}: {}",
+                            hwnd, e
+                        );
+                        break;
+                    }
                 }
-                thread::sleep(interval);
+
+                let now = clock.now();
+                next_tick = schedule_next_tick(next_tick, interval, now);
+                thread::sleep(next_tick - now);
             }
         });
 
         // Return the timer instance
-        Self { stop_flag }
+        Self {
+            stop_flag,
+            is_tick_pending,
+        }
+    }
+
+    // Called by the border when it receives an Animate message
+    pub fn acknowledge_tick(&self) {
+        self.is_tick_pending.store(false, Ordering::Release);
     }
 
     pub fn stop(&mut self) {
@@ -46,3 +70,42 @@ impl AnimationTimer {
         }
     }
 }
+
+// Schedule ticks against a fixed timeline so time spent posting the message (or waking up late)
+// doesn't accumulate. If we've fallen behind by more than a tick, skip the missed ticks instead of
+// firing them all back-to-back.
+fn schedule_next_tick(last_tick: Instant, interval: Duration, now: Instant) -> Instant {
+    let next_tick = last_tick + interval;
+    match next_tick <= now {
+        true => now + interval,
+        false => next_tick,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_stay_on_a_fixed_timeline() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(16);
+
+        // Waking up a little late shouldn't push back later ticks
+        let now = start + Duration::from_millis(18);
+        assert_eq!(
+            schedule_next_tick(start + interval, interval, now),
+            start + interval * 2
+        );
+    }
+
+    #[test]
+    fn missed_ticks_are_skipped() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(16);
+
+        // After stalling for several ticks, the next tick is a full interval from now
+        let now = start + Duration::from_millis(100);
+        assert_eq!(schedule_next_tick(start, interval, now), now + interval);
+    }
+}
@@ -1,13 +1,17 @@
 use serde::Deserialize;
+use std::f32::consts::PI;
 use std::sync::Arc;
 use std::time;
 
 use windows::Foundation::Numerics::Matrix3x2;
 
 use crate::anim_timer::AnimationTimer;
-use crate::border_config::serde_default_i32;
-use crate::utils::cubic_bezier;
+use crate::border_config::{serde_default_bool, serde_default_i32};
+use crate::color_math::{interpolate_color, ColorSpace};
+use crate::colors::Color;
+use crate::utils::{cubic_bezier, steps, StepPosition};
 use crate::window_border::WindowBorder;
+use crate::APP_STATE;
 
 #[derive(Debug, Default, Clone, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
@@ -18,9 +22,43 @@ pub struct AnimationsConfig {
     pub inactive: Vec<AnimParamsConfig>,
     #[serde(default = "serde_default_i32::<60>")]
     pub fps: i32,
+    #[serde(default = "serde_default_bool::<true>")]
+    pub frame_skip: bool,
+    #[serde(default)]
+    pub fade_in: Option<FadeConfig>,
+    #[serde(default)]
+    pub fade_out: Option<FadeConfig>,
+}
+
+// Optional overrides for the Fade animation depending on the direction of the state transition
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FadeConfig {
+    pub duration: Option<f32>,
+    pub easing: Option<AnimEasing>,
+}
+
+impl FadeConfig {
+    fn to_anim_params(&self) -> AnimParams {
+        AnimParamsConfig {
+            anim_type: AnimType::Fade,
+            duration: self.duration,
+            easing: self.easing,
+        }
+        .to_anim_params()
+    }
 }
 
 impl AnimationsConfig {
+    // A config without any animations, used for 'animations: False' in window rules
+    pub fn disabled() -> Self {
+        Self {
+            fps: 60,
+            frame_skip: true,
+            ..Default::default()
+        }
+    }
+
     pub fn to_animations(&self) -> Animations {
         Animations {
             active: self
@@ -34,22 +72,70 @@ impl AnimationsConfig {
                 .map(|params_config| params_config.to_anim_params())
                 .collect(),
             fps: self.fps,
+            frame_skip: self.frame_skip,
+            fade_in: self.fade_in.as_ref().map(FadeConfig::to_anim_params),
+            fade_out: self.fade_out.as_ref().map(FadeConfig::to_anim_params),
             ..Default::default()
         }
     }
 }
 
+// Source of the current time for animations. Borders use SystemClock, while tests can substitute a
+// clock that they step manually to get deterministic results.
+pub trait AnimClock: Send + Sync {
+    fn now(&self) -> time::Instant;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl AnimClock for SystemClock {
+    fn now(&self) -> time::Instant {
+        time::Instant::now()
+    }
+}
+
+#[derive(Clone)]
+pub struct SharedClock(pub Arc<dyn AnimClock>);
+
+impl SharedClock {
+    pub fn now(&self) -> time::Instant {
+        self.0.now()
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self(Arc::new(SystemClock))
+    }
+}
+
+impl std::fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedClock")
+            .field(&Arc::as_ptr(&self.0))
+            .finish()
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Animations {
     pub active: Vec<AnimParams>,
     pub inactive: Vec<AnimParams>,
     pub timer: Option<AnimationTimer>,
     pub fps: i32,
+    pub frame_skip: bool,
+    pub fade_in: Option<AnimParams>,
+    pub fade_out: Option<AnimParams>,
     pub fade_progress: f32,
     pub fade_to_visible: bool,
     pub should_fade: bool,
     pub spiral_progress: f32,
     pub spiral_angle: f32,
+    // Set while a focus ring is playing. It's drawn on top of the border and cleared once finished.
+    pub focus_ring_progress: Option<f32>,
+    pub focus_ring: Option<FocusRingFrame>,
+    pub clock: SharedClock,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -66,15 +152,15 @@ impl AnimParamsConfig {
         let duration = self.duration.unwrap_or(match self.anim_type {
             AnimType::Spiral | AnimType::ReverseSpiral => 1800.0,
             AnimType::Fade => 200.0,
+            AnimType::FocusRing => 400.0,
         });
 
         let easing = self.easing.unwrap_or_default();
-        let easing_function = cubic_bezier(&easing.to_points()).unwrap();
 
         AnimParams {
             anim_type: self.anim_type,
             duration,
-            easing_fn: Arc::new(easing_function),
+            easing_fn: easing.to_easing_fn(),
         }
     }
 }
@@ -113,6 +199,8 @@ pub enum AnimType {
     Spiral,
     ReverseSpiral,
     Fade,
+    // Plays once each time the window gains focus, so it only has an effect in 'active'
+    FocusRing,
 }
 
 // Thanks to 0xJWLabs for the AnimEasing enum along with its methods
@@ -157,13 +245,90 @@ pub enum AnimEasing {
 
     #[serde(untagged)]
     CubicBezier([f32; 4]),
+
+    // Easing functions as written in CSS (and exported by tools like cubic-bezier.com), e.g.
+    // "cubic-bezier(.17,.67,.83,.67)" or "steps(4, jump-start)"
+    #[serde(untagged)]
+    Function(EasingFunction),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub enum EasingFunction {
+    CubicBezier([f32; 4]),
+    Steps(u32, StepPosition),
+}
+
+impl TryFrom<String> for EasingFunction {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let value = value.trim();
+        let invalid = || format!("invalid easing function: {value}");
+
+        let (name, args) = value
+            .strip_suffix(')')
+            .and_then(|value| value.split_once('('))
+            .ok_or_else(invalid)?;
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+
+        match (name.trim(), args.as_slice()) {
+            ("cubic-bezier", [x1, y1, x2, y2]) => {
+                let mut points = [0.0; 4];
+                for (point, arg) in points.iter_mut().zip([x1, y1, x2, y2]) {
+                    *point = arg.parse().map_err(|_| invalid())?;
+                }
+
+                // Check the control points now so an invalid curve is a config error
+                if let Err(err) = cubic_bezier(&points) {
+                    return Err(format!("{}: {err}", invalid()));
+                }
+
+                Ok(EasingFunction::CubicBezier(points))
+            }
+            ("steps", [count, rest @ ..]) => {
+                let count: u32 = count.parse().map_err(|_| invalid())?;
+                let position = match rest {
+                    [] | ["end"] | ["jump-end"] => StepPosition::End,
+                    ["start"] | ["jump-start"] => StepPosition::Start,
+                    ["jump-none"] => StepPosition::Neither,
+                    ["jump-both"] => StepPosition::Both,
+                    _ => return Err(invalid()),
+                };
+
+                // jump-none needs at least 2 steps to get from 0 to 1
+                let min_count = match position {
+                    StepPosition::Neither => 2,
+                    _ => 1,
+                };
+                if count < min_count {
+                    return Err(invalid());
+                }
+
+                Ok(EasingFunction::Steps(count, position))
+            }
+            _ => Err(invalid()),
+        }
+    }
 }
 
 impl AnimEasing {
+    pub fn to_easing_fn(self) -> Arc<dyn Fn(f32) -> f32 + Send + Sync> {
+        match self {
+            AnimEasing::Function(EasingFunction::Steps(count, position)) => {
+                Arc::new(steps(count, position))
+            }
+            AnimEasing::Function(EasingFunction::CubicBezier(points)) => {
+                Arc::new(cubic_bezier(&points).unwrap())
+            }
+            named => Arc::new(cubic_bezier(&named.to_points()).unwrap()),
+        }
+    }
+
     /// Converts the easing to a corresponding array of points.
     /// Linear and named easing variants will return predefined control points,
     /// while CubicBezier returns its own array.
-    pub fn to_points(self) -> [f32; 4] {
+    fn to_points(self) -> [f32; 4] {
         match self {
             // Linear
             AnimEasing::Linear => [0.0, 0.0, 1.0, 1.0],
@@ -203,6 +368,10 @@ impl AnimEasing {
 
             // CubicBezier variant returns its own points.
             AnimEasing::CubicBezier(bezier) => bezier,
+            AnimEasing::Function(EasingFunction::CubicBezier(bezier)) => bezier,
+
+            // Steps aren't a curve, so to_easing_fn() handles them separately
+            AnimEasing::Function(EasingFunction::Steps(..)) => [0.0, 0.0, 1.0, 1.0],
         }
     }
 }
@@ -240,7 +409,7 @@ pub fn animate_spiral(
     );
 
     border.active_color.set_transform(&transform);
-    border.inactive_color.set_transform(&transform);
+    border.get_inactive_color().set_transform(&transform);
 }
 
 pub fn animate_fade(
@@ -248,10 +417,17 @@ pub fn animate_fade(
     anim_elapsed: &time::Duration,
     anim_params: &AnimParams,
 ) {
+    // Use the fade_in/fade_out params (if configured) depending on which way we are fading
+    let direction_params = match border.is_active_window {
+        true => border.animations.fade_in.clone(),
+        false => border.animations.fade_out.clone(),
+    };
+    let anim_params = direction_params.as_ref().unwrap_or(anim_params);
+
     // If both are 0, that means the window has been opened for the first time or has been
     // unminimized. If that is the case, only one of the colors should be visible while fading.
     if border.active_color.get_opacity() == Some(0.0)
-        && border.inactive_color.get_opacity() == Some(0.0)
+        && border.get_inactive_color().get_opacity() == Some(0.0)
     {
         // Set fade_progress here so we start from 0 opacity for the visible color
         border.animations.fade_progress = match border.is_active_window {
@@ -262,40 +438,210 @@ pub fn animate_fade(
         border.animations.fade_to_visible = true;
     }
 
-    // Determine which direction we should move fade_progress
-    let direction = match border.is_active_window {
-        true => 1.0,
-        false => -1.0,
-    };
-
-    let delta_x = anim_elapsed.as_secs_f32() * 1000.0 / anim_params.duration * direction;
-    border.animations.fade_progress += delta_x;
+    let frame = advance_fade(
+        border.animations.fade_progress,
+        border.animations.fade_to_visible,
+        border.is_active_window,
+        anim_elapsed,
+        anim_params,
+    );
+    border.animations.fade_progress = frame.progress;
 
     // Check if the fade animation is finished
-    if !(0.0..=1.0).contains(&border.animations.fade_progress) {
-        let final_opacity = border.animations.fade_progress.clamp(0.0, 1.0);
+    if frame.is_finished {
+        // Undo any color blending from below
+        border.active_color.reset_brush_color();
+        border.get_inactive_color().reset_brush_color();
 
-        border.active_color.set_opacity(final_opacity);
-        border.inactive_color.set_opacity(1.0 - final_opacity);
+        border.active_color.set_opacity(frame.active_opacity);
+        border
+            .get_inactive_color()
+            .set_opacity(frame.inactive_opacity);
 
-        border.animations.fade_progress = final_opacity;
         border.animations.fade_to_visible = false;
         border.animations.should_fade = false;
         return;
     }
 
-    let y_coord = anim_params.easing_fn.as_ref()(border.animations.fade_progress);
+    // Crossfading two brushes' opacities effectively blends them in sRGB. For other color spaces, we
+    // instead draw both solid colors with the blended color at full opacity.
+    if border.color_space != ColorSpace::Srgb && !border.animations.fade_to_visible {
+        if let (Some(active), Some(inactive)) = (
+            border.active_color.get_solid_color(),
+            border.get_inactive_color().get_solid_color(),
+        ) {
+            let blended = interpolate_color(&inactive, &active, frame.eased, border.color_space);
+
+            for color in [&border.active_color, border.get_inactive_color()] {
+                color.set_brush_color(&blended);
+                color.set_opacity(1.0);
+            }
+            return;
+        }
+    }
+
+    border.active_color.set_opacity(frame.active_opacity);
+    border
+        .get_inactive_color()
+        .set_opacity(frame.inactive_opacity);
+}
 
-    let (new_active_opacity, new_inactive_opacity) = match border.animations.fade_to_visible {
-        true => match border.is_active_window {
-            true => (y_coord, 0.0),
-            false => (0.0, 1.0 - y_coord),
+// The state of a fade after advancing it by one tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeFrame {
+    // How far the fade is from the inactive color (0.0) to the active color (1.0)
+    pub progress: f32,
+    // The progress after applying the easing function
+    pub eased: f32,
+    pub active_opacity: f32,
+    pub inactive_opacity: f32,
+    pub is_finished: bool,
+}
+
+// Advance a fade by anim_elapsed toward the active color if is_active is set, or toward the inactive
+// color otherwise. This only does the math, so animate_fade() applies the result to the border.
+pub fn advance_fade(
+    progress: f32,
+    fade_to_visible: bool,
+    is_active: bool,
+    anim_elapsed: &time::Duration,
+    anim_params: &AnimParams,
+) -> FadeFrame {
+    let direction = match is_active {
+        true => 1.0,
+        false => -1.0,
+    };
+
+    // A fade without a duration finishes immediately (and would otherwise divide by zero)
+    let delta_x = match anim_params.duration > 0.0 {
+        true => anim_elapsed.as_secs_f32() * 1000.0 / anim_params.duration * direction,
+        false => f32::INFINITY * direction,
+    };
+    let progress = progress + delta_x;
+
+    if !(0.0..=1.0).contains(&progress) {
+        let final_opacity = progress.clamp(0.0, 1.0);
+
+        return FadeFrame {
+            progress: final_opacity,
+            eased: final_opacity,
+            active_opacity: final_opacity,
+            inactive_opacity: 1.0 - final_opacity,
+            is_finished: true,
+        };
+    }
+
+    let eased = anim_params.easing_fn.as_ref()(progress);
+
+    let (active_opacity, inactive_opacity) = match fade_to_visible {
+        true => match is_active {
+            true => (eased, 0.0),
+            false => (0.0, 1.0 - eased),
         },
-        false => (y_coord, 1.0 - y_coord),
+        false => (eased, 1.0 - eased),
+    };
+
+    FadeFrame {
+        progress,
+        eased,
+        active_opacity,
+        inactive_opacity,
+        is_finished: false,
+    }
+}
+
+// How much wider than the border the focus ring gets at its widest, as a multiple of border width
+const FOCUS_RING_SPREAD: f32 = 2.0;
+
+// What the focus ring looks like on the current frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusRingFrame {
+    // The ring's stroke width as a multiple of the border width
+    pub width_scale: f32,
+    pub opacity: f32,
+}
+
+// The ring swells out of the border and settles back into it while fading out. It grows outward
+// from the border's inner edge, into the padding from get_focus_ring_padding.
+pub fn get_focus_ring_frame(eased: f32) -> FocusRingFrame {
+    let eased = eased.clamp(0.0, 1.0);
+
+    FocusRingFrame {
+        width_scale: 1.0 + FOCUS_RING_SPREAD * (PI * eased).sin(),
+        opacity: 1.0 - eased,
+    }
+}
+
+// How much room the border window needs outside of the border's stroke to fit the focus ring at its
+// widest
+pub fn get_focus_ring_padding(border_width: i32) -> i32 {
+    (border_width as f32 * FOCUS_RING_SPREAD).ceil() as i32
+}
+
+// Advance a focus ring by anim_elapsed. Returns None once the ring has finished.
+pub fn advance_focus_ring(
+    progress: f32,
+    anim_elapsed: &time::Duration,
+    anim_params: &AnimParams,
+) -> Option<f32> {
+    let progress = match anim_params.duration > 0.0 {
+        true => progress + anim_elapsed.as_secs_f32() * 1000.0 / anim_params.duration,
+        false => f32::INFINITY,
     };
 
-    border.active_color.set_opacity(new_active_opacity);
-    border.inactive_color.set_opacity(new_inactive_opacity);
+    (progress < 1.0).then_some(progress)
+}
+
+pub fn start_focus_ring(border: &mut WindowBorder) {
+    border.animations.focus_ring_progress = Some(0.0);
+    border.animations.focus_ring = Some(get_focus_ring_frame(0.0));
+}
+
+pub fn stop_focus_ring(border: &mut WindowBorder) {
+    border.animations.focus_ring_progress = None;
+    border.animations.focus_ring = None;
+}
+
+pub fn animate_focus_ring(
+    border: &mut WindowBorder,
+    anim_elapsed: &time::Duration,
+    anim_params: &AnimParams,
+) {
+    let Some(progress) = border.animations.focus_ring_progress else {
+        return;
+    };
+
+    border.animations.focus_ring_progress = advance_focus_ring(progress, anim_elapsed, anim_params);
+    border.animations.focus_ring = border
+        .animations
+        .focus_ring_progress
+        .map(|progress| get_focus_ring_frame(anim_params.easing_fn.as_ref()(progress)));
+}
+
+// Get the amount of time the animations should be progressed by on this tick. With frame_skip, we
+// use the actual elapsed time so animations stay accurate even if ticks arrive late (skipping
+// frames). Without it, we progress by a fixed frame interval so animations stay smooth.
+pub fn get_anim_elapsed(border: &WindowBorder) -> time::Duration {
+    get_anim_elapsed_at(
+        border.last_anim_time,
+        border.animations.clock.now(),
+        border.animations.frame_skip,
+        border.animations.fps,
+    )
+}
+
+pub fn get_anim_elapsed_at(
+    last_anim_time: Option<time::Instant>,
+    now: time::Instant,
+    frame_skip: bool,
+    fps: i32,
+) -> time::Duration {
+    let actual_elapsed = now.saturating_duration_since(last_anim_time.unwrap_or(now));
+
+    match frame_skip {
+        true => actual_elapsed,
+        false => actual_elapsed.min(time::Duration::from_secs_f32(1.0 / fps as f32)),
+    }
 }
 
 pub fn get_current_anims(border: &mut WindowBorder) -> &Vec<AnimParams> {
@@ -306,13 +652,31 @@ pub fn get_current_anims(border: &mut WindowBorder) -> &Vec<AnimParams> {
 }
 
 pub fn set_timer_if_anims_enabled(border: &mut WindowBorder) {
-    if (!border.animations.active.is_empty() || !border.animations.inactive.is_empty())
+    if APP_STATE.are_animations_disabled() {
+        return;
+    }
+
+    // Keyframed colors also need the timer to advance
+    let has_keyframes = border.active_color.has_keyframes()
+        || border.inactive_color.has_keyframes()
+        || border
+            .last_active_color
+            .as_ref()
+            .is_some_and(Color::has_keyframes);
+
+    if (!border.animations.active.is_empty()
+        || !border.animations.inactive.is_empty()
+        || has_keyframes)
         && border.animations.timer.is_none()
     {
         let timer_duration = (1000.0 / border.animations.fps as f32) as u64;
-        border.animations.timer = Some(AnimationTimer::start(border.border_window, timer_duration));
+        border.animations.timer = Some(AnimationTimer::start(
+            border.border_window,
+            timer_duration,
+            border.animations.clock.clone(),
+        ));
 
-        border.last_anim_time = Some(time::Instant::now());
+        border.last_anim_time = Some(border.animations.clock.now());
     }
 }
 
@@ -329,3 +693,218 @@ pub fn update_fade_progress(border: &mut WindowBorder) {
         false => 0.0,
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // A clock that only moves when the test advances it
+    struct ManualClock {
+        start: time::Instant,
+        offset: Mutex<time::Duration>,
+    }
+
+    impl ManualClock {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                start: time::Instant::now(),
+                offset: Mutex::new(time::Duration::ZERO),
+            })
+        }
+
+        fn advance(&self, millis: u64) {
+            *self.offset.lock().unwrap() += time::Duration::from_millis(millis);
+        }
+    }
+
+    impl AnimClock for ManualClock {
+        fn now(&self) -> time::Instant {
+            self.start + *self.offset.lock().unwrap()
+        }
+    }
+
+    fn linear_fade(duration: f32) -> AnimParams {
+        AnimParamsConfig {
+            anim_type: AnimType::Fade,
+            duration: Some(duration),
+            easing: Some(AnimEasing::Linear),
+        }
+        .to_anim_params()
+    }
+
+    // Step the clock by step_ms before each tick and fade toward is_active[i] on the i-th tick,
+    // returning the active opacity after each tick
+    fn fade_trajectory(
+        params: &AnimParams,
+        start_progress: f32,
+        step_ms: u64,
+        is_active: &[bool],
+    ) -> Vec<f32> {
+        let manual_clock = ManualClock::new();
+        let clock = SharedClock(manual_clock.clone());
+
+        let mut last_anim_time = Some(clock.now());
+        let mut progress = start_progress;
+        let mut trajectory = Vec::new();
+
+        for &is_active in is_active {
+            manual_clock.advance(step_ms);
+            let now = clock.now();
+            let anim_elapsed = get_anim_elapsed_at(last_anim_time, now, true, 60);
+            last_anim_time = Some(now);
+
+            let frame = advance_fade(progress, false, is_active, &anim_elapsed, params);
+            progress = frame.progress;
+            trajectory.push((frame.active_opacity * 1000.0).round() / 1000.0);
+        }
+
+        trajectory
+    }
+
+    #[test]
+    fn focus_ring_expands_then_settles() {
+        let start = get_focus_ring_frame(0.0);
+        let middle = get_focus_ring_frame(0.5);
+        let end = get_focus_ring_frame(1.0);
+
+        assert_eq!(start.width_scale, 1.0);
+        assert_eq!(start.opacity, 1.0);
+        assert!((middle.width_scale - (1.0 + FOCUS_RING_SPREAD)).abs() < 1e-5);
+        assert!((end.width_scale - 1.0).abs() < 1e-5);
+        assert_eq!(end.opacity, 0.0);
+    }
+
+    #[test]
+    fn focus_ring_finishes_after_its_duration() {
+        let params = AnimParamsConfig {
+            anim_type: AnimType::FocusRing,
+            duration: None,
+            easing: None,
+        }
+        .to_anim_params();
+        let step = time::Duration::from_millis(100);
+
+        let mut progress = Some(0.0);
+        let mut ticks = 0;
+        while let Some(current) = progress {
+            progress = advance_focus_ring(current, &step, &params);
+            ticks += 1;
+        }
+        assert_eq!(ticks, 4);
+
+        // Like fades, a ring without a duration finishes immediately
+        let params = AnimParams {
+            duration: 0.0,
+            ..params
+        };
+        assert_eq!(
+            advance_focus_ring(0.0, &time::Duration::ZERO, &params),
+            None
+        );
+    }
+
+    #[test]
+    fn fade_follows_the_clock() {
+        let trajectory = fade_trajectory(&linear_fade(200.0), 0.0, 50, &[true; 5]);
+        assert_eq!(trajectory, vec![0.25, 0.5, 0.75, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn fade_reverses_on_rapid_focus_flips() {
+        let is_active = [true, true, false, true, false, false, false];
+        let trajectory = fade_trajectory(&linear_fade(200.0), 0.0, 50, &is_active);
+        assert_eq!(trajectory, vec![0.25, 0.5, 0.25, 0.5, 0.25, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn zero_duration_fade_finishes_immediately() {
+        let params = linear_fade(0.0);
+
+        for elapsed in [time::Duration::ZERO, time::Duration::from_millis(16)] {
+            let frame = advance_fade(0.5, false, true, &elapsed, &params);
+            assert!(frame.is_finished);
+            assert_eq!(frame.active_opacity, 1.0);
+            assert_eq!(frame.inactive_opacity, 0.0);
+
+            let frame = advance_fade(0.5, false, false, &elapsed, &params);
+            assert!(frame.is_finished);
+            assert_eq!(frame.active_opacity, 0.0);
+            assert_eq!(frame.inactive_opacity, 1.0);
+        }
+    }
+
+    #[test]
+    fn fade_to_visible_only_shows_the_target_color() {
+        let elapsed = time::Duration::from_millis(100);
+
+        let frame = advance_fade(0.0, true, true, &elapsed, &linear_fade(200.0));
+        assert_eq!((frame.active_opacity, frame.inactive_opacity), (0.5, 0.0));
+
+        let frame = advance_fade(1.0, true, false, &elapsed, &linear_fade(200.0));
+        assert_eq!((frame.active_opacity, frame.inactive_opacity), (0.0, 0.5));
+    }
+
+    #[test]
+    fn anim_elapsed_is_capped_without_frame_skip() {
+        let manual_clock = ManualClock::new();
+        let last_anim_time = Some(manual_clock.now());
+        manual_clock.advance(200);
+        let now = manual_clock.now();
+
+        assert_eq!(
+            get_anim_elapsed_at(last_anim_time, now, true, 8),
+            time::Duration::from_millis(200)
+        );
+        assert_eq!(
+            get_anim_elapsed_at(last_anim_time, now, false, 8),
+            time::Duration::from_millis(125)
+        );
+        assert_eq!(
+            get_anim_elapsed_at(None, now, true, 60),
+            time::Duration::ZERO
+        );
+    }
+
+    fn parse(value: &str) -> Result<EasingFunction, String> {
+        EasingFunction::try_from(value.to_string())
+    }
+
+    #[test]
+    fn parses_cubic_bezier_strings() {
+        assert_eq!(
+            parse("cubic-bezier(.17,.67,.83,.67)"),
+            Ok(EasingFunction::CubicBezier([0.17, 0.67, 0.83, 0.67]))
+        );
+        assert_eq!(
+            parse(" cubic-bezier(0.25, 0.1, 0.25, 1.0) "),
+            Ok(EasingFunction::CubicBezier([0.25, 0.1, 0.25, 1.0]))
+        );
+    }
+
+    #[test]
+    fn parses_steps_with_and_without_position() {
+        assert_eq!(
+            parse("steps(4)"),
+            Ok(EasingFunction::Steps(4, StepPosition::End))
+        );
+        assert_eq!(
+            parse("steps(3, start)"),
+            Ok(EasingFunction::Steps(3, StepPosition::Start))
+        );
+        assert_eq!(
+            parse("steps(2, jump-none)"),
+            Ok(EasingFunction::Steps(2, StepPosition::Neither))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_easing_functions() {
+        assert!(parse("cubic-bezier(1.5, 0, 0.5, 1)").is_err());
+        assert!(parse("cubic-bezier(0.5, 0, 0.5)").is_err());
+        assert!(parse("steps(0)").is_err());
+        assert!(parse("steps(1, jump-none)").is_err());
+        assert!(parse("steps(4, sideways)").is_err());
+        assert!(parse("bounce(2)").is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::Context;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+use std::time;
+use windows::core::Interface;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::Media::Audio::Endpoints::IAudioMeterInformation;
+use windows::Win32::Media::Audio::{
+    eMultimedia, eRender, AudioSessionStateActive, IAudioSessionControl2, IAudioSessionManager2,
+    IMMDeviceEnumerator, MMDeviceEnumerator,
+};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED,
+};
+
+use crate::utils::{
+    get_process_path, get_process_path_by_id, post_message_w, AppMessage, LogIfErr,
+};
+use crate::APP_STATE;
+
+// Audio sessions don't notify us when their peak level changes, so we poll them instead
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(500);
+
+// Watch the default audio device's sessions and tell each border whether its tracking window's
+// process is currently playing audio (see 'audio_indicator' in the config). Browsers like Chrome,
+// Edge, and Firefox play audio from a child process, so sessions are matched by image path rather
+// than by process id.
+pub fn start_audio_watcher() {
+    let _ = thread::spawn(|| {
+        if let Err(err) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.ok() {
+            error!("could not initialize com for audio watcher: {err}");
+            return;
+        }
+
+        let mut session_manager: Option<IAudioSessionManager2> = None;
+
+        // Tracking windows mapped to whether we last told their border audio is playing
+        let mut border_states: HashMap<isize, bool> = HashMap::new();
+        // Tracking windows mapped to their process's image path (None if it couldn't be queried)
+        let mut process_paths: HashMap<isize, Option<String>> = HashMap::new();
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            if !is_audio_indicator_enabled() {
+                // Release the session manager so we aren't holding onto the audio device
+                session_manager = None;
+                update_border_states(&mut border_states, &mut process_paths, &HashSet::new());
+                continue;
+            }
+
+            if session_manager.is_none() {
+                session_manager = create_session_manager()
+                    .context("could not create audio session manager")
+                    .inspect_err(|err| error!("{err:#}"))
+                    .ok();
+            }
+
+            let Some(manager) = session_manager.as_ref() else {
+                continue;
+            };
+
+            match get_playing_processes(manager) {
+                Ok(playing_paths) => {
+                    update_border_states(&mut border_states, &mut process_paths, &playing_paths)
+                }
+                Err(err) => {
+                    // The audio device may have been removed, so recreate it on the next poll
+                    error!("could not retrieve audio sessions: {err:#}");
+                    session_manager = None;
+                }
+            }
+        }
+    });
+}
+
+fn is_audio_indicator_enabled() -> bool {
+    let config = APP_STATE.config.load();
+
+    config.global.audio_indicator
+        || config
+            .window_rules
+            .iter()
+            .any(|rule| rule.audio_indicator == Some(true))
+}
+
+fn create_session_manager() -> anyhow::Result<IAudioSessionManager2> {
+    unsafe {
+        let device_enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let device = device_enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+
+        Ok(device.Activate(CLSCTX_ALL, None)?)
+    }
+}
+
+// Return the lowercased image paths of processes with an active audio session that is actually
+// producing sound
+fn get_playing_processes(manager: &IAudioSessionManager2) -> anyhow::Result<HashSet<String>> {
+    let mut playing_paths = HashSet::new();
+
+    unsafe {
+        let session_enumerator = manager.GetSessionEnumerator()?;
+
+        for i in 0..session_enumerator.GetCount()? {
+            let session = session_enumerator.GetSession(i)?;
+
+            if session.GetState()? != AudioSessionStateActive {
+                continue;
+            }
+
+            // Sessions can stay active while silent (e.g. a paused video), so check the meter too
+            let peak_value = session.cast::<IAudioMeterInformation>()?.GetPeakValue()?;
+            if peak_value > 0.0 {
+                let process_id = session.cast::<IAudioSessionControl2>()?.GetProcessId()?;
+                match get_process_path_by_id(process_id) {
+                    Ok(path) => {
+                        playing_paths.insert(path.to_lowercase());
+                    }
+                    Err(err) => debug!("could not get path of audio process: {err:#}"),
+                }
+            }
+        }
+    }
+
+    Ok(playing_paths)
+}
+
+fn update_border_states(
+    border_states: &mut HashMap<isize, bool>,
+    process_paths: &mut HashMap<isize, Option<String>>,
+    playing_paths: &HashSet<String>,
+) {
+    let borders = APP_STATE.borders.lock().unwrap().clone();
+
+    // Forget about borders that no longer exist
+    border_states.retain(|tracking_window, _| borders.contains_key(tracking_window));
+    process_paths.retain(|tracking_window, _| borders.contains_key(tracking_window));
+
+    for (tracking_window, border_window) in borders {
+        // Skip querying the window's process if nothing is playing anyway
+        let is_playing = !playing_paths.is_empty()
+            && process_paths
+                .entry(tracking_window)
+                .or_insert_with(|| {
+                    get_process_path(HWND(tracking_window as _))
+                        .map(|path| path.to_lowercase())
+                        .ok()
+                })
+                .as_ref()
+                .is_some_and(|path| playing_paths.contains(path));
+        let was_playing = border_states
+            .insert(tracking_window, is_playing)
+            .unwrap_or(false);
+
+        if is_playing != was_playing {
+            post_message_w(
+                HWND(border_window as _),
+                AppMessage::SetAudioPlaying.into(),
+                WPARAM(is_playing as usize),
+                LPARAM(0),
+            )
+            .context("could not post audio state to border")
+            .log_if_err();
+        }
+    }
+}
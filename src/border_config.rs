@@ -1,40 +1,178 @@
 use crate::animations::AnimationsConfig;
-use crate::colors::ColorConfig;
-use crate::utils::{get_adjusted_radius, get_window_corner_preference, LogIfErr};
+use crate::color_math::ColorSpace;
+use crate::colors::{ColorBlindMode, ColorConfig};
+use crate::conflicts::{clear_conflicting_modules, AlwaysOnTopFrame, ConflictMitigation};
+use crate::event_hook::ExtraUpdateEvent;
+use crate::game_mode::GameModeConfig;
+use crate::localization::Language;
+use crate::night_light::NightLightConfig;
+use crate::on_air::OnAirConfig;
+use crate::sys_tray_icon::{self, TrayConfig};
+use crate::utils::{
+    get_adjusted_radius, get_window_corner_preference, get_window_region_radius, LogIfErr,
+};
+use crate::window_border::BorderExStyle;
 use crate::{reload_borders, APP_STATE};
 use anyhow::{anyhow, Context};
 use dirs::home_dir;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::env;
 use std::fs::{self, DirBuilder};
 use std::os::windows::ffi::OsStrExt;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
 use std::{iter, ptr, slice, thread, time};
 use windows::core::PCWSTR;
-use windows::Win32::Foundation::{CloseHandle, FALSE, HANDLE, HWND};
+use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
 use windows::Win32::Graphics::Dwm::{
     DWMWCP_DEFAULT, DWMWCP_DONOTROUND, DWMWCP_ROUND, DWMWCP_ROUNDSMALL,
 };
 use windows::Win32::Storage::FileSystem::{
-    CreateFileW, ReadDirectoryChangesW, FILE_FLAG_BACKUP_SEMANTICS, FILE_LIST_DIRECTORY,
-    FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ,
-    FILE_SHARE_WRITE, OPEN_EXISTING,
+    CreateFileW, ReadDirectoryChangesW, FILE_ACTION_ADDED, FILE_ACTION_MODIFIED,
+    FILE_ACTION_RENAMED_NEW_NAME, FILE_FLAG_BACKUP_SEMANTICS, FILE_LIST_DIRECTORY,
+    FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_INFORMATION,
+    FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
 };
 use windows::Win32::System::IO::CancelIoEx;
 
 const DEFAULT_CONFIG: &str = include_str!("resources/config.yaml");
+// If this file is next to the executable, the config is kept there as well (see get_dir())
+const PORTABLE_MARKER: &str = "portable.txt";
+// Set when create() generates the default config, i.e. on first run (see take_default_generated())
+static WAS_DEFAULT_GENERATED: AtomicBool = AtomicBool::new(false);
+
+// Expand a leading '~' to the home directory, and '%VAR%', '${VAR}', or '$VAR' to the value of the
+// environment variable. Unknown variables are left as written.
+pub fn expand_vars(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    if let Some(stripped) = value.strip_prefix('~') {
+        if stripped.is_empty() || stripped.starts_with(['/', '\\']) {
+            if let Some(home_dir) = home_dir() {
+                expanded.push_str(&home_dir.to_string_lossy());
+                rest = stripped;
+            }
+        }
+    }
+
+    while let Some(start) = rest.find(['%', '$']) {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        // Find the variable's name and the length of the whole reference (e.g. '%VAR%')
+        let reference = if let Some(after) = rest.strip_prefix('%') {
+            after.find('%').map(|end| (&after[..end], end + 2))
+        } else if let Some(after) = rest.strip_prefix("${") {
+            after.find('}').map(|end| (&after[..end], end + 3))
+        } else {
+            let after = &rest[1..];
+            let end = after
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(after.len());
+            (end > 0).then(|| (&after[..end], end + 1))
+        };
+
+        let Some((name, len)) = reference else {
+            // A lone or unterminated '%' or '$', so keep the character and move on
+            expanded.push_str(&rest[..1]);
+            rest = &rest[1..];
+            continue;
+        };
+
+        match env::var(name) {
+            Ok(val) => expanded.push_str(&val),
+            Err(_) => expanded.push_str(&rest[..len]),
+        }
+        rest = &rest[len..];
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+// Supported config file formats, in the order we look for them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    const ALL: [ConfigFormat; 3] = [ConfigFormat::Yaml, ConfigFormat::Toml, ConfigFormat::Json];
+
+    fn file_name(&self) -> &'static str {
+        match self {
+            ConfigFormat::Yaml => "config.yaml",
+            ConfigFormat::Toml => "config.toml",
+            ConfigFormat::Json => "config.json",
+        }
+    }
+
+    fn parse(&self, contents: &str) -> anyhow::Result<Config> {
+        match self {
+            ConfigFormat::Yaml => serde_yml::from_str(contents).map_err(anyhow::Error::new),
+            ConfigFormat::Toml => toml::from_str(contents).map_err(anyhow::Error::new),
+            ConfigFormat::Json => serde_json::from_str(contents).map_err(anyhow::Error::new),
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     #[serde(default)]
     pub watch_config_changes: bool,
+    #[serde(default)]
+    pub lazy_startup: bool,
+    #[serde(default)]
+    pub recreate_stuck_borders: bool,
+    #[serde(default)]
+    pub check_updates: bool,
+    // Start with animations turned off. They can also be toggled at runtime from the tray menu.
+    #[serde(default)]
+    pub disable_animations: bool,
+    // Draw render stats (fps, frame time, etc.) inside every border. Also toggleable from the tray.
+    #[serde(default)]
+    pub debug_overlay: bool,
+    // Remap border colors for a type of color blindness. Also toggleable from the tray.
+    #[serde(default)]
+    pub color_blind_mode: ColorBlindMode,
+    #[serde(default)]
+    pub language: Option<Language>,
+    #[serde(default)]
+    pub game_mode: GameModeConfig,
+    #[serde(default)]
+    pub on_air: OnAirConfig,
+    #[serde(default)]
+    pub night_light: NightLightConfig,
+    #[serde(default)]
+    pub tray: TrayConfig,
+    #[serde(default)]
+    pub editor: Option<String>,
+    #[serde(default)]
+    pub exclude_processes: Vec<String>,
+    #[serde(default)]
+    pub exclude_classes: Vec<String>,
+    // Additional WinEvents that should update borders, for apps with nonstandard event behavior
+    #[serde(default)]
+    pub extra_update_events: Vec<ExtraUpdateEvent>,
+    // Replaces the extended window styles that border windows are created with
+    #[serde(default)]
+    pub border_window_ex_styles: Option<Vec<BorderExStyle>>,
     #[serde(default = "serde_default_global")]
     pub global: Global,
     #[serde(default)]
     pub window_rules: Vec<WindowRule>,
+    // Built from window_rules after loading; see WindowRulePlan
+    #[serde(skip)]
+    pub window_rule_plan: WindowRulePlan,
 }
 
-// Show borders even if the config.yaml is completely empty
+// Show borders even if the config file is completely empty
 // NOTE: this is just for serde and is intentionally kept separate from the Default trait
 // because I still want the width and offset zeroed out when I call Config::default()
 fn serde_default_global() -> Global {
@@ -53,19 +191,52 @@ pub struct Global {
     #[serde(default = "serde_default_i32::<-1>")]
     pub border_offset: i32,
     #[serde(default)]
+    pub width_scale: Option<WidthScale>,
+    #[serde(default)]
     pub border_radius: RadiusConfig,
+    #[serde(alias = "placement")]
+    #[serde(default)]
+    pub border_placement: BorderPlacement,
+    #[serde(default)]
+    pub pixel_snap: bool,
+    #[serde(default)]
+    pub min_window_size: MinWindowSize,
+    #[serde(default)]
+    pub only_on_focused_monitor: bool,
+    #[serde(default)]
+    pub pause_when_occluded: bool,
     #[serde(default)]
     pub active_color: ColorConfig,
     #[serde(default)]
     pub inactive_color: ColorConfig,
     #[serde(default)]
+    pub last_active_color: Option<ColorConfig>,
+    #[serde(default)]
+    pub color_space: ColorSpace,
+    #[serde(default)]
+    pub color_management: bool,
+    #[serde(default)]
     pub animations: AnimationsConfig,
+    #[serde(default)]
+    pub tint_native_border: bool,
+    #[serde(default)]
+    pub audio_indicator: bool,
+    #[serde(default)]
+    pub conflict_mitigation: ConflictMitigation,
+    #[serde(default)]
+    pub always_on_top_frame: AlwaysOnTopFrame,
     #[serde(alias = "init_delay")]
     #[serde(default = "serde_default_u64::<250>")]
     pub initialize_delay: u64, // Adjust delay when creating new windows/borders
     #[serde(alias = "restore_delay")]
     #[serde(default = "serde_default_u64::<200>")]
     pub unminimize_delay: u64, // Adjust delay when restoring minimized windows
+    #[serde(default)]
+    pub suppress_transient_ms: u64,
+}
+
+pub fn serde_default_bool<const V: bool>() -> bool {
+    V
 }
 
 pub fn serde_default_u64<const V: u64>() -> u64 {
@@ -90,21 +261,143 @@ pub struct WindowRule {
     pub strategy: Option<MatchStrategy>,
     pub border_width: Option<f32>,
     pub border_offset: Option<i32>,
+    pub width_scale: Option<WidthScale>,
     pub border_radius: Option<RadiusConfig>,
+    #[serde(alias = "placement")]
+    pub border_placement: Option<BorderPlacement>,
+    pub pixel_snap: Option<bool>,
+    pub min_window_size: Option<MinWindowSize>,
     pub active_color: Option<ColorConfig>,
     pub inactive_color: Option<ColorConfig>,
+    pub last_active_color: Option<ColorConfig>,
+    pub color_management: Option<bool>,
     pub enabled: Option<EnableMode>,
+    #[serde(default, deserialize_with = "deserialize_rule_animations")]
     pub animations: Option<AnimationsConfig>,
+    pub tint_native_border: Option<bool>,
+    pub audio_indicator: Option<bool>,
+    // If true, draw the border around the work area of the window's monitor instead of around the
+    // window itself. Meant for the desktop window (Progman), e.g. as a recording indicator.
+    pub ambient_frame: Option<bool>,
+    pub conflict_mitigation: Option<ConflictMitigation>,
+    pub always_on_top_frame: Option<AlwaysOnTopFrame>,
     #[serde(alias = "init_delay")]
     pub initialize_delay: Option<u64>,
     #[serde(alias = "restore_delay")]
     pub unminimize_delay: Option<u64>,
+    pub suppress_transient_ms: Option<u64>,
+    // If true, keep matching later rules after this one, layering them underneath it
+    pub merge: Option<bool>,
+    // If true, stop matching after this rule even if 'merge' is set
+    pub stop: Option<bool>,
+}
+
+// Window rules accept 'animations: False' as a shorthand for an animations config without any
+// animations, while 'animations: True' just falls back to the global animations
+fn deserialize_rule_animations<'de, D>(
+    deserializer: D,
+) -> Result<Option<AnimationsConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RuleAnimations {
+        Bool(bool),
+        Config(AnimationsConfig),
+    }
+
+    Ok(match RuleAnimations::deserialize(deserializer)? {
+        RuleAnimations::Bool(false) => Some(AnimationsConfig::disabled()),
+        RuleAnimations::Bool(true) => None,
+        RuleAnimations::Config(animations_config) => Some(animations_config),
+    })
+}
+
+impl WindowRule {
+    // Fill in any fields left unspecified by this rule using the other rule
+    pub fn merge_from(&mut self, other: &WindowRule) {
+        // Destructure without '..' so that new fields fail to compile until they're handled here
+        let WindowRule {
+            // Matching and match flow only apply to the rule they're written in
+            kind: _,
+            name: _,
+            strategy: _,
+            merge: _,
+            stop: _,
+            border_width,
+            border_offset,
+            width_scale,
+            border_radius,
+            border_placement,
+            pixel_snap,
+            min_window_size,
+            active_color,
+            inactive_color,
+            last_active_color,
+            color_management,
+            enabled,
+            animations,
+            tint_native_border,
+            audio_indicator,
+            ambient_frame,
+            conflict_mitigation,
+            always_on_top_frame,
+            initialize_delay,
+            unminimize_delay,
+            suppress_transient_ms,
+        } = self;
+
+        macro_rules! fill_unset {
+            ($($field:ident),*) => {
+                $(
+                    if $field.is_none() {
+                        *$field = other.$field.clone();
+                    }
+                )*
+            };
+        }
+
+        fill_unset!(
+            border_width,
+            border_offset,
+            width_scale,
+            border_radius,
+            border_placement,
+            pixel_snap,
+            min_window_size,
+            active_color,
+            inactive_color,
+            last_active_color,
+            color_management,
+            enabled,
+            animations,
+            tint_native_border,
+            audio_indicator,
+            ambient_frame,
+            conflict_mitigation,
+            always_on_top_frame,
+            initialize_delay,
+            unminimize_delay,
+            suppress_transient_ms
+        );
+    }
+
+    // Whether matching should continue to later rules after this one matches
+    pub fn is_fallthrough(&self) -> bool {
+        self.merge == Some(true) && self.stop != Some(true)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum MatchKind {
     Title,
     Class,
+    Process,
+    // The full path of the process's executable
+    Path,
+    // The full command line the process was started with, including its executable
+    CommandLine,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -114,6 +407,57 @@ pub enum MatchStrategy {
     Regex,
 }
 
+// Pre-analyzed window rules, so get_window_rule() can skip Win32 calls and string allocations for
+// window properties that no rule actually matches on
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WindowRulePlan {
+    pub needs_title: bool,
+    pub needs_class: bool,
+    pub needs_process: bool,
+    pub needs_path: bool,
+    pub needs_command_line: bool,
+    // Set if every rule matches a process name exactly. Maps each lowercase process name to the
+    // index of the first rule that matches it.
+    pub process_lookup: Option<HashMap<String, usize>>,
+}
+
+impl WindowRulePlan {
+    pub fn new(window_rules: &[WindowRule]) -> Self {
+        let needs_kind = |kind: MatchKind| {
+            window_rules
+                .iter()
+                .any(|rule| rule.kind.as_ref() == Some(&kind))
+        };
+
+        // Merged rules can match more than once per window, so they can't use the lookup table
+        let is_process_only = window_rules.iter().all(|rule| {
+            rule.kind == Some(MatchKind::Process)
+                && rule.name.is_some()
+                && matches!(rule.strategy, Some(MatchStrategy::Equals) | None)
+                && !rule.is_fallthrough()
+        });
+
+        let process_lookup = is_process_only.then(|| {
+            let mut lookup = HashMap::new();
+            for (index, rule) in window_rules.iter().enumerate() {
+                if let Some(name) = &rule.name {
+                    lookup.entry(name.to_lowercase()).or_insert(index);
+                }
+            }
+            lookup
+        });
+
+        Self {
+            needs_title: needs_kind(MatchKind::Title),
+            needs_class: needs_kind(MatchKind::Class),
+            needs_process: needs_kind(MatchKind::Process),
+            needs_path: needs_kind(MatchKind::Path),
+            needs_command_line: needs_kind(MatchKind::CommandLine),
+            process_lookup,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub enum RadiusConfig {
     #[default]
@@ -121,31 +465,115 @@ pub enum RadiusConfig {
     Square,
     Round,
     RoundSmall,
+    Region,
     #[serde(untagged)]
     Custom(f32),
 }
 
 impl RadiusConfig {
-    pub fn to_radius(&self, border_width: i32, dpi: f32, tracking_window: HWND) -> f32 {
+    pub fn to_radius(
+        &self,
+        border_width: i32,
+        border_placement: BorderPlacement,
+        dpi: f32,
+        tracking_window: HWND,
+    ) -> f32 {
+        // Distance from the tracking window's edge to the center of the border's stroke
+        let stroke_center = border_placement.get_stroke_center(border_width);
+
         match self {
             // We also check Custom(-1.0) for legacy reasons (don't wanna break anyone's old config)
             RadiusConfig::Auto | RadiusConfig::Custom(-1.0) => {
                 match get_window_corner_preference(tracking_window) {
                     // TODO check if the user is running Windows 11 or 10
-                    DWMWCP_DEFAULT => get_adjusted_radius(8.0, dpi, border_width),
+                    DWMWCP_DEFAULT => get_adjusted_radius(8.0, dpi, stroke_center),
                     DWMWCP_DONOTROUND => 0.0,
-                    DWMWCP_ROUND => get_adjusted_radius(8.0, dpi, border_width),
-                    DWMWCP_ROUNDSMALL => get_adjusted_radius(4.0, dpi, border_width),
+                    DWMWCP_ROUND => get_adjusted_radius(8.0, dpi, stroke_center),
+                    DWMWCP_ROUNDSMALL => get_adjusted_radius(4.0, dpi, stroke_center),
                     _ => 0.0,
                 }
             }
             RadiusConfig::Square => 0.0,
-            RadiusConfig::Round => get_adjusted_radius(8.0, dpi, border_width),
-            RadiusConfig::RoundSmall => get_adjusted_radius(4.0, dpi, border_width),
+            RadiusConfig::Round => get_adjusted_radius(8.0, dpi, stroke_center),
+            RadiusConfig::RoundSmall => get_adjusted_radius(4.0, dpi, stroke_center),
+            // The region is in device pixels, so the radius doesn't need to be scaled by the dpi
+            RadiusConfig::Region => match get_window_region_radius(tracking_window) {
+                Some(radius) => get_adjusted_radius(radius, 96.0, stroke_center),
+                None => RadiusConfig::Auto.to_radius(
+                    border_width,
+                    border_placement,
+                    dpi,
+                    tracking_window,
+                ),
+            },
             RadiusConfig::Custom(radius) => radius * dpi / 96.0,
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum BorderPlacement {
+    #[default]
+    Outside,
+    Inside,
+    Center,
+}
+
+impl BorderPlacement {
+    // Get how much the window rect must be expanded on each side to make room for the border
+    pub fn get_rect_expansion(&self, border_width: i32) -> i32 {
+        match self {
+            BorderPlacement::Outside => border_width,
+            BorderPlacement::Center => (border_width + 1) / 2,
+            BorderPlacement::Inside => 0,
+        }
+    }
+
+    // Get the distance from the tracking window's edge to the center of the border's stroke.
+    // Positive values are outside the window and negative values are inside.
+    pub fn get_stroke_center(&self, border_width: i32) -> f32 {
+        match self {
+            BorderPlacement::Outside => border_width as f32 / 2.0,
+            BorderPlacement::Center => 0.0,
+            BorderPlacement::Inside => -(border_width as f32) / 2.0,
+        }
+    }
+}
+
+// Scale the border width with the window's size. The configured border width is used as-is for
+// windows whose larger dimension equals 'reference' (in pixels at 100% scaling).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WidthScale {
+    pub min: f32,
+    pub max: f32,
+    #[serde(default = "serde_default_f32::<1920>")]
+    pub reference: f32,
+}
+
+impl WidthScale {
+    pub fn scale_width(&self, border_width: f32, window_size: f32) -> f32 {
+        if self.reference <= 0.0 {
+            return border_width;
+        }
+
+        // Not using clamp() here because it panics if min > max
+        (border_width * window_size / self.reference)
+            .max(self.min)
+            .min(self.max)
+    }
+}
+
+// Borders are hidden for windows smaller than this (e.g. flyouts and tooltips)
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MinWindowSize {
+    #[serde(default)]
+    pub width: i32,
+    #[serde(default)]
+    pub height: i32,
+}
+
 #[derive(Debug, Clone, Default, Deserialize, PartialEq)]
 pub enum EnableMode {
     #[default]
@@ -154,26 +582,170 @@ pub enum EnableMode {
     Bool(bool),
 }
 
+fn validate_border_width(border_width: f32) -> anyhow::Result<()> {
+    match border_width.is_finite() && border_width >= 0.0 {
+        true => Ok(()),
+        false => Err(anyhow!("invalid border_width: {border_width}")),
+    }
+}
+
+fn validate_animations(animations_config: &AnimationsConfig) -> anyhow::Result<()> {
+    match animations_config.fps > 0 {
+        true => Ok(()),
+        false => Err(anyhow!("invalid animation fps: {}", animations_config.fps)),
+    }
+}
+
+fn validate_window_rule(rule: &WindowRule) -> anyhow::Result<()> {
+    if rule.kind.is_none() || rule.name.is_none() {
+        return Err(anyhow!("window rules must have both 'match' and 'name'"));
+    }
+
+    if let Some(border_width) = rule.border_width {
+        validate_border_width(border_width)?;
+    }
+
+    let color_configs = [
+        &rule.active_color,
+        &rule.inactive_color,
+        &rule.last_active_color,
+    ];
+    for color_config in color_configs.into_iter().flatten() {
+        validate_color_config(color_config)?;
+    }
+
+    if let Some(ref animations_config) = rule.animations {
+        validate_animations(animations_config)?;
+    }
+
+    Ok(())
+}
+
+fn validate_color_config(color_config: &ColorConfig) -> anyhow::Result<()> {
+    match color_config {
+        ColorConfig::GradientConfig(gradient_config) => {
+            if gradient_config.colors.len() < 2 {
+                return Err(anyhow!("gradients must have at least 2 colors"));
+            }
+        }
+        ColorConfig::RadialGradientConfig(radial_config) => {
+            if radial_config.colors.len() < 2 {
+                return Err(anyhow!("gradients must have at least 2 colors"));
+            }
+            if !radial_config.radius.is_finite() || radial_config.radius <= 0.0 {
+                return Err(anyhow!("invalid gradient radius: {}", radial_config.radius));
+            }
+        }
+        ColorConfig::ConicGradientConfig(conic_config) => {
+            if conic_config.colors.len() < 2 {
+                return Err(anyhow!("gradients must have at least 2 colors"));
+            }
+        }
+        ColorConfig::ThemedConfig(themed_config) => {
+            validate_color_config(&themed_config.light)?;
+            validate_color_config(&themed_config.dark)?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
 impl Config {
     pub fn create() -> anyhow::Result<Self> {
-        let config_dir = Self::get_dir()?;
-        let config_path = config_dir.join("config.yaml");
+        let (contents, format) = match Self::find_path_and_format()? {
+            Some((config_path, format)) => {
+                let contents = fs::read_to_string(&config_path)
+                    .context(format!("could not read {}", config_path.display()))?;
+                (contents, format)
+            }
+            // We can't write anything in read-only mode, so just use the default config as is
+            None if Self::is_read_only() => {
+                info!("no config file found in read-only mode; using the default config");
+                (DEFAULT_CONFIG.to_string(), ConfigFormat::Yaml)
+            }
+            None => {
+                // If no config file exists in any format, create the default config.yaml
+                let config_dir = Self::get_dir()?;
+                let config_path = config_dir.join(ConfigFormat::Yaml.file_name());
+                fs::write(&config_path, DEFAULT_CONFIG.as_bytes())
+                    .context("could not create default config.yaml")?;
 
-        // If the config.yaml does not exist, try to create it
-        if !fs::exists(&config_path).context("could not check if config path exists")? {
-            let default_contents = DEFAULT_CONFIG.as_bytes();
-            fs::write(&config_path, default_contents)
-                .context("could not create default config.yaml")?;
+                info!("generating default config in {}", config_dir.display());
+                WAS_DEFAULT_GENERATED.store(true, Ordering::SeqCst);
+                (DEFAULT_CONFIG.to_string(), ConfigFormat::Yaml)
+            }
+        };
+
+        let mut config = format.parse(&contents)?;
+        config.remove_invalid_values();
+        config.window_rule_plan = WindowRulePlan::new(&config.window_rules);
+
+        // Expand environment variables and '~' in values that hold paths
+        config.editor = config.editor.as_deref().map(expand_vars);
+
+        // Exclusions are matched case-insensitively, so lowercase them once here
+        config
+            .exclude_processes
+            .iter_mut()
+            .chain(config.exclude_classes.iter_mut())
+            .for_each(|name| *name = name.to_lowercase());
+
+        Ok(config)
+    }
+
+    // Catch values that deserialize fine but can't be used, so they're reported when loading the
+    // config rather than misbehaving later on. Invalid global values fall back to their defaults
+    // and invalid window rules are skipped, so one mistake doesn't throw out the whole config.
+    fn remove_invalid_values(&mut self) {
+        let global = &mut self.global;
 
-            info!("generating default config in {}", config_dir.display());
+        if let Err(err) = validate_border_width(global.border_width) {
+            error!("global has an {err}; using the default");
+            global.border_width = serde_default_f32::<4>();
+        }
+        for color_config in [&mut global.active_color, &mut global.inactive_color] {
+            if let Err(err) = validate_color_config(color_config) {
+                error!("global has an invalid color ({err}); using the default");
+                *color_config = ColorConfig::default();
+            }
+        }
+        if let Some(Err(err)) = global.last_active_color.as_ref().map(validate_color_config) {
+            error!("global has an invalid last_active_color ({err}); ignoring it");
+            global.last_active_color = None;
+        }
+        if let Err(err) = validate_animations(&global.animations) {
+            error!("global has an {err}; using the default");
+            global.animations.fps = serde_default_i32::<60>();
         }
 
-        let contents = fs::read_to_string(&config_path).context("could not read config.yaml")?;
+        for color_config in [
+            &mut self.on_air.active_color,
+            &mut self.on_air.inactive_color,
+        ] {
+            if let Some(Err(err)) = color_config.as_ref().map(validate_color_config) {
+                error!("on_air has an invalid color ({err}); ignoring it");
+                *color_config = None;
+            }
+        }
 
-        serde_yml::from_str(&contents).map_err(anyhow::Error::new)
+        let mut index = 0;
+        self.window_rules.retain(|rule| {
+            let result = validate_window_rule(rule);
+            if let Err(ref err) = result {
+                error!("skipping window rule {index}: {err}");
+            }
+            index += 1;
+            result.is_ok()
+        });
     }
 
     pub fn get_dir() -> anyhow::Result<PathBuf> {
+        // In portable mode, everything lives next to the executable instead
+        if let Some(portable_dir) = Self::get_portable_dir() {
+            return Ok(portable_dir);
+        }
+
         let Some(home_dir) = home_dir() else {
             return Err(anyhow!("could not find home directory!"));
         };
@@ -191,14 +763,104 @@ impl Config {
         Ok(config_dir)
     }
 
+    // Returns true once if the default config was generated since the last call
+    pub fn take_default_generated() -> bool {
+        WAS_DEFAULT_GENERATED.swap(false, Ordering::SeqCst)
+    }
+
+    // Read-only mode is used if the config directory can't be created (e.g. on locked-down
+    // machines). The config is then read from get_read_only_dirs() and nothing is written to disk.
+    pub fn is_read_only() -> bool {
+        static IS_READ_ONLY: LazyLock<bool> = LazyLock::new(|| Config::get_dir().is_err());
+        *IS_READ_ONLY
+    }
+
+    // Directories searched for a config file in read-only mode, in order of precedence
+    fn get_read_only_dirs() -> Vec<PathBuf> {
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|exe_path| exe_path.parent().map(|exe_dir| exe_dir.to_path_buf()));
+        let program_data_dir =
+            env::var_os("PROGRAMDATA").map(|dir| PathBuf::from(dir).join("tacky-borders"));
+
+        exe_dir.into_iter().chain(program_data_dir).collect()
+    }
+
+    // Whether the config watcher should run. Read-only configs are assumed to be managed by
+    // someone else, so we don't watch them.
+    pub fn should_watch_changes(&self) -> bool {
+        self.watch_config_changes && !Self::is_read_only()
+    }
+
+    // Portable mode is enabled if the executable's directory contains a config file or the marker
+    fn get_portable_dir() -> Option<PathBuf> {
+        let exe_path = env::current_exe().ok()?;
+        let exe_dir = exe_path.parent()?;
+
+        let is_portable = ConfigFormat::ALL
+            .iter()
+            .map(ConfigFormat::file_name)
+            .chain(iter::once(PORTABLE_MARKER))
+            .any(|file_name| exe_dir.join(file_name).is_file());
+
+        is_portable.then(|| exe_dir.to_path_buf())
+    }
+
+    // Return the path of the config file in use. If there are several, the first in
+    // ConfigFormat::ALL wins. This falls back to config.yaml if none exist yet.
+    pub fn get_path() -> anyhow::Result<PathBuf> {
+        match Self::find_path_and_format()? {
+            Some((path, _)) => Ok(path),
+            None => Ok(Self::get_dir()?.join(ConfigFormat::Yaml.file_name())),
+        }
+    }
+
+    fn find_path_and_format() -> anyhow::Result<Option<(PathBuf, ConfigFormat)>> {
+        let config_dirs = match Self::is_read_only() {
+            true => Self::get_read_only_dirs(),
+            false => vec![Self::get_dir()?],
+        };
+
+        for config_dir in config_dirs {
+            for format in ConfigFormat::ALL {
+                let path = config_dir.join(format.file_name());
+                if fs::exists(&path).context("could not check if config path exists")? {
+                    return Ok(Some((path, format)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Watch every supported config file so that switching formats also triggers a reload
+    pub fn get_watch_targets() -> Vec<WatchTarget> {
+        let config_dir = Self::get_dir().unwrap_or_default();
+
+        ConfigFormat::ALL
+            .iter()
+            .map(|format| WatchTarget::File(config_dir.join(format.file_name())))
+            .collect()
+    }
+
+    // In read-only mode, the log goes to the temp directory instead
+    pub fn get_log_path() -> anyhow::Result<PathBuf> {
+        let log_dir = match Self::is_read_only() {
+            true => env::temp_dir(),
+            false => Self::get_dir()?,
+        };
+
+        Ok(log_dir.join("tacky-borders.log"))
+    }
+
     pub fn reload() {
         let new_config = match Self::create() {
             Ok(config) => {
                 let mut config_watcher = APP_STATE.config_watcher.lock().unwrap();
 
-                if config.watch_config_changes && !config_watcher.is_running() {
+                if config.should_watch_changes() && !config_watcher.is_running() {
                     config_watcher.start().log_if_err();
-                } else if !config.watch_config_changes && config_watcher.is_running() {
+                } else if !config.should_watch_changes() && config_watcher.is_running() {
                     config_watcher.stop().log_if_err();
                 }
 
@@ -209,36 +871,60 @@ impl Config {
                 Config::default()
             }
         };
-        *APP_STATE.config.write().unwrap() = new_config;
+        // Reloading discards any runtime toggle in favor of the config
+        APP_STATE.set_animations_disabled(new_config.disable_animations);
+        APP_STATE.set_debug_overlay_enabled(new_config.debug_overlay);
+        APP_STATE.set_color_blind_mode(new_config.color_blind_mode);
+        APP_STATE.config.store(Arc::new(new_config));
+
+        // Rescan for conflicting modules once, in case they were loaded or unloaded since
+        clear_conflicting_modules();
+
+        // The tray's checkboxes show the toggles we just reset
+        sys_tray_icon::refresh_check_items();
     }
 
     pub fn config_watcher_callback() {
-        let old_config = (*APP_STATE.config.read().unwrap()).clone();
+        let old_config = APP_STATE.config.load_full();
         Self::reload();
-        let new_config = APP_STATE.config.read().unwrap();
+        let new_config = APP_STATE.config.load();
 
-        if old_config != *new_config {
-            info!("config.yaml has changed; reloading borders");
+        if *old_config != **new_config {
+            info!("config file has changed; reloading borders");
             reload_borders();
         }
     }
 }
 
+// A file that contributes to the effective config. Changes to any of these will trigger the config
+// watcher's callback.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchTarget {
+    File(PathBuf),
+}
+
+// The watch targets after grouping them by directory, since we only need one handle per directory
+#[derive(Debug, Clone, Default)]
+struct WatchedDir {
+    dir: PathBuf,
+    file_names: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigWatcher {
-    config_path: PathBuf,
+    watch_set: Vec<WatchTarget>,
     debounce_time: time::Duration,
     callback_fn: fn(),
-    config_dir_handle: Option<isize>,
+    dir_handles: Vec<isize>,
 }
 
 impl ConfigWatcher {
-    pub fn new(config_path: PathBuf, debounce_time: u64, callback_fn: fn()) -> Self {
+    pub fn new(watch_set: Vec<WatchTarget>, debounce_time: u64, callback_fn: fn()) -> Self {
         Self {
-            config_path,
+            watch_set,
             debounce_time: time::Duration::from_millis(debounce_time),
             callback_fn,
-            config_dir_handle: None,
+            dir_handles: Vec::new(),
         }
     }
 
@@ -249,12 +935,50 @@ impl ConfigWatcher {
             return Err(anyhow!("config watcher is already running"));
         }
 
-        // NOTE: apparently you can use context() on an Option lol
-        let config_dir = self
-            .config_path
-            .parent()
-            .context("could not get parent dir for config watcher")?;
-        let config_dir_vec: Vec<u16> = config_dir
+        for watched_dir in self.get_watched_dirs()? {
+            if let Err(e) = self.watch_dir(watched_dir) {
+                // Clean up the directories we've already started watching
+                self.stop().log_if_err();
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_watched_dirs(&self) -> anyhow::Result<Vec<WatchedDir>> {
+        let mut watched_dirs: Vec<WatchedDir> = Vec::new();
+
+        for target in self.watch_set.iter() {
+            let WatchTarget::File(path) = target;
+
+            // NOTE: apparently you can use context() on an Option lol
+            let dir = path
+                .parent()
+                .context("could not get parent dir for config watcher")?
+                .to_path_buf();
+            let file_name = path
+                .file_name()
+                .context("could not get file name for config watcher")?
+                .to_owned()
+                .into_string()
+                .map_err(|_| anyhow!("could not convert file name for config watcher"))?;
+
+            match watched_dirs.iter_mut().find(|watched| watched.dir == dir) {
+                Some(watched_dir) => watched_dir.file_names.push(file_name),
+                None => watched_dirs.push(WatchedDir {
+                    dir,
+                    file_names: vec![file_name],
+                }),
+            }
+        }
+
+        Ok(watched_dirs)
+    }
+
+    fn watch_dir(&mut self, watched_dir: WatchedDir) -> anyhow::Result<()> {
+        let dir_vec: Vec<u16> = watched_dir
+            .dir
             .as_os_str()
             .encode_wide()
             .chain(iter::once(0))
@@ -262,7 +986,7 @@ impl ConfigWatcher {
 
         let dir_handle = unsafe {
             CreateFileW(
-                PCWSTR(config_dir_vec.as_ptr()),
+                PCWSTR(dir_vec.as_ptr()),
                 FILE_LIST_DIRECTORY.0,
                 FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
                 None,
@@ -270,21 +994,17 @@ impl ConfigWatcher {
                 FILE_FLAG_BACKUP_SEMANTICS,
                 HANDLE::default(),
             )
-            .context("could not create dir handle for config watcher")?
+            .context(format!(
+                "could not create dir handle for config watcher: {}",
+                watched_dir.dir.display()
+            ))?
         };
 
         // Convert HANDLE to isize so we can move it into the new thread
         let dir_handle_isize = dir_handle.0 as isize;
-        self.config_dir_handle = Some(dir_handle_isize);
+        self.dir_handles.push(dir_handle_isize);
 
         // Also initialize these variables so we move them into the new thread
-        let config_name = self
-            .config_path
-            .file_name()
-            .context("could not get config name for config watcher")?
-            .to_owned()
-            .into_string()
-            .map_err(|_| anyhow!("could not convert config name for config watcher"))?;
         let debounce_time = self.debounce_time;
         let callback_fn = self.callback_fn;
 
@@ -302,8 +1022,10 @@ impl ConfigWatcher {
                     dir_handle,
                     buffer.as_mut_ptr() as _,
                     buffer.len() as u32,
-                    FALSE,
-                    FILE_NOTIFY_CHANGE_LAST_WRITE,
+                    false,
+                    // Some editors save by writing to a temp file and renaming it over the
+                    // config, which doesn't always trigger a LAST_WRITE notification
+                    FILE_NOTIFY_CHANGE_LAST_WRITE | FILE_NOTIFY_CHANGE_FILE_NAME,
                     Some(ptr::addr_of_mut!(bytes_returned)),
                     None,
                     None,
@@ -317,11 +1039,19 @@ impl ConfigWatcher {
                     thread::sleep(debounce_time - now.elapsed());
                 }
 
-                Self::process_dir_change_notifs(&buffer, bytes_returned, &config_name, callback_fn);
+                Self::process_dir_change_notifs(
+                    &buffer,
+                    bytes_returned,
+                    &watched_dir.file_names,
+                    callback_fn,
+                );
                 now = time::Instant::now();
             }
 
-            debug!("exiting config watcher thread");
+            debug!(
+                "exiting config watcher thread for {}",
+                watched_dir.dir.display()
+            );
         });
 
         Ok(())
@@ -330,7 +1060,7 @@ impl ConfigWatcher {
     pub fn process_dir_change_notifs(
         buffer: &[u8; 1024],
         bytes_returned: u32,
-        config_name: &str,
+        file_names: &[String],
         callback_fn: fn(),
     ) {
         let mut offset = 0usize;
@@ -343,9 +1073,21 @@ impl ConfigWatcher {
                 slice::from_raw_parts(info.FileName.as_ptr(), info.FileNameLength as usize / 2)
             };
             let file_name = String::from_utf16_lossy(name_slice);
-            debug!("file changed: {}", file_name);
+            debug!("file changed: {} (action: {:?})", file_name, info.Action);
+
+            // Only reload if the file now exists with new contents. We ignore REMOVED and
+            // RENAMED_OLD_NAME because those are emitted when an editor moves the old file out of
+            // the way, right before the new one is put in its place.
+            let is_file_updated = matches!(
+                info.Action,
+                FILE_ACTION_MODIFIED | FILE_ACTION_ADDED | FILE_ACTION_RENAMED_NEW_NAME
+            );
 
-            if file_name == *config_name {
+            let is_watched_file = file_names
+                .iter()
+                .any(|name| file_name.eq_ignore_ascii_case(name));
+
+            if is_file_updated && is_watched_file {
                 callback_fn();
                 break; // Prevent multiple callbacks from the same notification
             }
@@ -362,29 +1104,211 @@ impl ConfigWatcher {
     pub fn stop(&mut self) -> anyhow::Result<()> {
         debug!("stopping config watcher");
 
-        if let Some(dir_handle_isize) = self.config_dir_handle {
+        if self.dir_handles.is_empty() {
+            debug!("config watcher is not running; skipping cleanup");
+            return Ok(());
+        }
+
+        let mut res = Ok(());
+
+        // Keep any handles we failed to close so we can try again later
+        self.dir_handles.retain(|&dir_handle_isize| {
             let dir_handle = HANDLE(dir_handle_isize as _);
 
             // Cancel all pending I/O operations on the handle
             unsafe { CancelIoEx(dir_handle, None) }.log_if_err();
 
             // Close the handle for cleanup. This should automatically exit the watcher thread.
-            let res = unsafe { CloseHandle(dir_handle) }.map_err(anyhow::Error::new);
-
-            // Reset the config dir handle if we successfully closed it
-            if res.is_ok() {
-                self.config_dir_handle = None;
+            match unsafe { CloseHandle(dir_handle) } {
+                Ok(_) => false,
+                Err(e) => {
+                    res = Err(anyhow::Error::new(e));
+                    true
+                }
             }
+        });
 
-            res
-        } else {
-            debug!("config watcher is not running; skipping cleanup");
-
-            Ok(())
-        }
+        res
     }
 
     pub fn is_running(&self) -> bool {
-        self.config_dir_handle.is_some()
+        !self.dir_handles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_config(contents: &str) -> Config {
+        let mut config = ConfigFormat::Yaml.parse(contents).unwrap();
+        config.remove_invalid_values();
+        config
+    }
+
+    #[test]
+    fn invalid_window_rules_are_skipped() {
+        let config = parse_config(
+            "
+window_rules:
+  - match: Process
+    name: first
+  - name: missing_match
+  - match: Process
+    name: negative_width
+    border_width: -2
+  - match: Class
+    name: one_color_gradient
+    active_color: { colors: ['#ffffff'], direction: 45deg }
+  - match: Title
+    name: zero_fps
+    animations: { fps: 0 }
+  - match: Process
+    name: last
+",
+        );
+
+        let names: Vec<_> = config
+            .window_rules
+            .iter()
+            .map(|rule| rule.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["first", "last"]);
+    }
+
+    #[test]
+    fn invalid_global_values_fall_back_to_defaults() {
+        let config = parse_config(
+            "
+global:
+  border_width: -1
+  active_color: '#ff0000'
+  inactive_color: { colors: ['#ffffff'], direction: 45deg }
+  last_active_color: { colors: [], direction: 45deg }
+  animations: { fps: -5 }
+on_air:
+  active_color: { colors: ['#ffffff'], direction: 45deg }
+window_rules:
+  - match: Process
+    name: kept
+",
+        );
+
+        assert_eq!(config.global.border_width, 4.0);
+        assert_eq!(
+            config.global.active_color,
+            ColorConfig::SolidConfig("#ff0000".to_string())
+        );
+        assert_eq!(config.global.inactive_color, ColorConfig::default());
+        assert_eq!(config.global.last_active_color, None);
+        assert_eq!(config.global.animations.fps, 60);
+        assert_eq!(config.on_air.active_color, None);
+        assert_eq!(config.window_rules.len(), 1);
+    }
+
+    #[test]
+    fn expands_windows_style_variables() {
+        env::set_var("TACKY_TEST_WIN_VAR", r"C:\Users\tacky");
+
+        assert_eq!(
+            expand_vars(r"%TACKY_TEST_WIN_VAR%\config.yaml"),
+            r"C:\Users\tacky\config.yaml"
+        );
+        assert_eq!(
+            expand_vars("%TACKY_TEST_WIN_VAR%%TACKY_TEST_WIN_VAR%"),
+            r"C:\Users\tackyC:\Users\tacky"
+        );
+    }
+
+    #[test]
+    fn expands_unix_style_variables() {
+        env::set_var("TACKY_TEST_UNIX_VAR", "/home/tacky");
+
+        assert_eq!(
+            expand_vars("${TACKY_TEST_UNIX_VAR}/config.yaml"),
+            "/home/tacky/config.yaml"
+        );
+        assert_eq!(
+            expand_vars("$TACKY_TEST_UNIX_VAR/config.yaml"),
+            "/home/tacky/config.yaml"
+        );
+        assert_eq!(
+            expand_vars("${TACKY_TEST_UNIX_VAR}x $TACKY_TEST_UNIX_VAR.bak"),
+            "/home/tackyx /home/tacky.bak"
+        );
+    }
+
+    #[test]
+    fn expands_leading_tilde_only() {
+        let home_dir = home_dir().unwrap().to_string_lossy().into_owned();
+
+        assert_eq!(expand_vars("~"), home_dir);
+        assert_eq!(
+            expand_vars("~/config.yaml"),
+            format!("{home_dir}/config.yaml")
+        );
+        assert_eq!(
+            expand_vars(r"~\config.yaml"),
+            format!(r"{home_dir}\config.yaml")
+        );
+
+        // '~user' and tildes that aren't at the start aren't expanded
+        assert_eq!(expand_vars("~tacky/config.yaml"), "~tacky/config.yaml");
+        assert_eq!(expand_vars("C:/~/config.yaml"), "C:/~/config.yaml");
+    }
+
+    #[test]
+    fn leaves_undefined_variables_as_written() {
+        env::set_var("TACKY_TEST_DEFINED_VAR", "defined");
+
+        assert_eq!(
+            expand_vars("%TACKY_TEST_UNDEFINED%"),
+            "%TACKY_TEST_UNDEFINED%"
+        );
+        assert_eq!(
+            expand_vars("${TACKY_TEST_UNDEFINED}"),
+            "${TACKY_TEST_UNDEFINED}"
+        );
+        assert_eq!(
+            expand_vars("$TACKY_TEST_UNDEFINED/x"),
+            "$TACKY_TEST_UNDEFINED/x"
+        );
+
+        // The closing '%' of an undefined variable can't start another reference
+        assert_eq!(
+            expand_vars("%TACKY_TEST_UNDEFINED%TACKY_TEST_DEFINED_VAR%"),
+            "%TACKY_TEST_UNDEFINED%TACKY_TEST_DEFINED_VAR%"
+        );
+    }
+
+    #[test]
+    fn keeps_lone_and_unterminated_markers() {
+        env::set_var("TACKY_TEST_MARKER_VAR", "var");
+
+        assert_eq!(expand_vars("100%"), "100%");
+        assert_eq!(
+            expand_vars("%TACKY_TEST_MARKER_VAR"),
+            "%TACKY_TEST_MARKER_VAR"
+        );
+        assert_eq!(expand_vars("100% $TACKY_TEST_MARKER_VAR"), "100% var");
+        assert_eq!(expand_vars("%"), "%");
+        assert_eq!(expand_vars("$"), "$");
+        assert_eq!(expand_vars("$ 5"), "$ 5");
+        assert_eq!(
+            expand_vars("${TACKY_TEST_MARKER_VAR"),
+            "${TACKY_TEST_MARKER_VAR"
+        );
+    }
+
+    #[test]
+    fn themed_colors_are_validated_on_both_sides() {
+        let themed = |dark: &str| -> ColorConfig {
+            serde_yml::from_str(&format!("{{ light: '#ffffff', dark: {dark} }}")).unwrap()
+        };
+
+        assert!(validate_color_config(&themed("'#000000'")).is_ok());
+        assert!(
+            validate_color_config(&themed("{ colors: ['#000000'], direction: 45deg }")).is_err()
+        );
     }
 }
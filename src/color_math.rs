@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+use windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F;
+
+// The color space used when blending between colors (e.g. fades and gradients). Interpolating in
+// sRGB is cheap but produces muddy midpoints, whereas Oklab is perceptually uniform. Oklch is the
+// polar form of Oklab, which goes around the hue wheel instead of straight through the middle, so
+// it keeps colors saturated (e.g. red to blue passes through purple rather than a grayish pink).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Oklab,
+    Oklch,
+}
+
+pub fn interpolate_color(
+    from: &D2D1_COLOR_F,
+    to: &D2D1_COLOR_F,
+    t: f32,
+    color_space: ColorSpace,
+) -> D2D1_COLOR_F {
+    let t = t.clamp(0.0, 1.0);
+    let a = lerp(from.a, to.a, t);
+
+    match color_space {
+        ColorSpace::Srgb => D2D1_COLOR_F {
+            r: lerp(from.r, to.r, t),
+            g: lerp(from.g, to.g, t),
+            b: lerp(from.b, to.b, t),
+            a,
+        },
+        ColorSpace::Oklab => {
+            let from_lab = srgb_to_oklab(from);
+            let to_lab = srgb_to_oklab(to);
+
+            let lab = [
+                lerp(from_lab[0], to_lab[0], t),
+                lerp(from_lab[1], to_lab[1], t),
+                lerp(from_lab[2], to_lab[2], t),
+            ];
+
+            oklab_to_srgb(lab, a)
+        }
+        ColorSpace::Oklch => {
+            let [from_l, from_c, from_h] = oklab_to_oklch(srgb_to_oklab(from));
+            let [to_l, to_c, to_h] = oklab_to_oklch(srgb_to_oklab(to));
+
+            // Grays don't have a meaningful hue, so we keep the other color's hue instead of
+            // sweeping through unrelated hues on the way to (or from) a gray
+            let (from_h, to_h) = match (from_c < ACHROMATIC_CHROMA, to_c < ACHROMATIC_CHROMA) {
+                (true, false) => (to_h, to_h),
+                (false, true) => (from_h, from_h),
+                _ => (from_h, to_h),
+            };
+            // Take the shorter way around the hue wheel
+            let hue_diff = (to_h - from_h + 180.0).rem_euclid(360.0) - 180.0;
+
+            let lch = [
+                lerp(from_l, to_l, t),
+                lerp(from_c, to_c, t),
+                from_h + hue_diff * t,
+            ];
+
+            oklch_to_srgb(lch, a)
+        }
+    }
+}
+
+// Chroma below which we treat an Oklch color as gray
+const ACHROMATIC_CHROMA: f32 = 1e-4;
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+// Conversions below are from https://bottosson.github.io/posts/oklab/ (constants are kept exactly
+// as published, hence the allow)
+#[allow(clippy::excessive_precision)]
+pub fn srgb_to_oklab(color: &D2D1_COLOR_F) -> [f32; 3] {
+    let r = srgb_to_linear(color.r);
+    let g = srgb_to_linear(color.g);
+    let b = srgb_to_linear(color.b);
+
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+#[allow(clippy::excessive_precision)]
+pub fn oklab_to_srgb(lab: [f32; 3], alpha: f32) -> D2D1_COLOR_F {
+    let l = (lab[0] + 0.3963377774 * lab[1] + 0.2158037573 * lab[2]).powi(3);
+    let m = (lab[0] - 0.1055613458 * lab[1] - 0.0638541728 * lab[2]).powi(3);
+    let s = (lab[0] - 0.0894841775 * lab[1] - 1.2914855480 * lab[2]).powi(3);
+
+    let r = 4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s;
+    let g = -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s;
+    let b = -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s;
+
+    D2D1_COLOR_F {
+        r: linear_to_srgb(r).clamp(0.0, 1.0),
+        g: linear_to_srgb(g).clamp(0.0, 1.0),
+        b: linear_to_srgb(b).clamp(0.0, 1.0),
+        a: alpha,
+    }
+}
+
+// Oklch is [lightness, chroma, hue], where hue is in degrees
+fn oklab_to_oklch(lab: [f32; 3]) -> [f32; 3] {
+    let chroma = lab[1].hypot(lab[2]);
+    let hue = lab[2].atan2(lab[1]).to_degrees().rem_euclid(360.0);
+
+    [lab[0], chroma, hue]
+}
+
+pub fn oklch_to_srgb(lch: [f32; 3], alpha: f32) -> D2D1_COLOR_F {
+    let (sin, cos) = lch[2].to_radians().sin_cos();
+    oklab_to_srgb([lch[0], lch[1] * cos, lch[1] * sin], alpha)
+}
+
+// Convert CIE Lab (D50 white point, like CSS's lab()) to sRGB. Lightness ranges from 0.0 to 100.0.
+// Conversions and matrices are from https://www.w3.org/TR/css-color-4/#color-conversion-code
+#[allow(clippy::excessive_precision)]
+pub fn cielab_to_srgb(lab: [f32; 3], alpha: f32) -> D2D1_COLOR_F {
+    const EPSILON: f32 = 216.0 / 24389.0;
+    const KAPPA: f32 = 24389.0 / 27.0;
+    const D50_WHITE: [f32; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+
+    let f_inverse = |f: f32| match f.powi(3) > EPSILON {
+        true => f.powi(3),
+        false => (116.0 * f - 16.0) / KAPPA,
+    };
+    let y = match lab[0] > KAPPA * EPSILON {
+        true => fy.powi(3),
+        false => lab[0] / KAPPA,
+    };
+    let x = f_inverse(fx) * D50_WHITE[0];
+    let y = y * D50_WHITE[1];
+    let z = f_inverse(fz) * D50_WHITE[2];
+
+    // Bradford chromatic adaptation from D50 to D65
+    let (x, y, z) = (
+        0.955473421488075 * x - 0.02309845494876471 * y + 0.06325924320057072 * z,
+        -0.0283697093338637 * x + 1.0099953980813041 * y + 0.021041441191917323 * z,
+        0.012314014864481998 * x - 0.020507649298898964 * y + 1.330365926242124 * z,
+    );
+
+    // XYZ (D65) to linear sRGB
+    let r = 3.2409699419045226 * x - 1.537383177570094 * y - 0.4986107602930034 * z;
+    let g = -0.9692436362808796 * x + 1.8759675015077202 * y + 0.04155505740717559 * z;
+    let b = 0.05563007969699366 * x - 0.20397695888897652 * y + 1.0569715142428786 * z;
+
+    D2D1_COLOR_F {
+        r: linear_to_srgb(r).clamp(0.0, 1.0),
+        g: linear_to_srgb(g).clamp(0.0, 1.0),
+        b: linear_to_srgb(b).clamp(0.0, 1.0),
+        a: alpha,
+    }
+}
+
+fn srgb_to_linear(x: f32) -> f32 {
+    match x <= 0.04045 {
+        true => x / 12.92,
+        false => ((x + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+fn linear_to_srgb(x: f32) -> f32 {
+    match x <= 0.0031308 {
+        true => x * 12.92,
+        false => 1.055 * x.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+// Hue is in degrees, and saturation/lightness/value range from 0.0 to 1.0
+pub fn hsl_to_srgb(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> D2D1_COLOR_F {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    hue_chroma_to_srgb(hue, chroma, lightness - chroma / 2.0, alpha)
+}
+
+pub fn hsv_to_srgb(hue: f32, saturation: f32, value: f32, alpha: f32) -> D2D1_COLOR_F {
+    let chroma = value * saturation;
+    hue_chroma_to_srgb(hue, chroma, value - chroma, alpha)
+}
+
+// Shared by the HSL and HSV conversions, which only differ in how they get the chroma and the
+// amount added to every channel
+fn hue_chroma_to_srgb(hue: f32, chroma: f32, offset: f32, alpha: f32) -> D2D1_COLOR_F {
+    let hue_sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (hue_sector % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match hue_sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    D2D1_COLOR_F {
+        r: (r + offset).clamp(0.0, 1.0),
+        g: (g + offset).clamp(0.0, 1.0),
+        b: (b + offset).clamp(0.0, 1.0),
+        a: alpha,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn color(r: f32, g: f32, b: f32, a: f32) -> D2D1_COLOR_F {
+        D2D1_COLOR_F { r, g, b, a }
+    }
+
+    fn assert_close(actual: &D2D1_COLOR_F, expected: &D2D1_COLOR_F) {
+        let is_close = (actual.r - expected.r).abs() < EPSILON
+            && (actual.g - expected.g).abs() < EPSILON
+            && (actual.b - expected.b).abs() < EPSILON
+            && (actual.a - expected.a).abs() < EPSILON;
+
+        assert!(is_close, "expected {expected:?}, got {actual:?}");
+    }
+
+    fn samples() -> [D2D1_COLOR_F; 6] {
+        [
+            color(0.0, 0.0, 0.0, 1.0),
+            color(1.0, 1.0, 1.0, 1.0),
+            color(1.0, 0.0, 0.0, 0.5),
+            color(0.2, 0.6, 0.9, 1.0),
+            color(0.01, 0.02, 0.03, 0.0),
+            color(0.38, 0.45, 0.91, 0.8),
+        ]
+    }
+
+    #[test]
+    fn srgb_linear_round_trip() {
+        for x in [0.0, 0.02, 0.04045, 0.05, 0.5, 0.9, 1.0] {
+            assert!((linear_to_srgb(srgb_to_linear(x)) - x).abs() < 1e-5, "{x}");
+        }
+
+        // Both ends of the piecewise curve meet at the threshold
+        assert!((srgb_to_linear(0.04045) - 0.04045 / 12.92).abs() < 1e-6);
+        assert_eq!(srgb_to_linear(1.0), 1.0);
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        for sample in samples() {
+            assert_close(&oklab_to_srgb(srgb_to_oklab(&sample), sample.a), &sample);
+            assert_close(
+                &oklch_to_srgb(oklab_to_oklch(srgb_to_oklab(&sample)), sample.a),
+                &sample,
+            );
+        }
+
+        // White has a lightness of 1 and no chroma
+        let [l, a, b] = srgb_to_oklab(&color(1.0, 1.0, 1.0, 1.0));
+        assert!((l - 1.0).abs() < EPSILON && a.abs() < EPSILON && b.abs() < EPSILON);
+    }
+
+    #[test]
+    fn interpolation_keeps_end_points() {
+        let from = color(1.0, 0.0, 0.0, 1.0);
+        let to = color(0.2, 0.6, 0.9, 0.25);
+
+        for color_space in [ColorSpace::Srgb, ColorSpace::Oklab, ColorSpace::Oklch] {
+            assert_close(&interpolate_color(&from, &to, 0.0, color_space), &from);
+            assert_close(&interpolate_color(&from, &to, 1.0, color_space), &to);
+
+            // t is clamped, so overshooting easing functions can't extrapolate past the ends
+            assert_close(&interpolate_color(&from, &to, -0.5, color_space), &from);
+            assert_close(&interpolate_color(&from, &to, 1.5, color_space), &to);
+        }
+    }
+
+    #[test]
+    fn out_of_gamut_results_are_clamped() {
+        let in_range = |color: &D2D1_COLOR_F| {
+            [color.r, color.g, color.b]
+                .iter()
+                .all(|channel| (0.0..=1.0).contains(channel))
+        };
+
+        // Highly saturated Oklab/Oklch colors that sRGB can't represent
+        let colors = [
+            oklab_to_srgb([0.9, 0.4, -0.4], 1.0),
+            oklab_to_srgb([0.1, -0.4, 0.4], 1.0),
+            oklch_to_srgb([0.7, 0.5, 150.0], 1.0),
+            cielab_to_srgb([50.0, 120.0, -120.0], 1.0),
+            cielab_to_srgb([110.0, 0.0, 0.0], 1.0),
+        ];
+        for color in colors {
+            assert!(in_range(&color), "{color:?}");
+        }
+
+        // Oklch keeps the chroma while sweeping the hue, which can leave the sRGB gamut in between
+        let from = color(0.0, 1.0, 1.0, 1.0);
+        let to = color(1.0, 0.0, 0.0, 1.0);
+        for t in [0.25, 0.5, 0.75] {
+            let mixed = interpolate_color(&from, &to, t, ColorSpace::Oklch);
+            assert!(in_range(&mixed), "{mixed:?}");
+        }
+    }
+}
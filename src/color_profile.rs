@@ -0,0 +1,160 @@
+use anyhow::{anyhow, Context};
+use windows::core::{w, PCWSTR, PWSTR};
+use windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F;
+use windows::Win32::Graphics::Gdi::{
+    CreateDCW, DeleteDC, GetMonitorInfoW, HMONITOR, MONITORINFO, MONITORINFOEXW,
+};
+use windows::Win32::Storage::FileSystem::{FILE_SHARE_READ, OPEN_EXISTING};
+use windows::Win32::UI::ColorSystem::{
+    CloseColorProfile, CreateMultiProfileTransform, DeleteColorTransform, GetICMProfileW,
+    GetStandardColorSpaceProfileW, LCS_sRGB, OpenColorProfileW, TranslateColors, BEST_MODE, COLOR,
+    COLOR_RGB, INDEX_DONT_CARE, INTENT_PERCEPTUAL, PROFILE, PROFILE_FILENAME, PROFILE_READ,
+    RGBCOLOR,
+};
+
+// Converts sRGB colors into a monitor's color space using the monitor's ICC profile
+#[derive(Debug)]
+pub struct ColorTransform(isize);
+
+impl ColorTransform {
+    // Create a transform from sRGB to the color profile associated with the given monitor
+    pub fn for_monitor(hmonitor: HMONITOR) -> anyhow::Result<Self> {
+        let srgb_profile_path = get_srgb_profile_path()?;
+        let monitor_profile_path = get_monitor_profile_path(hmonitor)?;
+
+        let srgb_profile = open_color_profile(&srgb_profile_path)?;
+        let monitor_profile = match open_color_profile(&monitor_profile_path) {
+            Ok(monitor_profile) => monitor_profile,
+            Err(err) => {
+                let _ = unsafe { CloseColorProfile(srgb_profile) };
+                return Err(err);
+            }
+        };
+
+        let transform = unsafe {
+            CreateMultiProfileTransform(
+                &[srgb_profile, monitor_profile],
+                &[INTENT_PERCEPTUAL],
+                BEST_MODE,
+                INDEX_DONT_CARE,
+            )
+        };
+
+        // The transform keeps what it needs, so the profiles can be closed right away
+        let _ = unsafe { CloseColorProfile(srgb_profile) };
+        let _ = unsafe { CloseColorProfile(monitor_profile) };
+
+        match transform {
+            0 => Err(anyhow!(
+                "could not create color transform for {monitor_profile_path}"
+            )),
+            transform => Ok(Self(transform)),
+        }
+    }
+
+    pub fn apply(&self, color: D2D1_COLOR_F) -> D2D1_COLOR_F {
+        let to_u16 = |x: f32| (x.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        let input = COLOR {
+            rgb: RGBCOLOR {
+                red: to_u16(color.r),
+                green: to_u16(color.g),
+                blue: to_u16(color.b),
+            },
+        };
+        let mut output = COLOR::default();
+
+        if !unsafe { TranslateColors(self.0, &input, 1, COLOR_RGB, &mut output, COLOR_RGB) }
+            .as_bool()
+        {
+            return color;
+        }
+
+        let rgb = unsafe { output.rgb };
+        D2D1_COLOR_F {
+            r: rgb.red as f32 / 65535.0,
+            g: rgb.green as f32 / 65535.0,
+            b: rgb.blue as f32 / 65535.0,
+            a: color.a,
+        }
+    }
+}
+
+impl Drop for ColorTransform {
+    fn drop(&mut self) {
+        let _ = unsafe { DeleteColorTransform(self.0) };
+    }
+}
+
+fn get_srgb_profile_path() -> anyhow::Result<String> {
+    let mut buffer = [0u16; 260];
+    let mut buffer_size = (buffer.len() * size_of::<u16>()) as u32;
+
+    unsafe {
+        GetStandardColorSpaceProfileW(
+            PCWSTR::null(),
+            LCS_sRGB.0 as u32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut buffer_size,
+        )
+    }
+    .ok()
+    .context("could not retrieve the srgb color profile")?;
+
+    Ok(wide_to_string(&buffer))
+}
+
+fn get_monitor_profile_path(hmonitor: HMONITOR) -> anyhow::Result<String> {
+    let mut monitor_info = MONITORINFOEXW {
+        monitorInfo: MONITORINFO {
+            cbSize: size_of::<MONITORINFOEXW>() as u32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    if !unsafe { GetMonitorInfoW(hmonitor, &mut monitor_info.monitorInfo) }.as_bool() {
+        return Err(anyhow!("could not retrieve monitor info for {hmonitor:?}"));
+    }
+
+    let hdc = unsafe {
+        CreateDCW(
+            w!("DISPLAY"),
+            PCWSTR(monitor_info.szDevice.as_ptr()),
+            PCWSTR::null(),
+            None,
+        )
+    };
+    if hdc.is_invalid() {
+        return Err(anyhow!("could not create device context for {hmonitor:?}"));
+    }
+
+    let mut buffer = [0u16; 260];
+    let mut buffer_size = buffer.len() as u32;
+    let res = unsafe { GetICMProfileW(hdc, &mut buffer_size, PWSTR(buffer.as_mut_ptr())) };
+    let _ = unsafe { DeleteDC(hdc) };
+
+    res.ok()
+        .context(format!("could not retrieve color profile for {hmonitor:?}"))?;
+
+    Ok(wide_to_string(&buffer))
+}
+
+// Return a handle to the color profile at the given path, which must be closed with
+// CloseColorProfile()
+fn open_color_profile(path: &str) -> anyhow::Result<isize> {
+    let mut path_wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+    let profile = PROFILE {
+        dwType: PROFILE_FILENAME,
+        pProfileData: path_wide.as_mut_ptr() as *mut _,
+        cbDataSize: (path_wide.len() * size_of::<u16>()) as u32,
+    };
+
+    match unsafe { OpenColorProfileW(&profile, PROFILE_READ, FILE_SHARE_READ.0, OPEN_EXISTING.0) } {
+        0 => Err(anyhow!("could not open color profile {path}")),
+        hprofile => Ok(hprofile),
+    }
+}
+
+fn wide_to_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
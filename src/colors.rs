@@ -1,24 +1,42 @@
 use anyhow::Context;
 use core::f32;
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::f32::consts::PI;
+use std::time;
 use windows::Foundation::Numerics::Matrix3x2;
-use windows::Win32::Foundation::{BOOL, FALSE, RECT};
-use windows::Win32::Graphics::Direct2D::Common::{D2D1_COLOR_F, D2D1_GRADIENT_STOP, D2D_POINT_2F};
+use windows::Win32::Foundation::{BOOL, COLORREF, FALSE, RECT};
+use windows::Win32::Graphics::Direct2D::Common::{
+    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_COLOR_F, D2D1_GRADIENT_STOP, D2D1_PIXEL_FORMAT,
+    D2D_POINT_2F, D2D_SIZE_U,
+};
 use windows::Win32::Graphics::Direct2D::{
-    ID2D1Brush, ID2D1HwndRenderTarget, ID2D1LinearGradientBrush, ID2D1SolidColorBrush,
-    D2D1_BRUSH_PROPERTIES, D2D1_EXTEND_MODE_CLAMP, D2D1_GAMMA_2_2,
-    D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES,
+    ID2D1BitmapBrush, ID2D1Brush, ID2D1LinearGradientBrush, ID2D1RadialGradientBrush,
+    ID2D1RenderTarget, ID2D1SolidColorBrush, D2D1_BITMAP_BRUSH_PROPERTIES,
+    D2D1_BITMAP_INTERPOLATION_MODE_LINEAR, D2D1_BITMAP_PROPERTIES, D2D1_BRUSH_PROPERTIES,
+    D2D1_EXTEND_MODE_CLAMP, D2D1_GAMMA_2_2, D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES,
+    D2D1_RADIAL_GRADIENT_BRUSH_PROPERTIES,
 };
 use windows::Win32::Graphics::Dwm::DwmGetColorizationColor;
+use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
 
+use crate::color_math::{
+    cielab_to_srgb, hsl_to_srgb, hsv_to_srgb, interpolate_color, oklab_to_srgb, oklch_to_srgb,
+    ColorSpace,
+};
+use crate::utils::{get_colorization_intensity, is_light_theme, is_transparency_enabled};
 use crate::LogIfErr;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
+#[allow(clippy::enum_variant_names)]
 pub enum ColorConfig {
     SolidConfig(String),
     GradientConfig(GradientConfig),
+    RadialGradientConfig(RadialGradientConfig),
+    ConicGradientConfig(ConicGradientConfig),
+    KeyframesConfig(KeyframesConfig),
+    ThemedConfig(ThemedConfig),
 }
 
 impl Default for ColorConfig {
@@ -32,6 +50,93 @@ impl Default for ColorConfig {
 pub struct GradientConfig {
     pub colors: Vec<String>,
     pub direction: GradientDirection,
+    #[serde(default)]
+    pub relative_to: GradientSpace,
+    // Overrides the global color_space for this gradient
+    #[serde(default)]
+    pub interpolation: Option<ColorSpace>,
+}
+
+// A gradient that radiates outward from 'center' and reaches its last color at 'radius'. Both are
+// relative to the window (or monitor), so the gradient is stretched into an ellipse to fit it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RadialGradientConfig {
+    pub colors: Vec<String>,
+    #[serde(default = "serde_default_center")]
+    pub center: [f32; 2],
+    pub radius: f32,
+    #[serde(default)]
+    pub relative_to: GradientSpace,
+    #[serde(default)]
+    pub interpolation: Option<ColorSpace>,
+}
+
+// A gradient whose colors sweep clockwise around 'center', starting at 'start_angle' (where 0deg
+// points up). Like radial gradients, it's stretched to fit the window (or monitor).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ConicGradientConfig {
+    pub colors: Vec<String>,
+    #[serde(default = "serde_default_center")]
+    pub center: [f32; 2],
+    pub start_angle: String,
+    #[serde(default)]
+    pub relative_to: GradientSpace,
+    #[serde(default)]
+    pub interpolation: Option<ColorSpace>,
+}
+
+fn serde_default_center() -> [f32; 2] {
+    [0.5, 0.5]
+}
+
+// A solid color that changes over time by interpolating between keyframes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KeyframesConfig {
+    pub keyframes: Vec<KeyframeConfig>,
+    #[serde(rename = "loop", default)]
+    pub is_looping: bool,
+}
+
+// Separate colors for Windows' light and dark app themes. Each can be any other kind of color.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ThemedConfig {
+    pub light: Box<ColorConfig>,
+    pub dark: Box<ColorConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KeyframeConfig {
+    // Time since the start of the animation (e.g. "2s" or "500ms")
+    pub at: String,
+    pub color: String,
+}
+
+// What the gradient's direction coordinates are relative to. With Monitor, the gradient spans the
+// whole monitor and each window's border only shows the slice where the window sits.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum GradientSpace {
+    #[default]
+    Window,
+    Monitor,
+}
+
+// A type of color blindness to adjust colors for. Colors are remapped so that differences someone
+// with that type can't see are moved into differences they can (see remap_for_color_blindness).
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ColorBlindMode {
+    #[default]
+    Off,
+    // Reduced sensitivity to green, the most common type
+    Deuteranopia,
+    // Reduced sensitivity to red
+    Protanopia,
+    // Reduced sensitivity to blue
+    Tritanopia,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -59,6 +164,7 @@ impl Default for Color {
         Color::Solid(Solid {
             color: D2D1_COLOR_F::default(),
             brush: None,
+            keyframes: None,
         })
     }
 }
@@ -67,142 +173,365 @@ impl Default for Color {
 pub struct Solid {
     color: D2D1_COLOR_F,
     brush: Option<ID2D1SolidColorBrush>,
+    keyframes: Option<Keyframes>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keyframes {
+    // Pairs of (seconds since start, color), sorted by time
+    frames: Vec<(f32, D2D1_COLOR_F)>,
+    is_looping: bool,
+    color_space: ColorSpace,
+    start_time: time::Instant,
 }
 
 #[derive(Debug, Clone)]
 pub struct Gradient {
     gradient_stops: Vec<D2D1_GRADIENT_STOP>, // Array of gradient stops
-    direction: GradientCoordinates,
-    brush: Option<ID2D1LinearGradientBrush>,
+    shape: GradientShape,
+    relative_to: GradientSpace,
+    brush: Option<GradientBrush>,
+}
+
+#[derive(Debug, Clone)]
+enum GradientShape {
+    Linear(GradientCoordinates),
+    Radial { center: [f32; 2], radius: f32 },
+    Conic { center: [f32; 2], start_angle: f32 },
+}
+
+#[derive(Debug, Clone)]
+enum GradientBrush {
+    Linear(ID2D1LinearGradientBrush),
+    Radial(ID2D1RadialGradientBrush),
+    // Direct2D has no conic gradient brush, so we pre-render the gradient into a bitmap. The
+    // brush's transform stretches the bitmap over the span, which has to be combined with any
+    // transform set from outside (e.g. the spiral animation's rotation).
+    Conic {
+        id2d1_brush: ID2D1BitmapBrush,
+        span_transform: Cell<Matrix3x2>,
+        extra_transform: Cell<Matrix3x2>,
+    },
+}
+
+impl GradientBrush {
+    fn as_brush(&self) -> &ID2D1Brush {
+        match self {
+            GradientBrush::Linear(id2d1_brush) => id2d1_brush.into(),
+            GradientBrush::Radial(id2d1_brush) => id2d1_brush.into(),
+            GradientBrush::Conic { id2d1_brush, .. } => id2d1_brush.into(),
+        }
+    }
+
+    fn set_transform(&self, transform: &Matrix3x2) {
+        match self {
+            GradientBrush::Conic {
+                id2d1_brush,
+                span_transform,
+                extra_transform,
+            } => {
+                extra_transform.set(*transform);
+                unsafe { id2d1_brush.SetTransform(&(span_transform.get() * transform)) };
+            }
+            _ => unsafe { self.as_brush().SetTransform(transform) },
+        }
+    }
 }
 
 impl ColorConfig {
     // Convert the ColorConfig struct to a Color struct
-    pub fn to_color(&self, is_active_color: bool) -> Color {
+    pub fn to_color(&self, is_active_color: bool, color_space: ColorSpace) -> Color {
         match self {
-            ColorConfig::SolidConfig(solid_config) => {
-                if solid_config == "accent" {
-                    Color::Solid(Solid {
-                        color: get_accent_color(is_active_color),
-                        brush: None,
-                    })
-                } else {
-                    Color::Solid(Solid {
-                        color: get_color_from_hex(solid_config.as_str()),
-                        brush: None,
+            ColorConfig::SolidConfig(solid_config) => Color::Solid(Solid {
+                color: get_color_from_string(solid_config, is_active_color),
+                brush: None,
+                keyframes: None,
+            }),
+            ColorConfig::KeyframesConfig(keyframes_config) => {
+                let mut frames: Vec<(f32, D2D1_COLOR_F)> = keyframes_config
+                    .keyframes
+                    .iter()
+                    .filter_map(|keyframe| {
+                        let Some(at) = parse_duration_secs(&keyframe.at) else {
+                            error!("config contains an invalid keyframe time: {}", keyframe.at);
+                            return None;
+                        };
+
+                        Some((at, get_color_from_string(&keyframe.color, is_active_color)))
                     })
-                }
+                    .collect();
+                frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let Some(&(_, first_color)) = frames.first() else {
+                    error!("config contains keyframes without any valid keyframe!");
+                    return Color::default();
+                };
+
+                Color::Solid(Solid {
+                    color: first_color,
+                    brush: None,
+                    keyframes: Some(Keyframes {
+                        frames,
+                        is_looping: keyframes_config.is_looping,
+                        color_space,
+                        start_time: time::Instant::now(),
+                    }),
+                })
             }
             ColorConfig::GradientConfig(gradient_config) => {
-                // We use 'step' to calculate the position of each color in the gradient below
-                let step = 1.0 / (gradient_config.colors.len() - 1) as f32;
-
-                let gradient_stops = gradient_config
-                    .clone()
-                    .colors
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, color)| D2D1_GRADIENT_STOP {
-                        position: i as f32 * step,
-                        color: if color == "accent" {
-                            get_accent_color(is_active_color)
-                        } else {
-                            get_color_from_hex(color.as_str())
-                        },
-                    })
-                    .collect();
+                let gradient_stops = get_gradient_stops(
+                    &gradient_config.colors,
+                    is_active_color,
+                    gradient_config.interpolation.unwrap_or(color_space),
+                );
 
                 let direction = match gradient_config.direction {
                     // If we have an angle, we need to convert it into Coordinates
                     GradientDirection::Angle(ref angle) => {
-                        let Some(degree) = angle
-                            .strip_suffix("deg")
-                            .and_then(|d| d.trim().parse::<f32>().ok())
-                        else {
+                        let Some(degree) = parse_degrees(angle) else {
                             error!("config contains an invalid gradient direction!");
                             return Color::default();
                         };
 
-                        // We multiply degree by -1 to account for the fact that Win32's coordinate
-                        // system has its origin at the top left instead of the bottom left
-                        let rad = -degree * PI / 180.0;
-
-                        // Calculate the slope of the line whilst accounting for edge cases like 90
-                        // and 270 degrees where we would otherwise be dividing by 0 or something
-                        // close to 0.
-                        let m = match degree.abs() % 360.0 {
-                            90.0 | 270.0 => degree.signum() * f32::MAX,
-                            _ => rad.sin() / rad.cos(),
-                        };
-
-                        // y - y_p = m(x - x_p);
-                        // y = m(x - x_p) + y_p;
-                        // y = m*x - m*x_p + y_p;
-                        // b = -m*x_p + y_p;
-
-                        // Calculate the y-intercept of the line such that it goes through the
-                        // center point (0.5, 0.5)
-                        let b = -m * 0.5 + 0.5;
-
-                        // Create the line with the given slope and y-intercept
-                        let line = Line { m, b };
-
-                        // y = mx + b
-                        // 0 = mx + b
-                        // mx = -b
-                        // x = -b/m
-
-                        // y = mx + b
-                        // 1 = mx + b
-                        // mx = 1 - b
-                        // x = (1 - b)/m
-
-                        // When we cross certain angle thresholds, like 90 degrees, we need to flip
-                        // the x values (0.0 and 1.0) that we use to the calculate the start and
-                        // end points below due to the slope changing
-                        let (x_s, x_e) = match degree.abs() % 360.0 {
-                            0.0..90.0 => (0.0, 1.0),
-                            90.0..270.0 => (1.0, 0.0),
-                            270.0..360.0 => (0.0, 1.0),
-                            _ => {
-                                debug!("reached a gradient angle that is not covered by the match statement in colors.rs");
-                                (0.0, 1.0)
-                            }
-                        };
-
-                        // Here, we are checking three cases to make sure the calculated point
-                        // lies within the first quadrant:
-                        //
-                        // Case 1: the y-coordinate at x_s is between 0 and 1
-                        // Case 2: the y-coordinate at x_s is greater than 1
-                        // Case 3: the y-coordinate at x_s is less than 0
-                        let start = match line.plug_in_x(x_s) {
-                            0.0..=1.0 => [x_s, line.plug_in_x(x_s)],
-                            1.0.. => [(1.0 - line.b) / line.m, 1.0],
-                            _ => [-line.b / line.m, 0.0],
-                        };
-
-                        let end = match line.plug_in_x(x_e) {
-                            0.0..=1.0 => [x_e, line.plug_in_x(x_e)],
-                            1.0.. => [(1.0 - line.b) / line.m, 1.0],
-                            _ => [-line.b / line.m, 0.0],
-                        };
-
-                        GradientCoordinates { start, end }
+                        gradient_coordinates_from_angle(degree)
                     }
                     GradientDirection::Coordinates(ref coordinates) => coordinates.clone(),
                 };
 
                 Color::Gradient(Gradient {
                     gradient_stops,
-                    direction,
+                    shape: GradientShape::Linear(direction),
+                    relative_to: gradient_config.relative_to,
                     brush: None,
                 })
             }
+            ColorConfig::RadialGradientConfig(radial_config) => Color::Gradient(Gradient {
+                gradient_stops: get_gradient_stops(
+                    &radial_config.colors,
+                    is_active_color,
+                    radial_config.interpolation.unwrap_or(color_space),
+                ),
+                shape: GradientShape::Radial {
+                    center: radial_config.center,
+                    radius: radial_config.radius,
+                },
+                relative_to: radial_config.relative_to,
+                brush: None,
+            }),
+            ColorConfig::ConicGradientConfig(conic_config) => {
+                let Some(start_angle) = parse_degrees(&conic_config.start_angle) else {
+                    error!("config contains an invalid gradient start_angle!");
+                    return Color::default();
+                };
+
+                Color::Gradient(Gradient {
+                    gradient_stops: get_gradient_stops(
+                        &conic_config.colors,
+                        is_active_color,
+                        conic_config.interpolation.unwrap_or(color_space),
+                    ),
+                    shape: GradientShape::Conic {
+                        center: conic_config.center,
+                        start_angle,
+                    },
+                    relative_to: conic_config.relative_to,
+                    brush: None,
+                })
+            }
+            ColorConfig::ThemedConfig(themed_config) => {
+                let color_config = match is_light_theme() {
+                    true => &themed_config.light,
+                    false => &themed_config.dark,
+                };
+                color_config.to_color(is_active_color, color_space)
+            }
         }
     }
 }
 
+// Parse an angle like "45deg"
+fn parse_degrees(angle: &str) -> Option<f32> {
+    angle
+        .strip_suffix("deg")
+        .and_then(|d| d.trim().parse::<f32>().ok())
+}
+
+// Spread the colors evenly from position 0.0 to 1.0
+fn get_gradient_stops(
+    colors: &[String],
+    is_active_color: bool,
+    color_space: ColorSpace,
+) -> Vec<D2D1_GRADIENT_STOP> {
+    // We use 'step' to calculate the position of each color in the gradient below
+    let step = 1.0 / (colors.len() - 1) as f32;
+
+    let gradient_stops: Vec<D2D1_GRADIENT_STOP> = colors
+        .iter()
+        .enumerate()
+        .map(|(i, color)| D2D1_GRADIENT_STOP {
+            position: i as f32 * step,
+            color: get_color_from_string(color, is_active_color),
+        })
+        .collect();
+
+    // Direct2D interpolates between gradient stops in sRGB, so for other color spaces we
+    // approximate the gradient by inserting extra stops between each pair of colors
+    match color_space {
+        ColorSpace::Srgb => gradient_stops,
+        _ => subdivide_gradient_stops(&gradient_stops, color_space),
+    }
+}
+
+// Convert a gradient angle (in degrees) into start and end points within the unit square, where
+// the gradient line passes through the center (0.5, 0.5)
+fn gradient_coordinates_from_angle(degree: f32) -> GradientCoordinates {
+    // We multiply degree by -1 to account for the fact that Win32's coordinate
+    // system has its origin at the top left instead of the bottom left
+    let rad = -degree * PI / 180.0;
+
+    // Calculate the slope of the line whilst accounting for edge cases like 90
+    // and 270 degrees where we would otherwise be dividing by 0 or something
+    // close to 0.
+    let m = match degree.abs() % 360.0 {
+        90.0 | 270.0 => degree.signum() * f32::MAX,
+        _ => rad.sin() / rad.cos(),
+    };
+
+    // y - y_p = m(x - x_p);
+    // y = m(x - x_p) + y_p;
+    // y = m*x - m*x_p + y_p;
+    // b = -m*x_p + y_p;
+
+    // Calculate the y-intercept of the line such that it goes through the
+    // center point (0.5, 0.5)
+    let b = -m * 0.5 + 0.5;
+
+    // Create the line with the given slope and y-intercept
+    let line = Line { m, b };
+
+    // y = mx + b
+    // 0 = mx + b
+    // mx = -b
+    // x = -b/m
+
+    // y = mx + b
+    // 1 = mx + b
+    // mx = 1 - b
+    // x = (1 - b)/m
+
+    // When we cross certain angle thresholds, like 90 degrees, we need to flip
+    // the x values (0.0 and 1.0) that we use to the calculate the start and
+    // end points below due to the slope changing
+    let (x_s, x_e) = match degree.abs() % 360.0 {
+        0.0..90.0 => (0.0, 1.0),
+        90.0..270.0 => (1.0, 0.0),
+        270.0..360.0 => (0.0, 1.0),
+        _ => {
+            debug!(
+                "reached a gradient angle that is not covered by the match statement in colors.rs"
+            );
+            (0.0, 1.0)
+        }
+    };
+
+    // Here, we are checking three cases to make sure the calculated point
+    // lies within the first quadrant:
+    //
+    // Case 1: the y-coordinate at x_s is between 0 and 1
+    // Case 2: the y-coordinate at x_s is greater than 1
+    // Case 3: the y-coordinate at x_s is less than 0
+    let start = match line.plug_in_x(x_s) {
+        0.0..=1.0 => [x_s, line.plug_in_x(x_s)],
+        1.0.. => [(1.0 - line.b) / line.m, 1.0],
+        _ => [-line.b / line.m, 0.0],
+    };
+
+    let end = match line.plug_in_x(x_e) {
+        0.0..=1.0 => [x_e, line.plug_in_x(x_e)],
+        1.0.. => [(1.0 - line.b) / line.m, 1.0],
+        _ => [-line.b / line.m, 0.0],
+    };
+
+    GradientCoordinates { start, end }
+}
+
+// Parse a duration like "2s", "1.5s", or "500ms" into seconds
+fn parse_duration_secs(duration: &str) -> Option<f32> {
+    let duration = duration.trim();
+
+    let secs = match duration.strip_suffix("ms") {
+        Some(ms) => ms.trim().parse::<f32>().ok()? / 1000.0,
+        None => duration
+            .strip_suffix('s')
+            .unwrap_or(duration)
+            .trim()
+            .parse::<f32>()
+            .ok()?,
+    };
+
+    (secs.is_finite() && secs >= 0.0).then_some(secs)
+}
+
+impl Keyframes {
+    fn get_current_color(&self) -> D2D1_COLOR_F {
+        let (last_at, last_color) = *self.frames.last().unwrap();
+        let mut elapsed = self.start_time.elapsed().as_secs_f32();
+
+        // When looping, we restart from the first keyframe once we pass the last one
+        if self.is_looping && last_at > 0.0 {
+            elapsed %= last_at;
+        }
+
+        // Find the pair of keyframes we're currently between and interpolate between them
+        for pair in self.frames.windows(2) {
+            let ((from_at, from_color), (to_at, to_color)) = (pair[0], pair[1]);
+
+            if elapsed < from_at {
+                return from_color;
+            }
+            if elapsed < to_at {
+                let t = (elapsed - from_at) / (to_at - from_at);
+                return interpolate_color(&from_color, &to_color, t, self.color_space);
+            }
+        }
+
+        match elapsed < self.frames[0].0 {
+            true => self.frames[0].1,
+            false => last_color,
+        }
+    }
+}
+
+// Size (in pixels) of the bitmap that conic gradients are rendered into. It's stretched over the
+// span with linear interpolation, so this doesn't need to match the window's size.
+const CONIC_BITMAP_SIZE: u32 = 256;
+// Number of stops used to approximate each segment of a gradient in a non-sRGB color space
+const SUBDIVISIONS_PER_SEGMENT: usize = 8;
+
+fn subdivide_gradient_stops(
+    gradient_stops: &[D2D1_GRADIENT_STOP],
+    color_space: ColorSpace,
+) -> Vec<D2D1_GRADIENT_STOP> {
+    let mut subdivided_stops = Vec::new();
+
+    for pair in gradient_stops.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+
+        for i in 0..SUBDIVISIONS_PER_SEGMENT {
+            let t = i as f32 / SUBDIVISIONS_PER_SEGMENT as f32;
+            subdivided_stops.push(D2D1_GRADIENT_STOP {
+                position: from.position + (to.position - from.position) * t,
+                color: interpolate_color(&from.color, &to.color, t, color_space),
+            });
+        }
+    }
+
+    // The loop above never pushes the last stop, so we do that here
+    subdivided_stops.extend(gradient_stops.last().copied());
+
+    subdivided_stops
+}
+
 #[derive(Debug)]
 struct Line {
     m: f32,
@@ -218,7 +547,7 @@ impl Line {
 impl Color {
     pub fn init_brush(
         &mut self,
-        render_target: &ID2D1HwndRenderTarget,
+        render_target: &ID2D1RenderTarget,
         window_rect: &RECT,
         brush_properties: &D2D1_BRUSH_PROPERTIES,
     ) -> windows::core::Result<()> {
@@ -233,35 +562,88 @@ impl Color {
                 Ok(())
             },
             Color::Gradient(gradient) => unsafe {
-                let width = (window_rect.right - window_rect.left) as f32;
-                let height = (window_rect.bottom - window_rect.top) as f32;
-
-                // The direction/GradientCoordinates only range from 0.0 to 1.0, but we need to
-                // convert it into coordinates in terms of pixels
-                let gradient_properties = D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES {
-                    startPoint: D2D_POINT_2F {
-                        x: gradient.direction.start[0] * width,
-                        y: gradient.direction.start[1] * height,
-                    },
-                    endPoint: D2D_POINT_2F {
-                        x: gradient.direction.end[0] * width,
-                        y: gradient.direction.end[1] * height,
-                    },
-                };
-
                 let gradient_stop_collection = render_target.CreateGradientStopCollection(
                     &gradient.gradient_stops,
                     D2D1_GAMMA_2_2,
                     D2D1_EXTEND_MODE_CLAMP,
                 )?;
 
-                let id2d1_brush = render_target.CreateLinearGradientBrush(
-                    &gradient_properties,
-                    Some(brush_properties),
-                    &gradient_stop_collection,
-                )?;
+                // Monitor-relative gradients get their real geometry before each render
+                let span = gradient.get_span(window_rect, None);
+                let id2d1_brush = match gradient.shape {
+                    GradientShape::Linear(ref direction) => {
+                        let (start_point, end_point) = get_start_end_points(direction, span);
+                        let gradient_properties = D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES {
+                            startPoint: start_point,
+                            endPoint: end_point,
+                        };
 
-                id2d1_brush.SetOpacity(0.0);
+                        GradientBrush::Linear(render_target.CreateLinearGradientBrush(
+                            &gradient_properties,
+                            Some(brush_properties),
+                            &gradient_stop_collection,
+                        )?)
+                    }
+                    GradientShape::Radial { center, radius } => {
+                        let (center, radius_x, radius_y) =
+                            get_radial_geometry(center, radius, span);
+                        let gradient_properties = D2D1_RADIAL_GRADIENT_BRUSH_PROPERTIES {
+                            center,
+                            gradientOriginOffset: D2D_POINT_2F::default(),
+                            radiusX: radius_x,
+                            radiusY: radius_y,
+                        };
+
+                        GradientBrush::Radial(render_target.CreateRadialGradientBrush(
+                            &gradient_properties,
+                            Some(brush_properties),
+                            &gradient_stop_collection,
+                        )?)
+                    }
+                    GradientShape::Conic {
+                        center,
+                        start_angle,
+                    } => {
+                        let pixels =
+                            render_conic_gradient(&gradient.gradient_stops, center, start_angle);
+                        let bitmap_properties = D2D1_BITMAP_PROPERTIES {
+                            pixelFormat: D2D1_PIXEL_FORMAT {
+                                format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                                alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                            },
+                            dpiX: 96.0,
+                            dpiY: 96.0,
+                        };
+                        let bitmap = render_target.CreateBitmap(
+                            D2D_SIZE_U {
+                                width: CONIC_BITMAP_SIZE,
+                                height: CONIC_BITMAP_SIZE,
+                            },
+                            Some(pixels.as_ptr() as _),
+                            CONIC_BITMAP_SIZE * 4,
+                            &bitmap_properties,
+                        )?;
+                        let bitmap_brush_properties = D2D1_BITMAP_BRUSH_PROPERTIES {
+                            extendModeX: D2D1_EXTEND_MODE_CLAMP,
+                            extendModeY: D2D1_EXTEND_MODE_CLAMP,
+                            interpolationMode: D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                        };
+
+                        let id2d1_brush = GradientBrush::Conic {
+                            id2d1_brush: render_target.CreateBitmapBrush(
+                                &bitmap,
+                                Some(&bitmap_brush_properties),
+                                Some(brush_properties),
+                            )?,
+                            span_transform: Cell::new(get_conic_transform(span)),
+                            extra_transform: Cell::new(Matrix3x2::identity()),
+                        };
+                        id2d1_brush.set_transform(&brush_properties.transform);
+                        id2d1_brush
+                    }
+                };
+
+                id2d1_brush.as_brush().SetOpacity(0.0);
                 gradient.brush = Some(id2d1_brush);
 
                 Ok(())
@@ -272,10 +654,7 @@ impl Color {
     pub fn get_brush(&self) -> Option<&ID2D1Brush> {
         match self {
             Color::Solid(solid) => solid.brush.as_ref().map(|id2d1_brush| id2d1_brush.into()),
-            Color::Gradient(gradient) => gradient
-                .brush
-                .as_ref()
-                .map(|id2d1_brush| id2d1_brush.into()),
+            Color::Gradient(gradient) => gradient.brush.as_ref().map(GradientBrush::as_brush),
         }
     }
 
@@ -283,7 +662,7 @@ impl Color {
         match self {
             Color::Gradient(gradient) => {
                 if let Some(ref id2d1_brush) = gradient.brush {
-                    unsafe { id2d1_brush.SetOpacity(opacity) }
+                    unsafe { id2d1_brush.as_brush().SetOpacity(opacity) }
                 }
             }
             Color::Solid(solid) => {
@@ -303,10 +682,110 @@ impl Color {
             Color::Gradient(gradient) => gradient
                 .brush
                 .as_ref()
-                .map(|id2d1_brush| unsafe { id2d1_brush.GetOpacity() }),
+                .map(|id2d1_brush| unsafe { id2d1_brush.as_brush().GetOpacity() }),
+        }
+    }
+
+    pub fn has_keyframes(&self) -> bool {
+        matches!(self, Color::Solid(solid) if solid.keyframes.is_some())
+    }
+
+    // Replace every color (including keyframes and gradient stops) with the result of map_fn. The
+    // brush has to be recreated afterwards to pick up the new colors.
+    pub fn map_colors(&mut self, map_fn: impl Fn(D2D1_COLOR_F) -> D2D1_COLOR_F) {
+        match self {
+            Color::Solid(solid) => {
+                solid.color = map_fn(solid.color);
+                if let Some(ref mut keyframes) = solid.keyframes {
+                    for (_, color) in keyframes.frames.iter_mut() {
+                        *color = map_fn(*color);
+                    }
+                }
+            }
+            Color::Gradient(gradient) => {
+                for gradient_stop in gradient.gradient_stops.iter_mut() {
+                    gradient_stop.color = map_fn(gradient_stop.color);
+                }
+            }
+        }
+    }
+
+    // Remap every color to stay distinguishable with the given type of color blindness. Like
+    // map_colors, the brush has to be recreated afterwards.
+    pub fn apply_color_blind_mode(&mut self, mode: ColorBlindMode) {
+        if mode != ColorBlindMode::Off {
+            self.map_colors(|color| remap_for_color_blindness(color, mode));
+        }
+    }
+
+    // Advance a keyframed color to the current time. Returns true if the color changed.
+    pub fn update_keyframes(&mut self) -> bool {
+        let Color::Solid(solid) = self else {
+            return false;
+        };
+        let Some(ref keyframes) = solid.keyframes else {
+            return false;
+        };
+
+        let color = keyframes.get_current_color();
+        if color == solid.color {
+            return false;
+        }
+
+        solid.color = color;
+        if let Some(ref id2d1_brush) = solid.brush {
+            unsafe { id2d1_brush.SetColor(&color) }
+        }
+
+        true
+    }
+
+    pub fn is_monitor_relative(&self) -> bool {
+        matches!(self, Color::Gradient(gradient) if gradient.relative_to == GradientSpace::Monitor)
+    }
+
+    // Temporarily draw a solid color's brush with a different color (e.g. while fading). This has no
+    // effect on gradients.
+    pub fn set_brush_color(&self, color: &D2D1_COLOR_F) {
+        if let Color::Solid(solid) = self {
+            if let Some(ref id2d1_brush) = solid.brush {
+                unsafe { id2d1_brush.SetColor(color) }
+            }
+        }
+    }
+
+    // Restore the brush to its configured color after set_brush_color()
+    pub fn reset_brush_color(&self) {
+        if let Color::Solid(solid) = self {
+            self.set_brush_color(&solid.color);
+        }
+    }
+
+    pub fn get_solid_color(&self) -> Option<D2D1_COLOR_F> {
+        match self {
+            Color::Solid(solid) => Some(solid.color),
+            Color::Gradient(_) => None,
         }
     }
 
+    // Approximate this color as a single COLORREF (0x00BBGGRR). Gradients use their first stop.
+    pub fn to_colorref(&self) -> COLORREF {
+        let color = match self {
+            Color::Solid(solid) => solid.color,
+            Color::Gradient(gradient) => gradient
+                .gradient_stops
+                .first()
+                .map(|stop| stop.color)
+                .unwrap_or_default(),
+        };
+
+        let r = (color.r * 255.0).round() as u32;
+        let g = (color.g * 255.0).round() as u32;
+        let b = (color.b * 255.0).round() as u32;
+
+        COLORREF(r | (g << 8) | (b << 16))
+    }
+
     pub fn set_transform(&self, transform: &Matrix3x2) {
         match self {
             Color::Solid(solid) => {
@@ -318,9 +797,7 @@ impl Color {
             }
             Color::Gradient(gradient) => {
                 if let Some(ref id2d1_brush) = gradient.brush {
-                    unsafe {
-                        id2d1_brush.SetTransform(transform);
-                    }
+                    id2d1_brush.set_transform(transform);
                 }
             }
         }
@@ -328,31 +805,409 @@ impl Color {
 }
 
 impl Gradient {
-    pub fn update_start_end_points(&self, window_rect: &RECT) {
-        let width = (window_rect.right - window_rect.left) as f32;
-        let height = (window_rect.bottom - window_rect.top) as f32;
-
-        // The direction/GradientCoordinates only range from 0.0 to 1.0, but we need to
-        // convert it into coordinates in terms of pixels
-        let start_point = D2D_POINT_2F {
-            x: self.direction.start[0] * width,
-            y: self.direction.start[1] * height,
-        };
-        let end_point = D2D_POINT_2F {
-            x: self.direction.end[0] * width,
-            y: self.direction.end[1] * height,
-        };
+    pub fn update_geometry(&self, window_rect: &RECT, monitor_rect: Option<&RECT>) {
+        let span = self.get_span(window_rect, monitor_rect);
+
+        match (&self.brush, &self.shape) {
+            (Some(GradientBrush::Linear(id2d1_brush)), GradientShape::Linear(direction)) => {
+                let (start_point, end_point) = get_start_end_points(direction, span);
+                unsafe {
+                    id2d1_brush.SetStartPoint(start_point);
+                    id2d1_brush.SetEndPoint(end_point)
+                };
+            }
+            (
+                Some(GradientBrush::Radial(id2d1_brush)),
+                GradientShape::Radial { center, radius },
+            ) => {
+                let (center, radius_x, radius_y) = get_radial_geometry(*center, *radius, span);
+                unsafe {
+                    id2d1_brush.SetCenter(center);
+                    id2d1_brush.SetRadiusX(radius_x);
+                    id2d1_brush.SetRadiusY(radius_y);
+                }
+            }
+            (
+                Some(
+                    id2d1_brush @ GradientBrush::Conic {
+                        span_transform,
+                        extra_transform,
+                        ..
+                    },
+                ),
+                GradientShape::Conic { .. },
+            ) => {
+                span_transform.set(get_conic_transform(span));
+                id2d1_brush.set_transform(&extra_transform.get());
+            }
+            _ => {}
+        }
+    }
+
+    // Get the rect (left, top, width, height) that the gradient spans, relative to the border
+    // window's top-left corner
+    fn get_span(&self, window_rect: &RECT, monitor_rect: Option<&RECT>) -> (f32, f32, f32, f32) {
+        match (self.relative_to, monitor_rect) {
+            (GradientSpace::Monitor, Some(monitor_rect)) => (
+                (monitor_rect.left - window_rect.left) as f32,
+                (monitor_rect.top - window_rect.top) as f32,
+                (monitor_rect.right - monitor_rect.left) as f32,
+                (monitor_rect.bottom - monitor_rect.top) as f32,
+            ),
+            _ => (
+                0.0,
+                0.0,
+                (window_rect.right - window_rect.left) as f32,
+                (window_rect.bottom - window_rect.top) as f32,
+            ),
+        }
+    }
+}
+
+fn get_start_end_points(
+    direction: &GradientCoordinates,
+    (left, top, width, height): (f32, f32, f32, f32),
+) -> (D2D_POINT_2F, D2D_POINT_2F) {
+    // The direction/GradientCoordinates only range from 0.0 to 1.0, but we need to
+    // convert it into coordinates in terms of pixels
+    let start_point = D2D_POINT_2F {
+        x: left + direction.start[0] * width,
+        y: top + direction.start[1] * height,
+    };
+    let end_point = D2D_POINT_2F {
+        x: left + direction.end[0] * width,
+        y: top + direction.end[1] * height,
+    };
+
+    (start_point, end_point)
+}
+
+// Convert a radial gradient's relative center and radius into a center point and x/y radii in
+// pixels
+fn get_radial_geometry(
+    center: [f32; 2],
+    radius: f32,
+    (left, top, width, height): (f32, f32, f32, f32),
+) -> (D2D_POINT_2F, f32, f32) {
+    let center_point = D2D_POINT_2F {
+        x: left + center[0] * width,
+        y: top + center[1] * height,
+    };
+
+    (center_point, radius * width, radius * height)
+}
+
+// Stretch the conic gradient's bitmap over the span
+fn get_conic_transform((left, top, width, height): (f32, f32, f32, f32)) -> Matrix3x2 {
+    Matrix3x2 {
+        M11: width / CONIC_BITMAP_SIZE as f32,
+        M12: 0.0,
+        M21: 0.0,
+        M22: height / CONIC_BITMAP_SIZE as f32,
+        M31: left,
+        M32: top,
+    }
+}
+
+// Render a conic gradient into premultiplied BGRA pixels for a CONIC_BITMAP_SIZE square bitmap.
+// The center is relative to the bitmap, like the gradient's span.
+fn render_conic_gradient(
+    gradient_stops: &[D2D1_GRADIENT_STOP],
+    center: [f32; 2],
+    start_angle: f32,
+) -> Vec<u8> {
+    let size = CONIC_BITMAP_SIZE as usize;
+    let to_u8 = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
 
-        if let Some(ref id2d1_brush) = self.brush {
-            unsafe {
-                id2d1_brush.SetStartPoint(start_point);
-                id2d1_brush.SetEndPoint(end_point)
-            };
+    let mut pixels = Vec::with_capacity(size * size * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let dx = (x as f32 + 0.5) / size as f32 - center[0];
+            let dy = (y as f32 + 0.5) / size as f32 - center[1];
+
+            // Measure the angle clockwise from straight up (y points down)
+            let angle = dx.atan2(-dy).to_degrees();
+            let position = (angle - start_angle).rem_euclid(360.0) / 360.0;
+            let color = sample_gradient_stops(gradient_stops, position);
+
+            pixels.extend([
+                to_u8(color.b * color.a),
+                to_u8(color.g * color.a),
+                to_u8(color.r * color.a),
+                to_u8(color.a),
+            ]);
         }
     }
+
+    pixels
+}
+
+// Get the color at the given position (0.0 to 1.0), interpolating in sRGB like Direct2D does
+fn sample_gradient_stops(gradient_stops: &[D2D1_GRADIENT_STOP], position: f32) -> D2D1_COLOR_F {
+    let Some(next_index) = gradient_stops
+        .iter()
+        .position(|stop| stop.position >= position)
+    else {
+        return gradient_stops
+            .last()
+            .map(|stop| stop.color)
+            .unwrap_or_default();
+    };
+    if next_index == 0 {
+        return gradient_stops[0].color;
+    }
+
+    let (from, to) = (&gradient_stops[next_index - 1], &gradient_stops[next_index]);
+    let t = (position - from.position) / (to.position - from.position);
+    interpolate_color(&from.color, &to.color, t, ColorSpace::Srgb)
 }
 
-fn get_accent_color(is_active_color: bool) -> D2D1_COLOR_F {
+// Conversions between RGB and LMS (the responses of the eye's long, medium, and short wavelength
+// cones), and the LMS projections for each type of color blindness. These are from Viénot et al.
+// and Fidaner et al., as used by the common "daltonize" algorithm.
+#[allow(clippy::excessive_precision)]
+const RGB_TO_LMS: [[f32; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+#[allow(clippy::excessive_precision)]
+const LMS_TO_RGB: [[f32; 3]; 3] = [
+    [0.0809444479, -0.130504409, 0.116721066],
+    [-0.0102485335, 0.0540193266, -0.113614708],
+    [-0.000365296938, -0.00412161469, 0.693511405],
+];
+
+fn get_color_blind_projection(mode: ColorBlindMode) -> [[f32; 3]; 3] {
+    match mode {
+        ColorBlindMode::Off => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        ColorBlindMode::Deuteranopia => {
+            [[1.0, 0.0, 0.0], [0.494207, 0.0, 1.24827], [0.0, 0.0, 1.0]]
+        }
+        ColorBlindMode::Protanopia => [[0.0, 2.02344, -2.52581], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        ColorBlindMode::Tritanopia => {
+            [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-0.395913, 0.801109, 0.0]]
+        }
+    }
+}
+
+fn multiply(matrix: &[[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+// Approximate how a color looks with the given type of color blindness
+fn simulate_color_blindness(color: D2D1_COLOR_F, mode: ColorBlindMode) -> [f32; 3] {
+    let lms = multiply(&RGB_TO_LMS, [color.r, color.g, color.b]);
+    let simulated_lms = multiply(&get_color_blind_projection(mode), lms);
+
+    multiply(&LMS_TO_RGB, simulated_lms)
+}
+
+// Shift the part of a color that's lost with the given type of color blindness into channels that
+// are still visible, so that e.g. red and green borders don't look the same for deuteranopes
+fn remap_for_color_blindness(color: D2D1_COLOR_F, mode: ColorBlindMode) -> D2D1_COLOR_F {
+    let simulated = simulate_color_blindness(color, mode);
+    let error = [
+        color.r - simulated[0],
+        color.g - simulated[1],
+        color.b - simulated[2],
+    ];
+
+    D2D1_COLOR_F {
+        r: color.r,
+        g: (color.g + 0.7 * error[0] + error[1]).clamp(0.0, 1.0),
+        b: (color.b + 0.7 * error[0] + error[2]).clamp(0.0, 1.0),
+        a: color.a,
+    }
+}
+
+// Parse a color from the config, which is a hex code, a color function like hsl(), a CSS color
+// name, or one of the accent color keywords
+fn get_color_from_string(color: &str, is_active_color: bool) -> D2D1_COLOR_F {
+    match color {
+        "accent" => get_accent_color(is_active_color, false),
+        "accent_translucent" => get_accent_color(is_active_color, true),
+        function if function.ends_with(')') => get_color_from_function(function),
+        other => get_named_color(other).unwrap_or_else(|| get_color_from_hex(other)),
+    }
+}
+
+// The named colors from CSS as 0xRRGGBB, sorted by name so they can be binary searched
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+// Look up a CSS color name like "tomato" or "RebeccaPurple" (names are case-insensitive)
+fn get_named_color(name: &str) -> Option<D2D1_COLOR_F> {
+    let name = name.trim().to_ascii_lowercase();
+    if name == "transparent" {
+        return Some(D2D1_COLOR_F::default());
+    }
+
+    let index = NAMED_COLORS
+        .binary_search_by_key(&name.as_str(), |&(color_name, _)| color_name)
+        .ok()?;
+    let rgb = NAMED_COLORS[index].1;
+
+    Some(D2D1_COLOR_F {
+        r: ((rgb >> 16) & 0xff) as f32 / 255.0,
+        g: ((rgb >> 8) & 0xff) as f32 / 255.0,
+        b: (rgb & 0xff) as f32 / 255.0,
+        a: 1.0,
+    })
+}
+
+// How much of the accent color is mixed into the inactive accent color at 50% colorization intensity
+const INACTIVE_ACCENT_SHARE: f32 = 0.1;
+
+fn get_accent_color(is_active_color: bool, is_translucent: bool) -> D2D1_COLOR_F {
     let mut pcr_colorization: u32 = 0;
     let mut pf_opaqueblend: BOOL = FALSE;
 
@@ -367,19 +1222,35 @@ fn get_accent_color(is_active_color: bool) -> D2D1_COLOR_F {
     let accent_blue = (pcr_colorization & 0x000000FF) as f32 / 255.0;
     let accent_avg = (accent_red + accent_green + accent_blue) / 3.0;
 
+    // The colorization color's alpha is only meaningful while transparency effects are enabled in
+    // Windows' personalization settings and DWM isn't blending it opaquely. Otherwise, Windows draws
+    // the accent color opaque too.
+    let accent_alpha =
+        match is_translucent && is_transparency_enabled() && !pf_opaqueblend.as_bool() {
+            true => ((pcr_colorization & 0xFF000000) >> 24) as f32 / 255.0,
+            false => 1.0,
+        };
+
     if is_active_color {
         D2D1_COLOR_F {
             r: accent_red,
             g: accent_green,
             b: accent_blue,
-            a: 1.0,
+            a: accent_alpha,
         }
     } else {
+        // Inactive title bars are mostly gray with a hint of the accent color, and a higher
+        // colorization intensity makes that hint stronger
+        let accent_share = get_colorization_intensity()
+            .map_or(INACTIVE_ACCENT_SHARE, |intensity| {
+                intensity * 2.0 * INACTIVE_ACCENT_SHARE
+            });
+
         D2D1_COLOR_F {
-            r: accent_avg / 1.5 + accent_red / 10.0,
-            g: accent_avg / 1.5 + accent_green / 10.0,
-            b: accent_avg / 1.5 + accent_blue / 10.0,
-            a: 1.0,
+            r: accent_avg / 1.5 + accent_red * accent_share,
+            g: accent_avg / 1.5 + accent_green * accent_share,
+            b: accent_avg / 1.5 + accent_blue * accent_share,
+            a: accent_alpha,
         }
     }
 }
@@ -444,3 +1315,482 @@ fn get_color_from_hex(hex: &str) -> D2D1_COLOR_F {
 
     D2D1_COLOR_F { r, g, b, a }
 }
+
+// Parse rgb(r, g, b), hsl(h, s%, l%), and hsv(h, s%, v%), along with their rgba()/hsla()/hsva()
+// forms that take an alpha (0.0 to 1.0, or a percentage) as a fourth argument. RGB channels range
+// from 0 to 255 (or 0% to 100%), and the hue can have a 'deg' suffix.
+//
+// We also parse the perceptual oklab(l, a, b), oklch(l, c, h), and lab(l, a, b) functions, which
+// accept an optional alpha too. Lightness is 0.0 to 1.0 for Oklab/Oklch and 0 to 100 for (CIE) Lab,
+// or a percentage for either.
+fn get_color_from_function(function: &str) -> D2D1_COLOR_F {
+    let parse_color = || -> Option<D2D1_COLOR_F> {
+        let (name, args) = function.strip_suffix(')')?.split_once('(')?;
+        let args: Vec<&str> = args.split(',').map(str::trim).collect();
+
+        let parse_number = |arg: &str| arg.parse::<f32>().ok().filter(|value| value.is_finite());
+        let parse_percent = |arg: &str| -> Option<f32> {
+            let value = parse_number(arg.strip_suffix('%').unwrap_or(arg).trim())?;
+            Some((value / 100.0).clamp(0.0, 1.0))
+        };
+        let parse_alpha = |arg: &str| -> Option<f32> {
+            match arg.ends_with('%') {
+                true => parse_percent(arg),
+                false => parse_number(arg).map(|alpha| alpha.clamp(0.0, 1.0)),
+            }
+        };
+        let parse_hue = |arg: &str| parse_number(arg.strip_suffix("deg").unwrap_or(arg).trim());
+        // RGB channels and lightness are a plain number up to 'max', or a percentage of 'max'
+        let parse_up_to = |arg: &str, max: f32| -> Option<f32> {
+            match arg.ends_with('%') {
+                true => parse_percent(arg).map(|value| value * max),
+                false => parse_number(arg).map(|value| value.clamp(0.0, max)),
+            }
+        };
+
+        let name = name.trim();
+        let (args, alpha) = match args.as_slice() {
+            [first, second, third] => ([*first, *second, *third], None),
+            [first, second, third, alpha] => ([*first, *second, *third], Some(parse_alpha(alpha)?)),
+            _ => return None,
+        };
+        // rgb(), hsl(), and hsv() only take an alpha in their rgba()/hsla()/hsva() forms
+        let alpha = match (name, alpha) {
+            ("rgb" | "hsl" | "hsv", Some(_)) | ("rgba" | "hsla" | "hsva", None) => return None,
+            (_, alpha) => alpha.unwrap_or(1.0),
+        };
+
+        match name {
+            "rgb" | "rgba" => Some(D2D1_COLOR_F {
+                r: parse_up_to(args[0], 255.0)? / 255.0,
+                g: parse_up_to(args[1], 255.0)? / 255.0,
+                b: parse_up_to(args[2], 255.0)? / 255.0,
+                a: alpha,
+            }),
+            "hsl" | "hsla" => Some(hsl_to_srgb(
+                parse_hue(args[0])?,
+                parse_percent(args[1])?,
+                parse_percent(args[2])?,
+                alpha,
+            )),
+            "hsv" | "hsva" => Some(hsv_to_srgb(
+                parse_hue(args[0])?,
+                parse_percent(args[1])?,
+                parse_percent(args[2])?,
+                alpha,
+            )),
+            "oklab" => Some(oklab_to_srgb(
+                [
+                    parse_up_to(args[0], 1.0)?,
+                    parse_number(args[1])?,
+                    parse_number(args[2])?,
+                ],
+                alpha,
+            )),
+            "oklch" => Some(oklch_to_srgb(
+                [
+                    parse_up_to(args[0], 1.0)?,
+                    parse_number(args[1])?.max(0.0),
+                    parse_hue(args[2])?,
+                ],
+                alpha,
+            )),
+            "lab" => Some(cielab_to_srgb(
+                [
+                    parse_up_to(args[0], 100.0)?,
+                    parse_number(args[1])?,
+                    parse_number(args[2])?,
+                ],
+                alpha,
+            )),
+            _ => None,
+        }
+    };
+
+    parse_color().unwrap_or_else(|| {
+        error!("invalid color function: {function}");
+        D2D1_COLOR_F {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_coordinates(degree: f32, start: [f32; 2], end: [f32; 2]) {
+        let coordinates = gradient_coordinates_from_angle(degree);
+        let is_close = |a: [f32; 2], b: [f32; 2]| {
+            (a[0] - b[0]).abs() < EPSILON && (a[1] - b[1]).abs() < EPSILON
+        };
+
+        assert!(
+            is_close(coordinates.start, start) && is_close(coordinates.end, end),
+            "{degree}deg: expected {start:?} -> {end:?}, got {:?} -> {:?}",
+            coordinates.start,
+            coordinates.end
+        );
+    }
+
+    #[test]
+    fn axis_aligned_angles() {
+        assert_coordinates(0.0, [0.0, 0.5], [1.0, 0.5]);
+        assert_coordinates(90.0, [0.5, 1.0], [0.5, 0.0]);
+        assert_coordinates(180.0, [1.0, 0.5], [0.0, 0.5]);
+        assert_coordinates(270.0, [0.5, 0.0], [0.5, 1.0]);
+    }
+
+    #[test]
+    fn diagonal_angles() {
+        assert_coordinates(45.0, [0.0, 1.0], [1.0, 0.0]);
+        assert_coordinates(135.0, [1.0, 1.0], [0.0, 0.0]);
+        assert_coordinates(225.0, [1.0, 0.0], [0.0, 1.0]);
+        assert_coordinates(315.0, [0.0, 0.0], [1.0, 1.0]);
+    }
+
+    #[test]
+    fn angles_hitting_left_and_right_edges() {
+        // tan(30deg) is less than 1, so the line exits through the left and right edges
+        let offset = 0.5 * (30.0f32).to_radians().tan();
+        assert_coordinates(30.0, [0.0, 0.5 + offset], [1.0, 0.5 - offset]);
+        assert_coordinates(150.0, [1.0, 0.5 + offset], [0.0, 0.5 - offset]);
+    }
+
+    #[test]
+    fn angles_hitting_top_and_bottom_edges() {
+        // tan(60deg) is greater than 1, so the line exits through the top and bottom edges
+        let offset = 0.5 / (60.0f32).to_radians().tan();
+        assert_coordinates(60.0, [0.5 - offset, 1.0], [0.5 + offset, 0.0]);
+        assert_coordinates(120.0, [0.5 + offset, 1.0], [0.5 - offset, 0.0]);
+    }
+
+    #[test]
+    fn angles_near_vertical() {
+        // Crossing 90 degrees should move the points smoothly instead of flipping them
+        let below = gradient_coordinates_from_angle(89.9);
+        let above = gradient_coordinates_from_angle(90.1);
+
+        assert_eq!(below.start[1], 1.0);
+        assert_eq!(above.start[1], 1.0);
+        assert!(below.start[0] < 0.5 && above.start[0] > 0.5);
+        assert!((below.start[0] - above.start[0]).abs() < 0.01);
+    }
+
+    #[test]
+    fn angles_wrap_around() {
+        assert_coordinates(360.0, [0.0, 0.5], [1.0, 0.5]);
+        assert_coordinates(450.0, [0.5, 1.0], [0.5, 0.0]);
+        assert_coordinates(720.0, [0.0, 0.5], [1.0, 0.5]);
+    }
+
+    #[test]
+    fn negative_angles() {
+        assert_coordinates(-45.0, [0.0, 0.0], [1.0, 1.0]);
+        assert_coordinates(-90.0, [0.5, 0.0], [0.5, 1.0]);
+        assert_coordinates(-180.0, [1.0, 0.5], [0.0, 0.5]);
+        assert_coordinates(-270.0, [0.5, 1.0], [0.5, 0.0]);
+    }
+
+    #[test]
+    fn points_stay_within_unit_square() {
+        for degree in (-720..=720).map(|d| d as f32 * 0.5) {
+            let coordinates = gradient_coordinates_from_angle(degree);
+
+            for point in [coordinates.start, coordinates.end] {
+                assert!(
+                    point
+                        .iter()
+                        .all(|&c| (-EPSILON..=1.0 + EPSILON).contains(&c)),
+                    "{degree}deg produced a point outside the unit square: {point:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn line_passes_through_center() {
+        for degree in (0..360).map(|d| d as f32) {
+            let coordinates = gradient_coordinates_from_angle(degree);
+            let midpoint = [
+                (coordinates.start[0] + coordinates.end[0]) / 2.0,
+                (coordinates.start[1] + coordinates.end[1]) / 2.0,
+            ];
+
+            assert!(
+                (midpoint[0] - 0.5).abs() < 1e-4 && (midpoint[1] - 0.5).abs() < 1e-4,
+                "{degree}deg is not centered: {midpoint:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn radial_config_is_not_mistaken_for_linear() {
+        let color_config: ColorConfig =
+            serde_yml::from_str("{ colors: ['#ffffff', '#000000'], radius: 0.75 }").unwrap();
+
+        let ColorConfig::RadialGradientConfig(radial_config) = color_config else {
+            panic!("expected a radial gradient, got {color_config:?}");
+        };
+        assert_eq!(radial_config.center, [0.5, 0.5]);
+        assert_eq!(radial_config.relative_to, GradientSpace::Window);
+    }
+
+    #[test]
+    fn radial_geometry_scales_with_span() {
+        let (center, radius_x, radius_y) =
+            get_radial_geometry([0.25, 0.5], 0.5, (-100.0, 20.0, 400.0, 200.0));
+
+        assert_eq!((center.x, center.y), (0.0, 120.0));
+        assert_eq!((radius_x, radius_y), (200.0, 100.0));
+    }
+
+    fn stop(position: f32, r: f32) -> D2D1_GRADIENT_STOP {
+        D2D1_GRADIENT_STOP {
+            position,
+            color: D2D1_COLOR_F {
+                r,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+        }
+    }
+
+    #[test]
+    fn sampling_interpolates_between_stops() {
+        let stops = [stop(0.0, 0.0), stop(0.5, 1.0), stop(1.0, 0.0)];
+
+        assert_eq!(sample_gradient_stops(&stops, 0.0).r, 0.0);
+        assert!((sample_gradient_stops(&stops, 0.25).r - 0.5).abs() < EPSILON);
+        assert_eq!(sample_gradient_stops(&stops, 0.5).r, 1.0);
+        assert!((sample_gradient_stops(&stops, 0.75).r - 0.5).abs() < EPSILON);
+        assert_eq!(sample_gradient_stops(&stops, 1.0).r, 0.0);
+    }
+
+    #[test]
+    fn conic_gradient_sweeps_clockwise_from_start_angle() {
+        let stops = [stop(0.0, 0.0), stop(1.0, 1.0)];
+        let size = CONIC_BITMAP_SIZE as usize;
+        // Red channel of the pixel at (x, y), which is the third byte in BGRA
+        let red_at = |pixels: &[u8], x: usize, y: usize| pixels[(y * size + x) * 4 + 2];
+
+        let pixels = render_conic_gradient(&stops, [0.5, 0.5], 0.0);
+        assert_eq!(pixels.len(), size * size * 4);
+        // Right of center is a quarter of the way around, below is halfway, and left is three
+        // quarters of the way around
+        assert!(red_at(&pixels, size - 1, size / 2).abs_diff(64) <= 2);
+        assert!(red_at(&pixels, size / 2, size - 1).abs_diff(128) <= 2);
+        assert!(red_at(&pixels, 0, size / 2).abs_diff(191) <= 2);
+
+        // Starting at 90deg puts the first color to the right of center instead of above it
+        let pixels = render_conic_gradient(&stops, [0.5, 0.5], 90.0);
+        assert!(red_at(&pixels, size / 2, size - 1).abs_diff(64) <= 2);
+    }
+
+    #[test]
+    fn conic_config_requires_start_angle() {
+        let color_config: ColorConfig = serde_yml::from_str(
+            "{ colors: ['#ffffff', '#000000'], start_angle: 45deg, relative_to: Monitor }",
+        )
+        .unwrap();
+        assert!(matches!(color_config, ColorConfig::ConicGradientConfig(_)));
+
+        assert!(serde_yml::from_str::<ColorConfig>("{ colors: ['#ffffff', '#000000'] }").is_err());
+    }
+
+    #[test]
+    fn themed_config_accepts_any_color_per_theme() {
+        let color_config: ColorConfig = serde_yml::from_str(
+            "{ light: '#ffffff', dark: { colors: ['#1e1e2e', '#89b4fa'], direction: 45deg } }",
+        )
+        .unwrap();
+
+        let ColorConfig::ThemedConfig(themed_config) = color_config else {
+            panic!("expected a themed color, got {color_config:?}");
+        };
+        assert_eq!(
+            *themed_config.light,
+            ColorConfig::SolidConfig("#ffffff".to_string())
+        );
+        assert!(matches!(
+            *themed_config.dark,
+            ColorConfig::GradientConfig(_)
+        ));
+
+        assert!(serde_yml::from_str::<ColorConfig>("{ light: '#ffffff' }").is_err());
+    }
+
+    fn assert_color(color: &str, [r, g, b, a]: [f32; 4]) {
+        let parsed = get_color_from_function(color);
+        let is_close = (parsed.r - r).abs() < 1e-3
+            && (parsed.g - g).abs() < 1e-3
+            && (parsed.b - b).abs() < 1e-3
+            && (parsed.a - a).abs() < 1e-3;
+
+        assert!(
+            is_close,
+            "{color}: expected {:?}, got {parsed:?}",
+            [r, g, b, a]
+        );
+    }
+
+    #[test]
+    fn parses_rgb_colors() {
+        assert_color(
+            "rgb(98, 114, 164)",
+            [98.0 / 255.0, 114.0 / 255.0, 164.0 / 255.0, 1.0],
+        );
+        assert_color(
+            "rgba(98,114,164,0.6)",
+            [98.0 / 255.0, 114.0 / 255.0, 164.0 / 255.0, 0.6],
+        );
+        assert_color("rgb(100%, 50%, 0%)", [1.0, 0.5, 0.0, 1.0]);
+        assert_color("rgba(300, -20, 0, 25%)", [1.0, 0.0, 0.0, 0.25]);
+    }
+
+    #[test]
+    fn parses_named_colors() {
+        assert_eq!(
+            get_named_color("tomato"),
+            Some(get_color_from_hex("#ff6347"))
+        );
+        assert_eq!(
+            get_named_color("RebeccaPurple"),
+            Some(get_color_from_hex("#663399"))
+        );
+        assert_eq!(
+            get_named_color("transparent").map(|color| color.a),
+            Some(0.0)
+        );
+        assert_eq!(get_named_color("#ff6347"), None);
+        assert_eq!(get_named_color("notacolor"), None);
+
+        // The lookup relies on the table being sorted
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn parses_hsl_colors() {
+        assert_color("hsl(0, 100%, 50%)", [1.0, 0.0, 0.0, 1.0]);
+        assert_color("hsl(120deg, 100%, 25%)", [0.0, 0.5, 0.0, 1.0]);
+        assert_color("hsl(210, 80%, 50%)", [0.1, 0.5, 0.9, 1.0]);
+        assert_color("hsl(-120, 100%, 50%)", [0.0, 0.0, 1.0, 1.0]);
+        assert_color("hsla(0, 0%, 100%, 0.5)", [1.0, 1.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn parses_hsv_colors() {
+        assert_color("hsv(240, 100%, 100%)", [0.0, 0.0, 1.0, 1.0]);
+        assert_color("hsv(60, 50%, 100%)", [1.0, 1.0, 0.5, 1.0]);
+        assert_color("hsva(300, 100%, 50%, 25%)", [0.5, 0.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn parses_perceptual_colors() {
+        assert_color("oklab(1, 0, 0)", [1.0, 1.0, 1.0, 1.0]);
+        assert_color("oklab(0.627955, 0.224863, 0.125846)", [1.0, 0.0, 0.0, 1.0]);
+        assert_color(
+            "oklch(62.7955%, 0.257683, 29.2339deg)",
+            [1.0, 0.0, 0.0, 1.0],
+        );
+        assert_color(
+            "oklch(0.45201, 0.313214, 264.052, 50%)",
+            [0.0, 0.0, 1.0, 0.5],
+        );
+        assert_color("lab(100, 0, 0)", [1.0, 1.0, 1.0, 1.0]);
+        assert_color("lab(0%, 0, 0)", [0.0, 0.0, 0.0, 1.0]);
+        assert_color("lab(54.2917, 80.8125, 69.8851)", [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn gradient_interpolation_overrides_color_space() {
+        let color_config: ColorConfig = serde_yml::from_str(
+            "{ colors: ['#ff0000', '#0000ff'], direction: 90deg, interpolation: Oklch }",
+        )
+        .unwrap();
+
+        let Color::Gradient(gradient) = color_config.to_color(true, ColorSpace::Srgb) else {
+            panic!("expected a gradient");
+        };
+        let stops = &gradient.gradient_stops;
+        assert_eq!(stops.len(), SUBDIVISIONS_PER_SEGMENT + 1);
+
+        // Red to blue in Oklch goes through a saturated purple instead of a muddy midpoint
+        let midpoint = stops[SUBDIVISIONS_PER_SEGMENT / 2].color;
+        assert!(
+            midpoint.r > 0.6 && midpoint.b > 0.6 && midpoint.g < 0.1,
+            "{midpoint:?}"
+        );
+    }
+
+    fn get_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn color_blind_mode_separates_confusable_colors() {
+        let red = get_color_from_hex("#e03030");
+        let green = get_color_from_hex("#30a030");
+
+        for mode in [ColorBlindMode::Deuteranopia, ColorBlindMode::Protanopia] {
+            let original_distance = get_distance(
+                simulate_color_blindness(red, mode),
+                simulate_color_blindness(green, mode),
+            );
+            let remapped_distance = get_distance(
+                simulate_color_blindness(remap_for_color_blindness(red, mode), mode),
+                simulate_color_blindness(remap_for_color_blindness(green, mode), mode),
+            );
+
+            assert!(
+                remapped_distance > original_distance,
+                "{mode:?}: {remapped_distance} is not more distinguishable than {original_distance}"
+            );
+        }
+    }
+
+    #[test]
+    fn color_blind_mode_keeps_grays_and_alpha() {
+        let gray = D2D1_COLOR_F {
+            r: 0.5,
+            g: 0.5,
+            b: 0.5,
+            a: 0.25,
+        };
+
+        for mode in [
+            ColorBlindMode::Deuteranopia,
+            ColorBlindMode::Protanopia,
+            ColorBlindMode::Tritanopia,
+        ] {
+            let remapped = remap_for_color_blindness(gray, mode);
+            assert!(
+                get_distance([remapped.r, remapped.g, remapped.b], [0.5, 0.5, 0.5]) < 0.01,
+                "{mode:?}: {remapped:?}"
+            );
+            assert_eq!(remapped.a, 0.25);
+        }
+    }
+
+    #[test]
+    fn invalid_color_functions_fall_back_to_white() {
+        for color in [
+            "hsl(0, 100%)",
+            "hsla(0, 100%, 50%)",
+            "rgb(255, 0, 0, 0.5)",
+            "rgba(255, 0, 0)",
+            "cmyk(0, 1, 1, 0)",
+            "hsl(red, 1, 1)",
+            "oklch(0.5, 0.1)",
+            "lab(50, a, 0)",
+        ] {
+            assert_color(color, [1.0, 1.0, 1.0, 1.0]);
+        }
+    }
+}
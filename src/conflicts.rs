@@ -0,0 +1,189 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::mem::size_of;
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{CloseHandle, E_ACCESSDENIED, FALSE, HMODULE, HWND, RECT};
+use windows::Win32::System::ProcessStatus::{
+    EnumProcessModulesEx, GetModuleBaseNameW, LIST_MODULES_ALL,
+};
+use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+use windows::Win32::UI::WindowsAndMessaging::{
+    FindWindowExW, GetWindowRect, GetWindowThreadProcessId, IsWindow,
+};
+
+use crate::utils::{
+    get_visible_window_rect, is_window_access_denied, is_window_visible,
+    record_access_denied_window, LogIfErr, ProcessAccess,
+};
+use crate::APP_STATE;
+
+// Modules injected by translucency/theming tools that are known to cause flickering with our
+// layered border windows. These must be lowercase.
+const CONFLICTING_MODULES: &[&str] = &[
+    "explorerblurmica.dll",
+    "translucentflyouts.dll",
+    "translucenttb.dll",
+    "windhawk.dll",
+];
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum ConflictMitigation {
+    // Only log a warning
+    #[default]
+    Ignore,
+    // Place the border directly below the tracking window instead of above it
+    BelowWindow,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub enum AlwaysOnTopFrame {
+    // Draw the border as usual, overlapping the frame
+    #[default]
+    Ignore,
+    // Hide the border while the window is pinned
+    Hide,
+    // Move the border outward so that it surrounds the frame
+    Nest,
+}
+
+// Class of the frame window that PowerToys' Always On Top draws around pinned windows
+const ALWAYS_ON_TOP_FRAME_CLASS: PCWSTR = w!("AlwaysOnTop_Border");
+// The frame is only a few pixels thick, so anything larger is probably framing another window
+const MAX_ALWAYS_ON_TOP_FRAME_WIDTH: i32 = 64;
+
+// Return the width of the PowerToys Always On Top frame around the window, if it has one. The
+// frame is a separate window that isn't owned by the pinned window, so we find it by looking for a
+// frame window that surrounds the window evenly on all sides.
+pub fn get_always_on_top_frame_width(hwnd: HWND) -> Option<i32> {
+    let window_rect = get_visible_window_rect(hwnd)?;
+
+    let mut frame_hwnd = HWND::default();
+    loop {
+        frame_hwnd = unsafe {
+            FindWindowExW(
+                HWND::default(),
+                frame_hwnd,
+                ALWAYS_ON_TOP_FRAME_CLASS,
+                PCWSTR::null(),
+            )
+        }
+        .ok()?;
+
+        if !is_window_visible(frame_hwnd) {
+            continue;
+        }
+
+        let mut frame_rect = RECT::default();
+        if unsafe { GetWindowRect(frame_hwnd, &mut frame_rect) }.is_err() {
+            continue;
+        }
+
+        let frame_width = window_rect.left - frame_rect.left;
+        let is_surrounding = (0..=MAX_ALWAYS_ON_TOP_FRAME_WIDTH).contains(&frame_width)
+            && (window_rect.top - frame_rect.top - frame_width).abs() <= 1
+            && (frame_rect.right - window_rect.right - frame_width).abs() <= 1
+            && (frame_rect.bottom - window_rect.bottom - frame_width).abs() <= 1;
+
+        if is_surrounding {
+            return Some(frame_width);
+        }
+    }
+}
+
+// Return the name of the first known conflicting module loaded in the window's process, if any.
+// Scanning the modules is expensive, so the result is cached until the config is reloaded.
+pub fn find_conflicting_module(hwnd: HWND) -> Option<String> {
+    if let Some(module) = APP_STATE
+        .conflicting_modules
+        .lock()
+        .unwrap()
+        .get(&(hwnd.0 as isize))
+    {
+        return module.clone();
+    }
+
+    // Scan without holding the lock so other border threads aren't blocked on it
+    let module = scan_for_conflicting_module(hwnd);
+
+    let mut conflicting_modules = APP_STATE.conflicting_modules.lock().unwrap();
+    // Forget about windows that have since been destroyed so that reused handles aren't affected
+    conflicting_modules.retain(|&window, _| unsafe { IsWindow(HWND(window as _)) }.as_bool());
+    conflicting_modules.insert(hwnd.0 as isize, module.clone());
+
+    module
+}
+
+pub fn clear_conflicting_modules() {
+    APP_STATE.conflicting_modules.lock().unwrap().clear();
+}
+
+fn scan_for_conflicting_module(hwnd: HWND) -> Option<String> {
+    // Don't keep retrying processes that already denied us access (e.g. elevated ones)
+    if is_window_access_denied(hwnd, ProcessAccess::ReadModules) {
+        return None;
+    }
+
+    match get_process_modules(hwnd) {
+        Ok(modules) => modules
+            .into_iter()
+            .find(|module| CONFLICTING_MODULES.contains(&module.to_lowercase().as_str())),
+        Err(err) => {
+            // This is expected for elevated processes, so we don't log it as an error
+            debug!("could not check modules for {hwnd:?}: {err:#}");
+            None
+        }
+    }
+}
+
+fn get_process_modules(hwnd: HWND) -> anyhow::Result<Vec<String>> {
+    let mut process_id = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut process_id)) };
+
+    let process_handle = unsafe {
+        OpenProcess(
+            PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
+            FALSE,
+            process_id,
+        )
+    }
+    .inspect_err(|err| {
+        if err.code() == E_ACCESSDENIED {
+            record_access_denied_window(hwnd, ProcessAccess::ReadModules);
+        }
+    })
+    .context(format!("could not open process {process_id}"))?;
+
+    let mut module_handles: Vec<HMODULE> = vec![HMODULE::default(); 1024];
+    let mut bytes_needed = 0u32;
+
+    let enum_res = unsafe {
+        EnumProcessModulesEx(
+            process_handle,
+            module_handles.as_mut_ptr(),
+            (module_handles.len() * size_of::<HMODULE>()) as u32,
+            &mut bytes_needed,
+            LIST_MODULES_ALL,
+        )
+    };
+
+    let modules = enum_res.map(|_| {
+        let module_count = (bytes_needed as usize / size_of::<HMODULE>()).min(module_handles.len());
+
+        module_handles[..module_count]
+            .iter()
+            .filter_map(|module| {
+                let mut name_arr: [u16; 256] = [0; 256];
+                let name_len =
+                    unsafe { GetModuleBaseNameW(process_handle, *module, &mut name_arr) };
+
+                (name_len > 0).then(|| String::from_utf16_lossy(&name_arr[..name_len as usize]))
+            })
+            .collect()
+    });
+
+    unsafe { CloseHandle(process_handle) }.log_if_err();
+
+    modules.context(format!(
+        "could not enumerate modules of process {process_id}"
+    ))
+}
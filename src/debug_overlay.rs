@@ -0,0 +1,103 @@
+use windows::core::w;
+use windows::Win32::Graphics::Direct2D::Common::{D2D1_COLOR_F, D2D_RECT_F};
+use windows::Win32::Graphics::Direct2D::{
+    ID2D1RenderTarget, ID2D1SolidColorBrush, D2D1_DRAW_TEXT_OPTIONS_CLIP,
+};
+use windows::Win32::Graphics::DirectWrite::{
+    DWriteCreateFactory, IDWriteFactory, IDWriteTextFormat, DWRITE_FACTORY_TYPE_SHARED,
+    DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_WEIGHT_NORMAL,
+    DWRITE_MEASURING_MODE_NATURAL,
+};
+
+const FONT_SIZE: f32 = 12.0;
+// Distance (in pixels) between the text and the inner edge of the border's stroke
+const PADDING: f32 = 4.0;
+
+// Text drawn inside a border showing how it's rendering, for tuning animations and tracking down
+// rendering issues (see Config::debug_overlay)
+#[derive(Debug, Clone)]
+pub struct DebugOverlay {
+    text_format: IDWriteTextFormat,
+    brush: ID2D1SolidColorBrush,
+}
+
+#[derive(Debug)]
+pub struct RenderStats {
+    pub fps: Option<f32>,
+    pub frame_time: Option<f32>,
+    pub is_software: bool,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DebugOverlay {
+    pub fn new(render_target: &ID2D1RenderTarget) -> windows::core::Result<Self> {
+        unsafe {
+            let dwrite_factory: IDWriteFactory = DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?;
+            let text_format = dwrite_factory.CreateTextFormat(
+                w!("Consolas"),
+                None,
+                DWRITE_FONT_WEIGHT_NORMAL,
+                DWRITE_FONT_STYLE_NORMAL,
+                DWRITE_FONT_STRETCH_NORMAL,
+                FONT_SIZE,
+                w!(""),
+            )?;
+
+            let color = D2D1_COLOR_F {
+                r: 1.0,
+                g: 1.0,
+                b: 1.0,
+                a: 1.0,
+            };
+            let brush = render_target.CreateSolidColorBrush(&color, None)?;
+
+            Ok(Self { text_format, brush })
+        }
+    }
+
+    // Draw the stats in the top-left corner inside of the given stroke rect
+    pub fn draw(
+        &self,
+        render_target: &ID2D1RenderTarget,
+        stats: &RenderStats,
+        stroke_rect: &D2D_RECT_F,
+        stroke_width: f32,
+    ) {
+        let format_stat = |value: Option<f32>, unit: &str| match value {
+            Some(value) => format!("{value:.1} {unit}"),
+            None => format!("- {unit}"),
+        };
+        let text = format!(
+            "{}\n{}\n{}\n{}x{}",
+            format_stat(stats.fps, "fps"),
+            format_stat(stats.frame_time, "ms"),
+            match stats.is_software {
+                true => "software",
+                false => "hardware",
+            },
+            stats.width,
+            stats.height
+        );
+        let text: Vec<u16> = text.encode_utf16().collect();
+
+        let inset = stroke_width / 2.0 + PADDING;
+        let layout_rect = D2D_RECT_F {
+            left: stroke_rect.left + inset,
+            top: stroke_rect.top + inset,
+            right: stroke_rect.right - inset,
+            bottom: stroke_rect.bottom - inset,
+        };
+
+        unsafe {
+            render_target.DrawText(
+                &text,
+                &self.text_format,
+                &layout_rect,
+                &self.brush,
+                D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                DWRITE_MEASURING_MODE_NATURAL,
+            )
+        };
+    }
+}
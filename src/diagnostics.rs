@@ -0,0 +1,253 @@
+use anyhow::Context;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::thread;
+use windows::core::HSTRING;
+use windows::Foundation::Numerics::Matrix3x2;
+use windows::Win32::Foundation::{GENERIC_WRITE, RECT};
+use windows::Win32::Graphics::Direct2D::Common::{
+    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_PIXEL_FORMAT, D2D_RECT_F,
+};
+use windows::Win32::Graphics::Direct2D::{
+    D2D1_ANTIALIAS_MODE_PER_PRIMITIVE, D2D1_BRUSH_PROPERTIES, D2D1_RENDER_TARGET_PROPERTIES,
+    D2D1_RENDER_TARGET_TYPE_DEFAULT, D2D1_ROUNDED_RECT,
+};
+use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+use windows::Win32::Graphics::Imaging::{
+    CLSID_WICImagingFactory, GUID_ContainerFormatPng, GUID_WICPixelFormat32bppPBGRA, IWICBitmap,
+    IWICImagingFactory, WICBitmapCacheOnLoad, WICBitmapEncoderNoCache,
+};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+};
+
+use crate::border_config::Config;
+use crate::color_math::ColorSpace;
+use crate::colors::{Color, ColorConfig};
+use crate::utils::{count_access_denied_windows, list_borders, LogIfErr, ProcessAccess};
+use crate::APP_STATE;
+
+// Size of each brush preview (in pixels)
+const PREVIEW_WIDTH: u32 = 160;
+const PREVIEW_HEIGHT: u32 = 100;
+const PREVIEW_RADIUS: f32 = 8.0;
+
+// Write a summary of the current state along with a preview of every configured color into the
+// 'diagnostics' folder next to the config, so maintainers can see what a user's config produces
+// without having to reproduce their setup
+pub fn save_diagnostics() {
+    let _ = thread::spawn(|| {
+        // WIC is a COM library, so this thread needs COM initialized
+        if let Err(err) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.ok() {
+            error!("could not initialize com for diagnostics: {err}");
+            return;
+        }
+
+        match write_diagnostics() {
+            Ok(dir) => {
+                info!("saved diagnostics to {}", dir.display());
+                let _ = open::that(dir);
+            }
+            Err(err) => error!("could not save diagnostics: {err:#}"),
+        }
+    });
+}
+
+fn write_diagnostics() -> anyhow::Result<PathBuf> {
+    let dir = match Config::is_read_only() {
+        true => env::temp_dir().join("tacky-borders-diagnostics"),
+        false => Config::get_dir()?.join("diagnostics"),
+    };
+    fs::create_dir_all(&dir).context("could not create diagnostics folder")?;
+
+    fs::write(dir.join("summary.txt"), get_summary()).context("could not write summary")?;
+
+    let config = APP_STATE.config.load();
+    let color_space = config.global.color_space;
+    let border_width = config.global.border_width;
+
+    let mut previews: Vec<(String, &ColorConfig)> = vec![
+        ("global_active".to_string(), &config.global.active_color),
+        ("global_inactive".to_string(), &config.global.inactive_color),
+    ];
+    if let Some(ref last_active_color) = config.global.last_active_color {
+        previews.push(("global_last_active".to_string(), last_active_color));
+    }
+    for (index, rule) in config.window_rules.iter().enumerate() {
+        let rule_colors = [
+            ("active", &rule.active_color),
+            ("inactive", &rule.inactive_color),
+            ("last_active", &rule.last_active_color),
+        ];
+        for (name, color_config) in rule_colors {
+            if let Some(color_config) = color_config {
+                previews.push((format!("rule_{index}_{name}"), color_config));
+            }
+        }
+    }
+
+    let imaging_factory: IWICImagingFactory =
+        unsafe { CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER) }
+            .context("could not create wic imaging factory")?;
+
+    for (name, color_config) in previews {
+        let path = dir.join(format!("{name}.png"));
+        save_preview(
+            &imaging_factory,
+            color_config,
+            color_space,
+            border_width,
+            &path,
+        )
+        .context(format!("could not save preview {}", path.display()))
+        .log_if_err();
+    }
+
+    Ok(dir)
+}
+
+fn get_summary() -> String {
+    let mut summary = String::new();
+
+    let _ = writeln!(summary, "tacky-borders v{}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(summary, "config path: {:?}", Config::get_path().ok());
+    let _ = writeln!(summary, "read-only mode: {}", Config::is_read_only());
+    let _ = writeln!(summary, "suspended: {}", APP_STATE.is_suspended());
+    let _ = writeln!(summary, "on air: {}", APP_STATE.is_on_air());
+    let _ = writeln!(
+        summary,
+        "night light: {}",
+        APP_STATE.is_night_light_active()
+    );
+    let _ = writeln!(
+        summary,
+        "software rendering: {}",
+        APP_STATE.is_software_fallback_active()
+    );
+    let _ = writeln!(
+        summary,
+        "windows whose process can't be queried (e.g. elevated or UIPI): {}",
+        count_access_denied_windows(ProcessAccess::Query)
+    );
+    let _ = writeln!(
+        summary,
+        "windows whose process modules can't be read (e.g. elevated): {}",
+        count_access_denied_windows(ProcessAccess::ReadModules)
+    );
+
+    let _ = writeln!(summary, "\nborders:");
+    for border in list_borders() {
+        let _ = writeln!(
+            summary,
+            "  {:?} (border {:?}): visible: {}, monitor: {:?}, window rule: {:?}",
+            border.tracking_window,
+            border.border_window,
+            border.is_visible,
+            border.monitor,
+            border.window_rule_index
+        );
+    }
+
+    let _ = writeln!(summary, "\nconfig: {:#?}", APP_STATE.config.load());
+
+    summary
+}
+
+fn save_preview(
+    imaging_factory: &IWICImagingFactory,
+    color_config: &ColorConfig,
+    color_space: ColorSpace,
+    border_width: f32,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let render_target_properties = D2D1_RENDER_TARGET_PROPERTIES {
+        r#type: D2D1_RENDER_TARGET_TYPE_DEFAULT,
+        pixelFormat: D2D1_PIXEL_FORMAT {
+            format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+        },
+        dpiX: 96.0,
+        dpiY: 96.0,
+        ..Default::default()
+    };
+    let brush_properties = D2D1_BRUSH_PROPERTIES {
+        opacity: 1.0,
+        transform: Matrix3x2::identity(),
+    };
+    let preview_rect = RECT {
+        left: 0,
+        top: 0,
+        right: PREVIEW_WIDTH as i32,
+        bottom: PREVIEW_HEIGHT as i32,
+    };
+
+    unsafe {
+        let bitmap = imaging_factory.CreateBitmap(
+            PREVIEW_WIDTH,
+            PREVIEW_HEIGHT,
+            &GUID_WICPixelFormat32bppPBGRA,
+            WICBitmapCacheOnLoad,
+        )?;
+        let render_target = APP_STATE
+            .render_factory
+            .CreateWicBitmapRenderTarget(&bitmap, &render_target_properties)?;
+        render_target.SetAntialiasMode(D2D1_ANTIALIAS_MODE_PER_PRIMITIVE);
+
+        let mut color = color_config.to_color(true, color_space);
+        color.init_brush(&render_target, &preview_rect, &brush_properties)?;
+        color.set_opacity(1.0);
+        if let Color::Gradient(ref gradient) = color {
+            gradient.update_geometry(&preview_rect, None);
+        }
+
+        // Draw the stroke fully inside the preview, like an Inside border placement
+        let inset = border_width / 2.0;
+        let rounded_rect = D2D1_ROUNDED_RECT {
+            rect: D2D_RECT_F {
+                left: inset,
+                top: inset,
+                right: PREVIEW_WIDTH as f32 - inset,
+                bottom: PREVIEW_HEIGHT as f32 - inset,
+            },
+            radiusX: PREVIEW_RADIUS,
+            radiusY: PREVIEW_RADIUS,
+        };
+
+        render_target.BeginDraw();
+        render_target.Clear(None);
+        if let Some(brush) = color.get_brush() {
+            render_target.DrawRoundedRectangle(&rounded_rect, brush, border_width, None);
+        }
+        render_target.EndDraw(None, None)?;
+
+        encode_png(imaging_factory, &bitmap, path)
+    }
+}
+
+unsafe fn encode_png(
+    imaging_factory: &IWICImagingFactory,
+    bitmap: &IWICBitmap,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let stream = imaging_factory.CreateStream()?;
+    stream.InitializeFromFilename(&HSTRING::from(path.as_os_str()), GENERIC_WRITE.0)?;
+
+    let encoder = imaging_factory.CreateEncoder(&GUID_ContainerFormatPng, ptr::null())?;
+    encoder.Initialize(&stream, WICBitmapEncoderNoCache)?;
+
+    let mut frame = None;
+    encoder.CreateNewFrame(&mut frame, ptr::null_mut())?;
+    let frame = frame.context("could not create png frame")?;
+
+    frame.Initialize(None)?;
+    frame.SetSize(PREVIEW_WIDTH, PREVIEW_HEIGHT)?;
+    let mut pixel_format = GUID_WICPixelFormat32bppPBGRA;
+    frame.SetPixelFormat(&mut pixel_format)?;
+    frame.WriteSource(bitmap, ptr::null())?;
+    frame.Commit()?;
+
+    Ok(encoder.Commit()?)
+}
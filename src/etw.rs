@@ -0,0 +1,82 @@
+use std::sync::LazyLock;
+use std::time;
+use windows::core::{GUID, HSTRING};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::System::Diagnostics::Etw::{
+    EventProviderEnabled, EventRegister, EventWriteString, REGHANDLE,
+};
+
+// A minimal ETW provider so tacky-borders' events can be analyzed in WPA alongside DWM traces.
+// Enable it in a trace session (e.g. with WPR or `logman`) using the GUID below.
+const PROVIDER_GUID: GUID = GUID::from_u128(0x95f817d2_91e8_4d13_ad8a_9ed6223b87a4);
+
+// These match the TRACE_LEVEL_* values from evntrace.h
+const LEVEL_INFORMATION: u8 = 4;
+const LEVEL_VERBOSE: u8 = 5;
+
+// Keywords let trace sessions filter which kinds of events they want
+const KEYWORD_LIFETIME: u64 = 0x1;
+const KEYWORD_RENDER: u64 = 0x2;
+
+static PROVIDER_HANDLE: LazyLock<Option<REGHANDLE>> = LazyLock::new(|| {
+    let mut handle = 0u64;
+
+    match unsafe { EventRegister(&PROVIDER_GUID, None, None, &mut handle) } {
+        0 => Some(REGHANDLE(handle as i64)),
+        err => {
+            error!("could not register etw provider: {err}");
+            None
+        }
+    }
+});
+
+pub enum TraceEvent {
+    BorderCreated(HWND),
+    BorderDestroyed(HWND),
+    Render(HWND, time::Duration),
+    RenderTargetRecreated(HWND),
+}
+
+impl TraceEvent {
+    fn get_level_and_keyword(&self) -> (u8, u64) {
+        match self {
+            TraceEvent::BorderCreated(_) | TraceEvent::BorderDestroyed(_) => {
+                (LEVEL_INFORMATION, KEYWORD_LIFETIME)
+            }
+            TraceEvent::Render(..) => (LEVEL_VERBOSE, KEYWORD_RENDER),
+            TraceEvent::RenderTargetRecreated(_) => (LEVEL_INFORMATION, KEYWORD_RENDER),
+        }
+    }
+
+    fn get_message(&self) -> String {
+        match self {
+            TraceEvent::BorderCreated(hwnd) => format!("BorderCreated tracking_window={hwnd:?}"),
+            TraceEvent::BorderDestroyed(hwnd) => {
+                format!("BorderDestroyed tracking_window={hwnd:?}")
+            }
+            TraceEvent::Render(hwnd, duration) => format!(
+                "Render tracking_window={hwnd:?} duration_us={}",
+                duration.as_micros()
+            ),
+            TraceEvent::RenderTargetRecreated(hwnd) => {
+                format!("RenderTargetRecreated tracking_window={hwnd:?}")
+            }
+        }
+    }
+}
+
+// Write an event if a trace session is listening. This is cheap when no one is listening, since
+// we skip formatting the message entirely.
+pub fn write_event(event: TraceEvent) {
+    let Some(handle) = *PROVIDER_HANDLE else {
+        return;
+    };
+
+    let (level, keyword) = event.get_level_and_keyword();
+    if !unsafe { EventProviderEnabled(handle, level, keyword) }.as_bool() {
+        return;
+    }
+
+    let message = HSTRING::from(event.get_message());
+    unsafe { EventWriteString(handle, level, keyword, &message) };
+}
@@ -1,23 +1,117 @@
 use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
 use std::thread;
 use std::time;
 use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
 use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
 use windows::Win32::UI::WindowsAndMessaging::{
     CHILDID_SELF, EVENT_OBJECT_CLOAKED, EVENT_OBJECT_DESTROY, EVENT_OBJECT_HIDE,
-    EVENT_OBJECT_LOCATIONCHANGE, EVENT_OBJECT_REORDER, EVENT_OBJECT_SHOW, EVENT_OBJECT_UNCLOAKED,
-    EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_MINIMIZEEND, EVENT_SYSTEM_MINIMIZESTART, OBJID_CURSOR,
-    OBJID_WINDOW,
+    EVENT_OBJECT_LOCATIONCHANGE, EVENT_OBJECT_NAMECHANGE, EVENT_OBJECT_REORDER, EVENT_OBJECT_SHOW,
+    EVENT_OBJECT_UNCLOAKED, EVENT_SYSTEM_DESKTOPSWITCH, EVENT_SYSTEM_FOREGROUND,
+    EVENT_SYSTEM_MINIMIZEEND, EVENT_SYSTEM_MINIMIZESTART, EVENT_SYSTEM_MOVESIZEEND,
+    EVENT_SYSTEM_MOVESIZESTART, OBJID_CURSOR, OBJID_WINDOW,
 };
 
+use crate::game_mode::update_game_mode;
 use crate::utils::{
-    destroy_border_for_window, get_border_for_window, get_foreground_window,
-    hide_border_for_window, is_window_visible, post_message_w, send_notify_message_w,
-    show_border_for_window, LogIfErr, WM_APP_FOREGROUND, WM_APP_LOCATIONCHANGE, WM_APP_MINIMIZEEND,
-    WM_APP_MINIMIZESTART, WM_APP_REORDER,
+    create_deferred_border, create_uncovered_deferred_borders, destroy_border_for_window,
+    get_border_for_window, get_foreground_window, hide_border_for_window,
+    is_hovering_maximize_button, is_task_view_window, is_window_visible, is_xaml_host_window,
+    post_message_w, send_notify_message_w, show_border_for_window, AppMessage, LogIfErr,
 };
-use crate::APP_STATE;
+use crate::window_system::{Win32WindowSystem, WindowSystem};
+use crate::{SuspendReason, APP_STATE};
 
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+pub enum UpdateAction {
+    // Re-render the border without changing anything else
+    Render,
+    // Update the border's position and size (like EVENT_OBJECT_LOCATIONCHANGE)
+    #[default]
+    Reposition,
+    // Re-check whether the window is active and update the border's color (like
+    // EVENT_SYSTEM_FOREGROUND)
+    Recolor,
+}
+
+impl UpdateAction {
+    fn to_app_message(self) -> AppMessage {
+        match self {
+            UpdateAction::Render => AppMessage::Render,
+            UpdateAction::Reposition => AppMessage::LocationChange,
+            UpdateAction::Recolor => AppMessage::Foreground,
+        }
+    }
+}
+
+// An additional WinEvent that updates the border of the window it's raised for. This is meant for
+// apps with nonstandard event behavior. It can be given as just the event id, which repositions the
+// border, or with an explicit action.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ExtraUpdateEvent {
+    Event(u32),
+    EventWithAction {
+        event: u32,
+        #[serde(default)]
+        action: UpdateAction,
+    },
+}
+
+impl ExtraUpdateEvent {
+    fn event(&self) -> u32 {
+        match *self {
+            ExtraUpdateEvent::Event(event) => event,
+            ExtraUpdateEvent::EventWithAction { event, .. } => event,
+        }
+    }
+
+    fn action(&self) -> UpdateAction {
+        match *self {
+            ExtraUpdateEvent::Event(_) => UpdateAction::default(),
+            ExtraUpdateEvent::EventWithAction { action, .. } => action,
+        }
+    }
+}
+
+// Events received by the hook, to be handled on the dispatcher thread. HWNDs aren't Send, so we
+// store them as isize.
+struct WinEvent {
+    event: u32,
+    hwnd: isize,
+    id_object: i32,
+    id_child: i32,
+}
+
+static EVENT_SENDER: OnceLock<Sender<WinEvent>> = OnceLock::new();
+
+// Start the thread that handles events received by the hook. This must be called before the event
+// hook is set, or else events will be dropped.
+pub fn start_event_dispatcher() {
+    let (sender, receiver) = mpsc::channel::<WinEvent>();
+    if EVENT_SENDER.set(sender).is_err() {
+        error!("event dispatcher has already been started");
+        return;
+    }
+
+    let _ = thread::spawn(move || {
+        for win_event in receiver {
+            handle_win_event(
+                win_event.event,
+                HWND(win_event.hwnd as _),
+                win_event.id_object,
+                win_event.id_child,
+            );
+        }
+    });
+}
+
+// The hook callback runs on the main thread and should return as quickly as possible, so it only
+// filters out events we never care about and leaves the rest to the dispatcher thread. Evaluating
+// window rules and process names can take a while, which would otherwise delay the event queue.
 pub extern "system" fn process_win_event(
     _h_win_event_hook: HWINEVENTHOOK,
     _event: u32,
@@ -32,6 +126,28 @@ pub extern "system" fn process_win_event(
         return;
     }
 
+    // Location changes are by far the most frequent events, so drop the ones for child objects
+    // (e.g. carets and scrollbars) here instead of queueing them
+    if _event == EVENT_OBJECT_LOCATIONCHANGE && _id_child != CHILDID_SELF as i32 {
+        return;
+    }
+
+    if let Some(sender) = EVENT_SENDER.get() {
+        let win_event = WinEvent {
+            event: _event,
+            hwnd: _hwnd.0 as isize,
+            id_object: _id_object,
+            id_child: _id_child,
+        };
+        if sender.send(win_event).is_err() {
+            error!("could not queue event {_event:#x}; the event dispatcher has stopped");
+        }
+    }
+}
+
+fn handle_win_event(_event: u32, _hwnd: HWND, _id_object: i32, _id_child: i32) {
+    handle_extra_update_event(_event, _hwnd);
+
     match _event {
         EVENT_OBJECT_LOCATIONCHANGE => {
             if _id_child != CHILDID_SELF as i32 {
@@ -39,19 +155,34 @@ pub extern "system" fn process_win_event(
             }
 
             if let Some(border) = get_border_for_window(_hwnd) {
-                send_notify_message_w(border, WM_APP_LOCATIONCHANGE, WPARAM(0), LPARAM(0))
-                    .context("EVENT_OBJECT_LOCATIONCHANGE")
-                    .log_if_err();
+                send_notify_message_w(
+                    border,
+                    AppMessage::LocationChange.into(),
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("EVENT_OBJECT_LOCATIONCHANGE")
+                .log_if_err();
+            } else {
+                create_deferred_border(_hwnd);
             }
         }
         EVENT_OBJECT_REORDER => {
+            // Windows that were covered at startup may have been uncovered now
+            create_uncovered_deferred_borders();
+
             // Send reorder messages to all the border windows
             for value in APP_STATE.borders.lock().unwrap().values() {
                 let border_window = HWND(*value as _);
                 if is_window_visible(border_window) {
-                    post_message_w(border_window, WM_APP_REORDER, WPARAM(0), LPARAM(0))
-                        .context("EVENT_OBJECT_REORDER")
-                        .log_if_err();
+                    post_message_w(
+                        border_window,
+                        AppMessage::Reorder.into(),
+                        WPARAM(0),
+                        LPARAM(0),
+                    )
+                    .context("EVENT_OBJECT_REORDER")
+                    .log_if_err();
                 }
             }
         }
@@ -67,32 +198,94 @@ pub extern "system" fn process_win_event(
                 handle_foreground_event(potential_active_hwnd, _hwnd);
             }
         }
+        // The foreground window can be stale after returning from the secure desktop (e.g. a UAC
+        // prompt), so re-query it and let every border update its active state
+        EVENT_SYSTEM_DESKTOPSWITCH => {
+            let foreground_hwnd = get_foreground_window();
+            debug!("desktop switched; foreground window is now {foreground_hwnd:?}");
+
+            if !foreground_hwnd.is_invalid() {
+                handle_foreground_event(foreground_hwnd, foreground_hwnd);
+            } else if !APP_STATE.is_polling_active_window() {
+                poll_active_window_with_limit(3);
+            }
+        }
         EVENT_OBJECT_SHOW | EVENT_OBJECT_UNCLOAKED => {
             if _id_object == OBJID_WINDOW.0 {
                 show_border_for_window(_hwnd);
             }
         }
+        // Title-based rules may match differently once the title changes (e.g. a browser
+        // navigating to another page), so let the border re-check its rule
+        EVENT_OBJECT_NAMECHANGE => {
+            if _id_object != OBJID_WINDOW.0 || _id_child != CHILDID_SELF as i32 {
+                return;
+            }
+
+            if !APP_STATE.config.load().window_rule_plan.needs_title {
+                return;
+            }
+
+            // Windows without a border may now match a title rule that enables one
+            match get_border_for_window(_hwnd) {
+                Some(border) => {
+                    post_message_w(border, AppMessage::NameChange.into(), WPARAM(0), LPARAM(0))
+                        .context("EVENT_OBJECT_NAMECHANGE")
+                        .log_if_err();
+                }
+                None => show_border_for_window(_hwnd),
+            }
+        }
         EVENT_OBJECT_HIDE | EVENT_OBJECT_CLOAKED => {
             if _id_object == OBJID_WINDOW.0 {
                 hide_border_for_window(_hwnd);
             }
         }
-        EVENT_SYSTEM_MINIMIZESTART => {
+        EVENT_SYSTEM_MOVESIZESTART => {
             if let Some(border) = get_border_for_window(_hwnd) {
-                post_message_w(border, WM_APP_MINIMIZESTART, WPARAM(0), LPARAM(0))
-                    .context("EVENT_SYSTEM_MINIMIZESTART")
+                post_message_w(
+                    border,
+                    AppMessage::MoveSizeStart.into(),
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("EVENT_SYSTEM_MOVESIZESTART")
+                .log_if_err();
+            }
+        }
+        EVENT_SYSTEM_MOVESIZEEND => {
+            if let Some(border) = get_border_for_window(_hwnd) {
+                post_message_w(border, AppMessage::MoveSizeEnd.into(), WPARAM(0), LPARAM(0))
+                    .context("EVENT_SYSTEM_MOVESIZEEND")
                     .log_if_err();
             }
         }
+        EVENT_SYSTEM_MINIMIZESTART => {
+            if let Some(border) = get_border_for_window(_hwnd) {
+                post_message_w(
+                    border,
+                    AppMessage::MinimizeStart.into(),
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("EVENT_SYSTEM_MINIMIZESTART")
+                .log_if_err();
+            }
+        }
         EVENT_SYSTEM_MINIMIZEEND => {
             if let Some(border) = get_border_for_window(_hwnd) {
-                post_message_w(border, WM_APP_MINIMIZEEND, WPARAM(0), LPARAM(0))
+                post_message_w(border, AppMessage::MinimizeEnd.into(), WPARAM(0), LPARAM(0))
                     .context("EVENT_SYSTEM_MINIMIZEEND")
                     .log_if_err();
             }
         }
         EVENT_OBJECT_DESTROY => {
             if _id_object == OBJID_WINDOW.0 && _id_child == CHILDID_SELF as i32 {
+                APP_STATE
+                    .deferred_windows
+                    .lock()
+                    .unwrap()
+                    .remove(&(_hwnd.0 as isize));
                 destroy_border_for_window(_hwnd);
             }
         }
@@ -100,6 +293,37 @@ pub extern "system" fn process_win_event(
     }
 }
 
+fn handle_extra_update_event(event: u32, hwnd: HWND) {
+    let config = APP_STATE.config.load();
+    let Some(extra_event) = config
+        .extra_update_events
+        .iter()
+        .find(|extra_event| extra_event.event() == event)
+    else {
+        return;
+    };
+
+    if let Some(border) = get_border_for_window(hwnd) {
+        post_message_w(
+            border,
+            extra_event.action().to_app_message().into(),
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context(format!("extra update event {event:#x}"))
+        .log_if_err();
+    }
+}
+
+// Suspend borders while Task View or the task switcher is open so they don't appear over its
+// backdrop while windows are being cloaked and rearranged
+fn update_task_view(is_task_view_open: bool) {
+    if is_task_view_open != APP_STATE.is_suspended_for(SuspendReason::TaskView) {
+        debug!("task view is open: {is_task_view_open}");
+        APP_STATE.set_suspended(SuspendReason::TaskView, is_task_view_open);
+    }
+}
+
 fn poll_active_window_with_limit(max_polls: u32) {
     APP_STATE.set_polling_active_window(true);
 
@@ -124,17 +348,127 @@ fn handle_foreground_event(potential_active_hwnd: HWND, event_hwnd: HWND) {
         true => potential_active_hwnd.0 as isize,
         false => event_hwnd.0 as isize,
     };
+
+    let old_active_window = *APP_STATE.active_window.lock().unwrap();
+
+    // Shell flyouts (e.g. the snap layouts flyout) aren't real window switches, so they shouldn't
+    // replace the last active window
+    if old_active_window != new_active_window && !is_xaml_host_window(HWND(old_active_window as _))
+    {
+        *APP_STATE.last_active_window.lock().unwrap() = old_active_window;
+    }
     *APP_STATE.active_window.lock().unwrap() = new_active_window;
 
+    // Don't notify the borders when the snap layouts flyout takes the foreground, so the active
+    // window's border doesn't flicker while hovering over its maximize button
+    if old_active_window != new_active_window
+        && is_xaml_host_window(HWND(new_active_window as _))
+        && is_hovering_maximize_button(HWND(old_active_window as _))
+    {
+        debug!("ignoring foreground change to the snap layouts flyout");
+        return;
+    }
+
+    update_game_mode(HWND(new_active_window as _));
+    update_task_view(is_task_view_window(HWND(new_active_window as _)));
+    create_deferred_border(HWND(new_active_window as _));
+
     // Send foreground messages to all the border windows
-    for (key, val) in APP_STATE.borders.lock().unwrap().iter() {
-        let border_window = HWND(*val as _);
-        // NOTE: some apps can become foreground even if they're not visible, so we also
-        // have to check the keys against the active_window HWND from earlier
-        if is_window_visible(border_window) || *key == new_active_window {
-            post_message_w(border_window, WM_APP_FOREGROUND, WPARAM(0), LPARAM(0))
-                .context("EVENT_OBJECT_FOCUS")
-                .log_if_err();
-        }
+    let borders = APP_STATE.borders.lock().unwrap().clone();
+    let only_on_focused_monitor = APP_STATE.config.load().global.only_on_focused_monitor;
+    let recipients = get_foreground_recipients(
+        &borders,
+        new_active_window,
+        only_on_focused_monitor,
+        &Win32WindowSystem,
+        is_window_visible,
+    );
+
+    for border_window in recipients {
+        post_message_w(
+            border_window,
+            AppMessage::Foreground.into(),
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context("EVENT_OBJECT_FOCUS")
+        .log_if_err();
+    }
+}
+
+// Get the border windows that need to handle a foreground change. Hidden borders are normally
+// skipped, but with only_on_focused_monitor, the ones on the newly focused monitor may have to be
+// shown again.
+fn get_foreground_recipients(
+    borders: &HashMap<isize, isize>,
+    new_active_window: isize,
+    only_on_focused_monitor: bool,
+    system: &impl WindowSystem,
+    is_visible: impl Fn(HWND) -> bool,
+) -> Vec<HWND> {
+    let active_monitor = system.get_monitor_for_window(HWND(new_active_window as _));
+
+    borders
+        .iter()
+        .filter(|(&tracking_window, &border_window)| {
+            // NOTE: some apps can become foreground even if they're not visible, so we also
+            // have to check the keys against the active_window HWND from earlier
+            is_visible(HWND(border_window as _))
+                || tracking_window == new_active_window
+                || (only_on_focused_monitor
+                    && system.get_monitor_for_window(HWND(tracking_window as _)) == active_monitor)
+        })
+        .map(|(_, &border_window)| HWND(border_window as _))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_system::mock::{MockWindow, MockWindowSystem};
+
+    const FOCUSED_WINDOW: isize = 1;
+    const HIDDEN_WINDOW: isize = 2;
+    const HIDDEN_BORDER: isize = 20;
+
+    fn system(hidden_window_monitor: isize) -> MockWindowSystem {
+        let window = |monitor| MockWindow {
+            monitor,
+            ..Default::default()
+        };
+
+        MockWindowSystem::default()
+            .with_window(FOCUSED_WINDOW, window(1))
+            .with_window(HIDDEN_WINDOW, window(hidden_window_monitor))
+    }
+
+    fn recipients(system: &MockWindowSystem, only_on_focused_monitor: bool) -> Vec<HWND> {
+        let borders = HashMap::from([(FOCUSED_WINDOW, 10), (HIDDEN_WINDOW, HIDDEN_BORDER)]);
+        get_foreground_recipients(
+            &borders,
+            FOCUSED_WINDOW,
+            only_on_focused_monitor,
+            system,
+            |_| false,
+        )
+    }
+
+    #[test]
+    fn notifies_hidden_border_when_its_monitor_is_focused() {
+        // The border was hidden while its monitor wasn't focused, and focus has now moved there
+        let recipients = recipients(&system(1), true);
+        assert!(recipients.contains(&HWND(HIDDEN_BORDER as _)));
+    }
+
+    #[test]
+    fn skips_hidden_border_on_other_monitor() {
+        let recipients = recipients(&system(2), true);
+        assert_eq!(recipients, vec![HWND(10 as _)]);
+    }
+
+    #[test]
+    fn skips_hidden_borders_without_only_on_focused_monitor() {
+        let recipients = recipients(&system(1), false);
+        assert_eq!(recipients, vec![HWND(10 as _)]);
     }
 }
@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use windows::Win32::Foundation::HWND;
+
+use crate::utils::{get_process_name, is_window_fullscreen};
+use crate::{SuspendReason, APP_STATE};
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GameModeConfig {
+    // Process names (e.g. "eldenring" or "eldenring.exe") that should suspend borders
+    #[serde(default)]
+    pub processes: Vec<String>,
+    // Also treat any fullscreen foreground window as a game
+    #[serde(default)]
+    pub detect_fullscreen: bool,
+}
+
+impl GameModeConfig {
+    fn is_enabled(&self) -> bool {
+        !self.processes.is_empty() || self.detect_fullscreen
+    }
+
+    fn is_game_window(&self, hwnd: HWND) -> bool {
+        if self.detect_fullscreen && is_window_fullscreen(hwnd) {
+            return true;
+        }
+
+        if self.processes.is_empty() {
+            return false;
+        }
+
+        let process_name = match get_process_name(hwnd) {
+            Ok(name) => name,
+            Err(err) => {
+                debug!("could not retrieve process name for {hwnd:?}: {err:#}");
+                return false;
+            }
+        };
+
+        self.processes.iter().any(|game| {
+            let game = game.strip_suffix(".exe").unwrap_or(game);
+            game.eq_ignore_ascii_case(&process_name)
+        })
+    }
+}
+
+// Suspend all borders while a game is in the foreground, and resume them once it's not
+pub fn update_game_mode(foreground_hwnd: HWND) {
+    let is_game = {
+        let config = APP_STATE.config.load();
+        config.game_mode.is_enabled() && config.game_mode.is_game_window(foreground_hwnd)
+    };
+
+    let was_game = APP_STATE.is_suspended_for(SuspendReason::GameMode);
+
+    if is_game && !was_game {
+        info!("game detected in the foreground; suspending borders");
+        APP_STATE.set_suspended(SuspendReason::GameMode, true);
+    } else if !is_game && was_game {
+        info!("game is no longer in the foreground; resuming borders");
+        APP_STATE.set_suspended(SuspendReason::GameMode, false);
+    }
+}
@@ -0,0 +1,157 @@
+use serde::Deserialize;
+use windows::Win32::Globalization::GetUserDefaultUILanguage;
+
+use crate::APP_STATE;
+
+// Primary language identifiers (the lower 10 bits of a LANGID)
+const LANG_CHINESE: u16 = 0x04;
+const LANG_GERMAN: u16 = 0x07;
+const LANG_SPANISH: u16 = 0x0a;
+const LANG_FRENCH: u16 = 0x0c;
+const LANG_JAPANESE: u16 = 0x11;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+    Japanese,
+    Chinese,
+}
+
+impl Language {
+    // Determine the language from the user's Windows display language
+    pub fn from_system() -> Self {
+        let lang_id = unsafe { GetUserDefaultUILanguage() };
+
+        match lang_id & 0x3ff {
+            LANG_GERMAN => Language::German,
+            LANG_FRENCH => Language::French,
+            LANG_SPANISH => Language::Spanish,
+            LANG_JAPANESE => Language::Japanese,
+            LANG_CHINESE => Language::Chinese,
+            _ => Language::English,
+        }
+    }
+
+    // Get the language from the config, falling back to the system language if it's not set
+    pub fn current() -> Self {
+        APP_STATE
+            .config
+            .load()
+            .language
+            .unwrap_or_else(Self::from_system)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringId {
+    // Tray menu items
+    ShowConfig,
+    OpenConfig,
+    OpenLog,
+    ToggleActiveBorder,
+    Reload,
+    Close,
+    SaveDiagnostics,
+    DisableAnimations,
+    DebugOverlay,
+    ColorBlindMode,
+    // Startup error dialog
+    StartupFailed,
+    OpenLogPrompt,
+}
+
+pub fn get_string(id: StringId) -> &'static str {
+    get_string_for(Language::current(), id)
+}
+
+pub fn get_string_for(language: Language, id: StringId) -> &'static str {
+    match (language, id) {
+        (Language::English, StringId::ShowConfig) => "Show Config",
+        (Language::English, StringId::OpenConfig) => "Open Config File",
+        (Language::English, StringId::OpenLog) => "Open Log File",
+        (Language::English, StringId::ToggleActiveBorder) => "Toggle Border for Active Window",
+        (Language::English, StringId::Reload) => "Reload",
+        (Language::English, StringId::SaveDiagnostics) => "Save Diagnostics",
+        (Language::English, StringId::DisableAnimations) => "Disable Animations",
+        (Language::English, StringId::DebugOverlay) => "Debug Overlay",
+        (Language::English, StringId::ColorBlindMode) => "Color-Blind Mode",
+        (Language::English, StringId::Close) => "Close",
+        (Language::English, StringId::StartupFailed) => "tacky-borders could not start:",
+        (Language::English, StringId::OpenLogPrompt) => "Open the log file?",
+
+        (Language::German, StringId::ShowConfig) => "Konfiguration anzeigen",
+        (Language::German, StringId::OpenConfig) => "Konfigurationsdatei öffnen",
+        (Language::German, StringId::OpenLog) => "Protokolldatei öffnen",
+        (Language::German, StringId::ToggleActiveBorder) => "Rahmen für aktives Fenster umschalten",
+        (Language::German, StringId::Reload) => "Neu laden",
+        (Language::German, StringId::SaveDiagnostics) => "Diagnosedaten speichern",
+        (Language::German, StringId::DisableAnimations) => "Animationen deaktivieren",
+        (Language::German, StringId::DebugOverlay) => "Debug-Overlay",
+        (Language::German, StringId::ColorBlindMode) => "Farbenblind-Modus",
+        (Language::German, StringId::Close) => "Beenden",
+        (Language::German, StringId::StartupFailed) => {
+            "tacky-borders konnte nicht gestartet werden:"
+        }
+        (Language::German, StringId::OpenLogPrompt) => "Protokolldatei öffnen?",
+
+        (Language::French, StringId::ShowConfig) => "Afficher la configuration",
+        (Language::French, StringId::OpenConfig) => "Ouvrir le fichier de configuration",
+        (Language::French, StringId::OpenLog) => "Ouvrir le fichier journal",
+        (Language::French, StringId::ToggleActiveBorder) => {
+            "Activer/désactiver la bordure de la fenêtre active"
+        }
+        (Language::French, StringId::Reload) => "Recharger",
+        (Language::French, StringId::SaveDiagnostics) => "Enregistrer les diagnostics",
+        (Language::French, StringId::DisableAnimations) => "Désactiver les animations",
+        (Language::French, StringId::DebugOverlay) => "Superposition de débogage",
+        (Language::French, StringId::ColorBlindMode) => "Mode daltonien",
+        (Language::French, StringId::Close) => "Fermer",
+        (Language::French, StringId::StartupFailed) => "tacky-borders n'a pas pu démarrer :",
+        (Language::French, StringId::OpenLogPrompt) => "Ouvrir le fichier journal ?",
+
+        (Language::Spanish, StringId::ShowConfig) => "Mostrar configuración",
+        (Language::Spanish, StringId::OpenConfig) => "Abrir archivo de configuración",
+        (Language::Spanish, StringId::OpenLog) => "Abrir archivo de registro",
+        (Language::Spanish, StringId::ToggleActiveBorder) => "Alternar borde de la ventana activa",
+        (Language::Spanish, StringId::Reload) => "Recargar",
+        (Language::Spanish, StringId::SaveDiagnostics) => "Guardar diagnóstico",
+        (Language::Spanish, StringId::DisableAnimations) => "Desactivar animaciones",
+        (Language::Spanish, StringId::DebugOverlay) => "Superposición de depuración",
+        (Language::Spanish, StringId::ColorBlindMode) => "Modo para daltónicos",
+        (Language::Spanish, StringId::Close) => "Cerrar",
+        (Language::Spanish, StringId::StartupFailed) => "tacky-borders no se pudo iniciar:",
+        (Language::Spanish, StringId::OpenLogPrompt) => "¿Abrir el archivo de registro?",
+
+        (Language::Japanese, StringId::ShowConfig) => "設定を表示",
+        (Language::Japanese, StringId::OpenConfig) => "設定ファイルを開く",
+        (Language::Japanese, StringId::OpenLog) => "ログファイルを開く",
+        (Language::Japanese, StringId::ToggleActiveBorder) => {
+            "アクティブウィンドウの枠線を切り替え"
+        }
+        (Language::Japanese, StringId::Reload) => "再読み込み",
+        (Language::Japanese, StringId::SaveDiagnostics) => "診断情報を保存",
+        (Language::Japanese, StringId::DisableAnimations) => "アニメーションを無効化",
+        (Language::Japanese, StringId::DebugOverlay) => "デバッグオーバーレイ",
+        (Language::Japanese, StringId::ColorBlindMode) => "色覚サポートモード",
+        (Language::Japanese, StringId::Close) => "終了",
+        (Language::Japanese, StringId::StartupFailed) => "tacky-borders を起動できませんでした:",
+        (Language::Japanese, StringId::OpenLogPrompt) => "ログファイルを開きますか?",
+
+        (Language::Chinese, StringId::ShowConfig) => "显示配置",
+        (Language::Chinese, StringId::OpenConfig) => "打开配置文件",
+        (Language::Chinese, StringId::OpenLog) => "打开日志文件",
+        (Language::Chinese, StringId::ToggleActiveBorder) => "切换活动窗口的边框",
+        (Language::Chinese, StringId::Reload) => "重新加载",
+        (Language::Chinese, StringId::SaveDiagnostics) => "保存诊断信息",
+        (Language::Chinese, StringId::DisableAnimations) => "禁用动画",
+        (Language::Chinese, StringId::DebugOverlay) => "调试叠加层",
+        (Language::Chinese, StringId::ColorBlindMode) => "色盲模式",
+        (Language::Chinese, StringId::Close) => "关闭",
+        (Language::Chinese, StringId::StartupFailed) => "tacky-borders 无法启动:",
+        (Language::Chinese, StringId::OpenLogPrompt) => "打开日志文件?",
+    }
+}
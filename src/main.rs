@@ -8,94 +8,200 @@ extern crate log;
 extern crate sp_log;
 
 use anyhow::{anyhow, Context};
+use arc_swap::ArcSwap;
 use sp_log::{ColorChoice, CombinedLogger, FileLogger, LevelFilter, TermLogger, TerminalMode};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{LazyLock, Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time;
 use utils::get_foreground_window;
-use windows::core::w;
+use windows::core::{w, HSTRING};
 use windows::Win32::Foundation::{GetLastError, BOOL, HWND, LPARAM, TRUE, WPARAM};
 use windows::Win32::Graphics::Direct2D::{
-    D2D1CreateFactory, ID2D1Factory, D2D1_FACTORY_TYPE_MULTI_THREADED,
+    D2D1CreateFactory, ID2D1Factory, D2D1_FACTORY_TYPE_MULTI_THREADED, D2D1_RENDER_TARGET_TYPE,
+    D2D1_RENDER_TARGET_TYPE_DEFAULT, D2D1_RENDER_TARGET_TYPE_SOFTWARE,
 };
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::Accessibility::{SetWinEventHook, HWINEVENTHOOK};
 use windows::Win32::UI::HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2;
 use windows::Win32::UI::WindowsAndMessaging::{
-    DispatchMessageW, EnumWindows, GetMessageW, LoadCursorW, RegisterClassExW, TranslateMessage,
-    EVENT_MAX, EVENT_MIN, IDC_ARROW, MSG, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS,
-    WM_NCDESTROY, WNDCLASSEXW,
+    DispatchMessageW, EnumWindows, GetMessageW, LoadCursorW, MessageBoxW, RegisterClassExW,
+    TranslateMessage, EVENT_MAX, EVENT_MIN, IDC_ARROW, IDYES, MB_ICONERROR, MB_YESNO, MSG,
+    WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS, WNDCLASSEXW,
 };
 
 mod anim_timer;
 mod animations;
+mod audio_watcher;
 mod border_config;
+mod color_math;
+mod color_profile;
 mod colors;
+mod conflicts;
+mod debug_overlay;
+mod diagnostics;
+mod etw;
 mod event_hook;
+mod game_mode;
+mod localization;
+mod night_light;
+mod on_air;
+mod onboarding;
 mod sys_tray_icon;
+mod update_checker;
 mod utils;
+mod watchdog;
 mod window_border;
+mod window_overrides;
+mod window_system;
 
 use crate::border_config::{Config, ConfigWatcher, EnableMode};
+use crate::colors::ColorBlindMode;
+use crate::localization::{get_string_for, Language, StringId};
 use crate::utils::{
     create_border_for_window, get_window_rule, has_filtered_style, imm_disable_ime,
-    is_window_cloaked, is_window_top_level, is_window_visible, post_message_w,
-    set_process_dpi_awareness_context, LogIfErr,
+    is_composition_enabled, is_remote_session, is_window_cloaked, is_window_excluded,
+    is_window_occluded, is_window_top_level, is_window_visible, list_borders,
+    post_message_to_all_borders, post_message_w, set_process_dpi_awareness_context, AppMessage,
+    ClosedWindow, LogIfErr, ProcessAccess,
 };
+use crate::window_overrides::WindowOverrides;
 
 // TODO: dunno if I should pass an Arc ptr of this to other functions/structs
-static APP_STATE: LazyLock<AppState> = LazyLock::new(AppState::new);
+// This is set at the start of main() since creating the app state can fail (see init_app_state)
+static APP_STATE: AppStateCell = AppStateCell(OnceLock::new());
+
+struct AppStateCell(OnceLock<AppState>);
+
+impl Deref for AppStateCell {
+    type Target = AppState;
+
+    fn deref(&self) -> &AppState {
+        self.0
+            .get()
+            .expect("app state was accessed before it was initialized")
+    }
+}
+
+// Number of consecutive render failures (across all borders) before we fall back to software
+// rendering, and how long we stay in software rendering before retrying hardware rendering
+const RENDER_FAILURE_THRESHOLD: u32 = 3;
+const SOFTWARE_FALLBACK_COOLDOWN: time::Duration = time::Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+enum SuspendReason {
+    // A game is in the foreground (see game_mode.rs)
+    GameMode = 1 << 0,
+    // Task View or the task switcher is open
+    TaskView = 1 << 1,
+}
 
 struct AppState {
     borders: Mutex<HashMap<isize, isize>>,
     initial_windows: Mutex<Vec<isize>>,
+    // Windows whose borders were skipped at startup because they were covered (see lazy_startup)
+    deferred_windows: Mutex<HashSet<isize>>,
+    // Windows that just closed, used to detect windows that recreate themselves (see ClosedWindow)
+    recently_closed: Mutex<Vec<ClosedWindow>>,
+    // Windows whose processes we were denied access to (and which access), so we don't keep trying
+    // to open them
+    access_denied_windows: Mutex<HashMap<isize, ProcessAccess>>,
+    // The conflicting module found in each window's process, if any (see find_conflicting_module).
+    // Cleared whenever the config is reloaded.
+    conflicting_modules: Mutex<HashMap<isize, Option<String>>>,
     active_window: Mutex<isize>,
+    // The window that was active before the current active window
+    last_active_window: Mutex<isize>,
     is_polling_active_window: AtomicBool,
-    config: RwLock<Config>,
+    // Bitmask of SuspendReasons. Borders are suspended while any reason is set.
+    suspend_reasons: AtomicU32,
+    // Set while a screen capture tool is running (see on_air.rs)
+    is_on_air: AtomicBool,
+    // Set while Windows' Night Light is on and night_light is enabled (see night_light.rs)
+    is_night_light_active: AtomicBool,
+    // Global animations kill switch; overrides the configured animations while set
+    are_animations_disabled: AtomicBool,
+    // Whether borders draw render stats inside of themselves (see debug_overlay.rs)
+    is_debug_overlay_enabled: AtomicBool,
+    // Starts out as the configured color_blind_mode, but can be toggled from the tray menu
+    color_blind_mode: Mutex<ColorBlindMode>,
+    // Readers load a snapshot of the config instead of taking a lock, and reloading the config
+    // swaps in a new snapshot (see Config::reload)
+    config: ArcSwap<Config>,
     config_watcher: Mutex<ConfigWatcher>,
+    window_overrides: Mutex<WindowOverrides>,
     render_factory: ID2D1Factory,
+    render_failures: AtomicU32,
+    software_fallback_time: Mutex<Option<time::Instant>>,
+    // Set at startup if hardware rendering is unlikely to work (e.g. in a remote session)
+    is_software_rendering_forced: bool,
+    // Border windows mapped to the last time their thread handled a heartbeat (see watchdog.rs)
+    heartbeats: Mutex<HashMap<isize, time::Instant>>,
 }
 
 impl AppState {
-    fn new() -> Self {
+    fn new() -> anyhow::Result<Self> {
+        let render_factory: ID2D1Factory =
+            unsafe { D2D1CreateFactory(D2D1_FACTORY_TYPE_MULTI_THREADED, None) }
+                .context("could not create ID2D1Factory")?;
+
         let active_window = get_foreground_window().0 as isize;
 
         // TODO: right now we use unwrap_or_default(), but I should probably handle the Err
-        let mut config_watcher = ConfigWatcher::new(
-            Config::get_dir().unwrap_or_default().join("config.yaml"),
+        let config_watcher = ConfigWatcher::new(
+            Config::get_watch_targets(),
             500,
             Config::config_watcher_callback,
         );
 
+        if Config::is_read_only() {
+            warn!(
+                "could not create the config directory; running in read-only mode. The config \
+                watcher is disabled and only the log is written, to the temp directory."
+            );
+        }
+
+        // The config watcher is started in init_app_state() once the app state is set
         let config = match Config::create() {
-            Ok(config) => {
-                if config.watch_config_changes {
-                    config_watcher.start().log_if_err();
-                }
-                config
-            }
+            Ok(config) => config,
             Err(err) => {
-                error!("could not read config.yaml: {err:#}");
+                error!("could not read config: {err:#}");
                 Config::default()
             }
         };
 
-        let render_factory = unsafe {
-            D2D1CreateFactory(D2D1_FACTORY_TYPE_MULTI_THREADED, None).unwrap_or_else(|err| {
-                error!("could not create ID2D1Factory: {err}");
-                panic!()
-            })
-        };
+        let are_animations_disabled = config.disable_animations;
+        let is_debug_overlay_enabled = config.debug_overlay;
+        let color_blind_mode = config.color_blind_mode;
+
+        let is_software_rendering_forced = probe_rendering_capabilities();
 
-        AppState {
+        Ok(AppState {
             borders: Mutex::new(HashMap::new()),
             initial_windows: Mutex::new(Vec::new()),
+            deferred_windows: Mutex::new(HashSet::new()),
+            recently_closed: Mutex::new(Vec::new()),
+            access_denied_windows: Mutex::new(HashMap::new()),
+            conflicting_modules: Mutex::new(HashMap::new()),
             active_window: Mutex::new(active_window),
+            last_active_window: Mutex::new(0),
             is_polling_active_window: AtomicBool::new(false),
-            config: RwLock::new(config),
+            suspend_reasons: AtomicU32::new(0),
+            is_on_air: AtomicBool::new(false),
+            is_night_light_active: AtomicBool::new(false),
+            are_animations_disabled: AtomicBool::new(are_animations_disabled),
+            is_debug_overlay_enabled: AtomicBool::new(is_debug_overlay_enabled),
+            color_blind_mode: Mutex::new(color_blind_mode),
+            config: ArcSwap::from_pointee(config),
             config_watcher: Mutex::new(config_watcher),
+            window_overrides: Mutex::new(WindowOverrides::load()),
             render_factory,
-        }
+            render_failures: AtomicU32::new(0),
+            software_fallback_time: Mutex::new(None),
+            is_software_rendering_forced,
+            heartbeats: Mutex::new(HashMap::new()),
+        })
     }
 
     fn is_polling_active_window(&self) -> bool {
@@ -105,6 +211,121 @@ impl AppState {
     fn set_polling_active_window(&self, val: bool) {
         self.is_polling_active_window.store(val, Ordering::SeqCst);
     }
+
+    fn is_on_air(&self) -> bool {
+        self.is_on_air.load(Ordering::SeqCst)
+    }
+
+    fn set_on_air(&self, val: bool) {
+        self.is_on_air.store(val, Ordering::SeqCst);
+    }
+
+    fn is_night_light_active(&self) -> bool {
+        self.is_night_light_active.load(Ordering::SeqCst)
+    }
+
+    fn set_night_light_active(&self, val: bool) {
+        self.is_night_light_active.store(val, Ordering::SeqCst);
+    }
+
+    fn are_animations_disabled(&self) -> bool {
+        self.are_animations_disabled.load(Ordering::SeqCst)
+    }
+
+    fn set_animations_disabled(&self, val: bool) {
+        self.are_animations_disabled.store(val, Ordering::SeqCst);
+    }
+
+    fn is_debug_overlay_enabled(&self) -> bool {
+        self.is_debug_overlay_enabled.load(Ordering::SeqCst)
+    }
+
+    fn set_debug_overlay_enabled(&self, val: bool) {
+        self.is_debug_overlay_enabled.store(val, Ordering::SeqCst);
+    }
+
+    fn get_color_blind_mode(&self) -> ColorBlindMode {
+        *self.color_blind_mode.lock().unwrap()
+    }
+
+    fn set_color_blind_mode(&self, val: ColorBlindMode) {
+        *self.color_blind_mode.lock().unwrap() = val;
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.suspend_reasons.load(Ordering::SeqCst) != 0
+    }
+
+    fn is_suspended_for(&self, reason: SuspendReason) -> bool {
+        self.suspend_reasons.load(Ordering::SeqCst) & reason as u32 != 0
+    }
+
+    // Set or clear a reason for suspending borders. Borders are only sent Suspend or Resume when
+    // the overall suspended state changes, so one reason being cleared can't resume borders that
+    // are still suspended for another.
+    fn set_suspended(&self, reason: SuspendReason, val: bool) {
+        let old_reasons = match val {
+            true => self
+                .suspend_reasons
+                .fetch_or(reason as u32, Ordering::SeqCst),
+            false => self
+                .suspend_reasons
+                .fetch_and(!(reason as u32), Ordering::SeqCst),
+        };
+        let new_reasons = self.suspend_reasons.load(Ordering::SeqCst);
+
+        match (old_reasons != 0, new_reasons != 0) {
+            (false, true) => post_message_to_all_borders(AppMessage::Suspend),
+            (true, false) => post_message_to_all_borders(AppMessage::Resume),
+            _ => {}
+        }
+    }
+
+    fn is_software_fallback_active(&self) -> bool {
+        self.is_software_rendering_forced || self.software_fallback_time.lock().unwrap().is_some()
+    }
+
+    fn get_render_target_type(&self) -> D2D1_RENDER_TARGET_TYPE {
+        if self.is_software_rendering_forced {
+            return D2D1_RENDER_TARGET_TYPE_SOFTWARE;
+        }
+
+        let mut software_fallback_time = self.software_fallback_time.lock().unwrap();
+
+        match *software_fallback_time {
+            Some(fallback_time) if fallback_time.elapsed() >= SOFTWARE_FALLBACK_COOLDOWN => {
+                info!("software rendering cooldown has elapsed; retrying hardware rendering");
+                *software_fallback_time = None;
+                self.render_failures.store(0, Ordering::SeqCst);
+
+                D2D1_RENDER_TARGET_TYPE_DEFAULT
+            }
+            Some(_) => D2D1_RENDER_TARGET_TYPE_SOFTWARE,
+            None => D2D1_RENDER_TARGET_TYPE_DEFAULT,
+        }
+    }
+
+    fn record_render_failure(&self) {
+        let failures = self.render_failures.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let mut software_fallback_time = self.software_fallback_time.lock().unwrap();
+        if failures >= RENDER_FAILURE_THRESHOLD && software_fallback_time.is_none() {
+            warn!(
+                "rendering has failed {failures} times in a row; falling back to software rendering for {}s",
+                SOFTWARE_FALLBACK_COOLDOWN.as_secs()
+            );
+            *software_fallback_time = Some(time::Instant::now());
+        }
+    }
+
+    fn record_render_success(&self) {
+        self.render_failures.store(0, Ordering::SeqCst);
+    }
+
+    // The number of render failures in a row across all borders
+    fn get_render_failures(&self) -> u32 {
+        self.render_failures.load(Ordering::SeqCst)
+    }
 }
 
 fn main() {
@@ -114,6 +335,11 @@ fn main() {
 
     info!("starting tacky-borders");
 
+    if !init_app_state() {
+        info!("exiting tacky-borders");
+        return;
+    }
+
     // xFFFFFFFF can be used to disable IME windows for all threads in the current process.
     if !imm_disable_ime(0xFFFFFFFF).as_bool() {
         error!("could not disable ime!");
@@ -123,6 +349,7 @@ fn main() {
         .context("could not make process dpi aware")
         .log_if_err();
 
+    event_hook::start_event_dispatcher();
     let hwineventhook = set_event_hook();
 
     // This is responsible for the actual tray icon window, so it must be kept in scope
@@ -136,9 +363,24 @@ fn main() {
     register_window_class().log_if_err();
     enum_windows().log_if_err();
 
+    audio_watcher::start_audio_watcher();
+    watchdog::start_watchdog();
+    on_air::start_on_air_watcher();
+    night_light::start_night_light_watcher();
+    update_checker::start_update_checker();
+
+    if Config::take_default_generated() {
+        onboarding::show_onboarding();
+    }
+
     unsafe {
         let mut message = MSG::default();
         while GetMessageW(&mut message, HWND::default(), 0, 0).into() {
+            // Thread messages don't belong to any window, so they have to be handled here
+            if message.hwnd.is_invalid() && sys_tray_icon::handle_thread_message(&message) {
+                continue;
+            }
+
             let _ = TranslateMessage(&message);
             DispatchMessageW(&message);
         }
@@ -147,9 +389,73 @@ fn main() {
     info!("exiting tacky-borders");
 }
 
+// Create the app state, showing an error dialog if that fails. Returns false if it failed.
+fn init_app_state() -> bool {
+    match AppState::new() {
+        Ok(app_state) => {
+            let _ = APP_STATE.0.set(app_state);
+
+            // The watcher's callback uses APP_STATE, so it can only be started once that's set
+            if APP_STATE.config.load().should_watch_changes() {
+                APP_STATE
+                    .config_watcher
+                    .lock()
+                    .unwrap()
+                    .start()
+                    .log_if_err();
+            }
+
+            true
+        }
+        Err(err) => {
+            error!("could not start tacky-borders: {err:#}");
+            show_startup_error(&err);
+            false
+        }
+    }
+}
+
+fn show_startup_error(err: &anyhow::Error) {
+    // The app state isn't available yet, so we can't use the configured language or editor here
+    let language = Language::from_system();
+    let text = HSTRING::from(format!(
+        "{}\n\n{err:#}\n\n{}",
+        get_string_for(language, StringId::StartupFailed),
+        get_string_for(language, StringId::OpenLogPrompt)
+    ));
+
+    let choice = unsafe { MessageBoxW(None, &text, w!("tacky-borders"), MB_YESNO | MB_ICONERROR) };
+
+    if choice == IDYES {
+        match Config::get_log_path() {
+            Ok(log_path) => {
+                if let Err(err) = open::that(&log_path) {
+                    error!("could not open {}: {err}", log_path.display());
+                }
+            }
+            Err(err) => error!("{err}"),
+        }
+    }
+}
+
+// Check upfront whether hardware rendering is likely to work so we don't fail late with confusing
+// errors. Returns true if we should always use software rendering. The reason is logged once here.
+fn probe_rendering_capabilities() -> bool {
+    if !is_composition_enabled() {
+        warn!("desktop composition is disabled; borders may not be transparent");
+    }
+
+    if is_remote_session() {
+        info!("remote session detected; using software rendering");
+        return true;
+    }
+
+    false
+}
+
 fn create_logger() -> anyhow::Result<()> {
     // NOTE: there are two Config structs in this function: tacky-borders' and sp_log's
-    let log_path = Config::get_dir()?.join("tacky-borders.log");
+    let log_path = Config::get_log_path()?;
     let Some(path_str) = log_path.to_str() else {
         return Err(anyhow!("could not convert log_path to str"));
     };
@@ -223,22 +529,30 @@ fn enum_windows() -> windows::core::Result<()> {
 }
 
 fn reload_borders() {
+    debug!("reloading borders: {:#?}", list_borders());
+
     let mut borders = APP_STATE.borders.lock().unwrap();
 
     // Send destroy messages to all the border windows
     for value in borders.values() {
         let border_window = HWND(*value as _);
-        post_message_w(border_window, WM_NCDESTROY, WPARAM(0), LPARAM(0))
-            .context("reload_borders")
-            .log_if_err();
+        post_message_w(
+            border_window,
+            AppMessage::Destroy.into(),
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context("reload_borders")
+        .log_if_err();
     }
 
     // Clear the borders hashmap
     borders.clear();
     drop(borders);
 
-    // Clear the initial windows list
+    // Clear the initial and deferred windows lists
     APP_STATE.initial_windows.lock().unwrap().clear();
+    APP_STATE.deferred_windows.lock().unwrap().clear();
 
     enum_windows().log_if_err();
 }
@@ -247,14 +561,36 @@ unsafe extern "system" fn enum_windows_callback(_hwnd: HWND, _lparam: LPARAM) ->
     if is_window_top_level(_hwnd) {
         // Only create borders for visible windows
         if is_window_visible(_hwnd) && !is_window_cloaked(_hwnd) {
-            let window_rule = get_window_rule(_hwnd);
-
-            if window_rule.enabled == Some(EnableMode::Bool(false)) {
-                info!("border is disabled for {_hwnd:?}");
-            } else if window_rule.enabled == Some(EnableMode::Bool(true))
-                || !has_filtered_style(_hwnd)
-            {
-                create_border_for_window(_hwnd, window_rule);
+            // Check the exclusion lists before doing any rule matching
+            if is_window_excluded(_hwnd) {
+                debug!("border is excluded for {_hwnd:?}");
+            } else {
+                let window_rule = get_window_rule(_hwnd);
+
+                if APP_STATE
+                    .window_overrides
+                    .lock()
+                    .unwrap()
+                    .is_border_disabled(_hwnd)
+                {
+                    info!("border is manually disabled for {_hwnd:?}");
+                } else if window_rule.enabled == Some(EnableMode::Bool(false)) {
+                    info!("border is disabled for {_hwnd:?}");
+                } else if window_rule.enabled == Some(EnableMode::Bool(true))
+                    || !has_filtered_style(_hwnd)
+                {
+                    // With lazy_startup, defer borders for covered windows until they're needed
+                    if APP_STATE.config.load().lazy_startup && is_window_occluded(_hwnd) {
+                        debug!("deferring border for covered window {_hwnd:?}");
+                        APP_STATE
+                            .deferred_windows
+                            .lock()
+                            .unwrap()
+                            .insert(_hwnd.0 as isize);
+                    } else {
+                        create_border_for_window(_hwnd, window_rule);
+                    }
+                }
             }
         }
 
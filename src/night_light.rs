@@ -0,0 +1,161 @@
+use serde::Deserialize;
+use std::thread;
+use std::time;
+use windows::core::w;
+use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+use windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F;
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_BINARY};
+
+use crate::border_config::serde_default_f32;
+use crate::utils::{post_message_to_all_borders, AppMessage};
+use crate::APP_STATE;
+
+// Night Light takes a few seconds to fade in or out anyway, so we don't need to check very often
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(3);
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NightLightConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // Color temperature (in Kelvin) that border colors are shifted toward while Night Light is on.
+    // Lower is warmer, and 6500 leaves colors unchanged.
+    #[serde(default = "serde_default_f32::<4000>")]
+    pub temperature: f32,
+}
+
+impl Default for NightLightConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            temperature: 4000.0,
+        }
+    }
+}
+
+// Watch Windows' Night Light setting and warm up border colors while it's on, so they don't clash
+// with the rest of the (warmer) screen
+pub fn start_night_light_watcher() {
+    let _ = thread::spawn(|| loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let is_night_light_active = match APP_STATE.config.load().night_light.enabled {
+            true => is_night_light_on(),
+            false => false,
+        };
+
+        if is_night_light_active != APP_STATE.is_night_light_active() {
+            match is_night_light_active {
+                true => info!("night light turned on; warming border colors"),
+                false => info!("night light turned off; restoring border colors"),
+            }
+
+            // Borders apply (or drop) the warm shift when reloading their appearance
+            APP_STATE.set_night_light_active(is_night_light_active);
+            post_message_to_all_borders(AppMessage::ReloadAppearance);
+        }
+    });
+}
+
+fn is_night_light_on() -> bool {
+    // Night Light's state isn't exposed through any API, so we read it from where the Settings app
+    // stores it. The key doesn't exist until Night Light is used for the first time.
+    let key = w!(
+        "Software\\Microsoft\\Windows\\CurrentVersion\\CloudStore\\Store\\DefaultAccount\\Current\\\
+        default$windows.data.bluelightreduction.bluelightreductionstate\\\
+        windows.data.bluelightreduction.bluelightreductionstate"
+    );
+
+    let mut data = [0u8; 256];
+    let mut size = data.len() as u32;
+    let res = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key,
+            w!("Data"),
+            RRF_RT_REG_BINARY,
+            None,
+            Some(data.as_mut_ptr() as _),
+            Some(&mut size),
+        )
+    };
+
+    match res {
+        ERROR_SUCCESS => is_night_light_state_on(&data[..size as usize]),
+        ERROR_FILE_NOT_FOUND => false,
+        err => {
+            error!("could not read night light state: {err:?}");
+            false
+        }
+    }
+}
+
+// The state is an undocumented binary blob, but the byte at index 18 is consistently 0x15 while
+// Night Light is on (and 0x13 while it's off)
+fn is_night_light_state_on(data: &[u8]) -> bool {
+    data.get(18) == Some(&0x15)
+}
+
+// Approximate the sRGB color of a white light at the given color temperature, from 1000K to 40000K.
+// This uses Tanner Helland's fit of the blackbody curve, which is close to white at 6500K.
+pub fn get_color_temperature_tint(kelvin: f32) -> [f32; 3] {
+    let temp = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+    let r = match temp <= 66.0 {
+        true => 255.0,
+        false => 329.69873 * (temp - 60.0).powf(-0.13320476),
+    };
+    let g = match temp <= 66.0 {
+        true => 99.4708 * temp.ln() - 161.11957,
+        false => 288.12216 * (temp - 60.0).powf(-0.07551485),
+    };
+    let b = if temp >= 66.0 {
+        255.0
+    } else if temp <= 19.0 {
+        0.0
+    } else {
+        138.51773 * (temp - 10.0).ln() - 305.0448
+    };
+
+    [r, g, b].map(|channel| (channel / 255.0).clamp(0.0, 1.0))
+}
+
+pub fn apply_tint(color: D2D1_COLOR_F, tint: [f32; 3]) -> D2D1_COLOR_F {
+    D2D1_COLOR_F {
+        r: color.r * tint[0],
+        g: color.g * tint[1],
+        b: color.b * tint[2],
+        a: color.a,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daylight_temperature_is_nearly_neutral() {
+        let tint = get_color_temperature_tint(6500.0);
+        assert!(tint.iter().all(|&channel| channel > 0.97), "{tint:?}");
+    }
+
+    #[test]
+    fn lower_temperatures_are_warmer() {
+        let warm = get_color_temperature_tint(4000.0);
+        let warmer = get_color_temperature_tint(2700.0);
+
+        assert_eq!(warm[0], 1.0);
+        assert!(warm[1] > warm[2]);
+        assert!(warmer[1] < warm[1] && warmer[2] < warm[2]);
+    }
+
+    #[test]
+    fn reads_night_light_state_byte() {
+        let mut data = [0u8; 41];
+        assert!(!is_night_light_state_on(&data));
+
+        data[18] = 0x15;
+        assert!(is_night_light_state_on(&data));
+        assert!(!is_night_light_state_on(&data[..18]));
+    }
+}
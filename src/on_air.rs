@@ -0,0 +1,112 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::thread;
+use std::time;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+
+use crate::colors::ColorConfig;
+use crate::utils::{post_message_to_all_borders, AppMessage};
+use crate::APP_STATE;
+
+// Processes are started and stopped rarely, so we don't need to check them very often
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(2);
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct OnAirConfig {
+    // Process names (e.g. "obs64" or "obs64.exe") whose presence means we're recording/streaming
+    #[serde(default)]
+    pub processes: Vec<String>,
+    // Colors that replace every border's configured colors while on air
+    #[serde(default)]
+    pub active_color: Option<ColorConfig>,
+    #[serde(default)]
+    pub inactive_color: Option<ColorConfig>,
+}
+
+impl OnAirConfig {
+    fn is_enabled(&self) -> bool {
+        !self.processes.is_empty()
+    }
+
+    fn is_capturing(&self, running_processes: &HashSet<String>) -> bool {
+        self.processes.iter().any(|process| {
+            let process = process.strip_suffix(".exe").unwrap_or(process);
+            running_processes.contains(&process.to_lowercase())
+        })
+    }
+}
+
+// Watch for screen capture tools and switch borders to the 'on_air' colors while one is running.
+// Windows doesn't expose other processes' capture sessions, so we go by process names instead.
+pub fn start_on_air_watcher() {
+    let _ = thread::spawn(|| loop {
+        thread::sleep(POLL_INTERVAL);
+
+        let is_on_air = {
+            let config = APP_STATE.config.load();
+            if !config.on_air.is_enabled() {
+                false
+            } else {
+                match get_running_processes() {
+                    Ok(running_processes) => config.on_air.is_capturing(&running_processes),
+                    Err(err) => {
+                        error!("could not retrieve running processes: {err:#}");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        if is_on_air != APP_STATE.is_on_air() {
+            match is_on_air {
+                true => info!("screen capture detected; switching borders to on_air colors"),
+                false => info!("screen capture ended; restoring border colors"),
+            }
+
+            // Borders pick up the on_air colors (or drop them) when reloading their appearance
+            APP_STATE.set_on_air(is_on_air);
+            post_message_to_all_borders(AppMessage::ReloadAppearance);
+        }
+    });
+}
+
+// Return the lowercase names (without .exe) of all running processes
+fn get_running_processes() -> anyhow::Result<HashSet<String>> {
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }
+        .context("could not create process snapshot")?;
+
+    let running_processes = get_snapshot_processes(snapshot);
+    let _ = unsafe { CloseHandle(snapshot) };
+
+    Ok(running_processes)
+}
+
+fn get_snapshot_processes(snapshot: HANDLE) -> HashSet<String> {
+    let mut running_processes = HashSet::new();
+
+    let mut entry = PROCESSENTRY32W {
+        dwSize: size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut next_res = unsafe { Process32FirstW(snapshot, &mut entry) };
+    while next_res.is_ok() {
+        let len = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+        let exe_name = String::from_utf16_lossy(&entry.szExeFile[..len]).to_lowercase();
+        let process_name = exe_name.strip_suffix(".exe").unwrap_or(&exe_name);
+        running_processes.insert(process_name.to_string());
+
+        next_res = unsafe { Process32NextW(snapshot, &mut entry) };
+    }
+
+    running_processes
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::thread;
+use windows::core::{w, HSTRING};
+use windows::Win32::UI::WindowsAndMessaging::{
+    MessageBoxW, IDYES, MB_ICONINFORMATION, MB_SETFOREGROUND, MB_YESNO,
+};
+
+use crate::border_config::Config;
+use crate::utils::{open_in_editor, LogIfErr};
+
+// Greet first-time users with where their config lives and how to get started. This is only shown
+// right after the default config was generated, so it appears once per install.
+pub fn show_onboarding() {
+    let config_path = match Config::get_path() {
+        Ok(config_path) => config_path,
+        Err(err) => {
+            error!("could not show onboarding: {err}");
+            return;
+        }
+    };
+
+    // Run the dialog on its own thread so it doesn't hold up the main message loop
+    let _ = thread::spawn(move || {
+        let text = HSTRING::from(get_onboarding_text(&config_path));
+        let choice = unsafe {
+            MessageBoxW(
+                None,
+                &text,
+                w!("Welcome to tacky-borders"),
+                MB_YESNO | MB_ICONINFORMATION | MB_SETFOREGROUND,
+            )
+        };
+
+        if choice == IDYES {
+            open_in_editor(&config_path).log_if_err();
+        }
+    });
+}
+
+fn get_onboarding_text(config_path: &Path) -> String {
+    // The config may be in any of the supported formats, so use its actual file name
+    let file_name = config_path
+        .file_name()
+        .map_or("the config".into(), |file_name| file_name.to_string_lossy());
+
+    format!(
+        "tacky-borders is running and drawing borders around your windows.\n\n\
+        Your config file was created at:\n{}\n\n\
+        To use your Windows accent color, set active_color (and optionally inactive_color) under \
+        'global' to \"accent\".\n\n\
+        Changes to the config are applied automatically when watch_config_changes is enabled \
+        (the default). Everything else is available from the tray icon.\n\n\
+        Open {file_name} now?",
+        config_path.display()
+    )
+}
@@ -1,12 +1,147 @@
 use anyhow::Context;
-use tray_icon::menu::{Menu, MenuEvent, MenuItem};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
 use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::UI::Accessibility::{UnhookWinEvent, HWINEVENTHOOK};
-use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::UI::WindowsAndMessaging::{PostQuitMessage, PostThreadMessageW, MSG, WM_APP};
 
 use crate::border_config::Config;
+use crate::colors::ColorBlindMode;
+use crate::diagnostics::save_diagnostics;
+use crate::localization::{get_string, StringId};
+use crate::utils::{
+    open_in_editor, post_message_to_all_borders, spawn_shell_command, toggle_border_for_window,
+    AppMessage, LogIfErr,
+};
 use crate::{reload_borders, APP_STATE};
 
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TrayConfig {
+    // The items to show in the tray menu, in order. Built-in items left out of this list are hidden.
+    #[serde(default = "default_tray_items")]
+    pub items: Vec<TrayItemConfig>,
+}
+
+impl Default for TrayConfig {
+    fn default() -> Self {
+        Self {
+            items: default_tray_items(),
+        }
+    }
+}
+
+fn default_tray_items() -> Vec<TrayItemConfig> {
+    vec![
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::ShowConfig),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::OpenConfig),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::OpenLog),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::SaveDiagnostics),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::ToggleActiveBorder),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::DisableAnimations),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::ColorBlindMode),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::DebugOverlay),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::Reload),
+        TrayItemConfig::BuiltIn(BuiltInTrayItem::Close),
+    ]
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum TrayItemConfig {
+    BuiltIn(BuiltInTrayItem),
+    Custom(CustomTrayItem),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+pub enum BuiltInTrayItem {
+    ShowConfig,
+    OpenConfig,
+    OpenLog,
+    ToggleActiveBorder,
+    Reload,
+    Close,
+    SaveDiagnostics,
+    DisableAnimations,
+    DebugOverlay,
+    ColorBlindMode,
+    Separator,
+}
+
+impl BuiltInTrayItem {
+    fn get_id(&self) -> &'static str {
+        match self {
+            BuiltInTrayItem::ShowConfig => "0",
+            BuiltInTrayItem::Reload => "1",
+            BuiltInTrayItem::Close => "2",
+            BuiltInTrayItem::ToggleActiveBorder => "3",
+            BuiltInTrayItem::OpenConfig => "4",
+            BuiltInTrayItem::OpenLog => "5",
+            BuiltInTrayItem::SaveDiagnostics => "6",
+            BuiltInTrayItem::DisableAnimations => "7",
+            BuiltInTrayItem::DebugOverlay => "8",
+            BuiltInTrayItem::ColorBlindMode => "9",
+            BuiltInTrayItem::Separator => "",
+        }
+    }
+
+    fn get_string_id(&self) -> Option<StringId> {
+        match self {
+            BuiltInTrayItem::ShowConfig => Some(StringId::ShowConfig),
+            BuiltInTrayItem::OpenConfig => Some(StringId::OpenConfig),
+            BuiltInTrayItem::OpenLog => Some(StringId::OpenLog),
+            BuiltInTrayItem::ToggleActiveBorder => Some(StringId::ToggleActiveBorder),
+            BuiltInTrayItem::Reload => Some(StringId::Reload),
+            BuiltInTrayItem::Close => Some(StringId::Close),
+            BuiltInTrayItem::SaveDiagnostics => Some(StringId::SaveDiagnostics),
+            BuiltInTrayItem::DisableAnimations => Some(StringId::DisableAnimations),
+            BuiltInTrayItem::DebugOverlay => Some(StringId::DebugOverlay),
+            BuiltInTrayItem::ColorBlindMode => Some(StringId::ColorBlindMode),
+            BuiltInTrayItem::Separator => None,
+        }
+    }
+
+    // Items that toggle a setting are shown as checkboxes with the setting's current state
+    fn get_checked_state(&self) -> Option<bool> {
+        match self {
+            BuiltInTrayItem::DisableAnimations => Some(APP_STATE.are_animations_disabled()),
+            BuiltInTrayItem::DebugOverlay => Some(APP_STATE.is_debug_overlay_enabled()),
+            BuiltInTrayItem::ColorBlindMode => {
+                Some(APP_STATE.get_color_blind_mode() != ColorBlindMode::Off)
+            }
+            _ => None,
+        }
+    }
+}
+
+// A custom menu item that runs a shell command (through cmd.exe) when clicked
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTrayItem {
+    pub label: String,
+    pub command: String,
+}
+
+const CUSTOM_ITEM_ID_PREFIX: &str = "custom-";
+// Posted to the tray thread to refresh the checkbox items. This is a thread message, so it can't
+// be confused with the AppMessages sent to border windows.
+const WM_REFRESH_CHECK_ITEMS: u32 = WM_APP + 0x100;
+
+// The thread that created the tray menu. Menu items can only be used from this thread.
+static TRAY_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    // The checkbox items in the tray menu, so their state can be refreshed when the settings they
+    // toggle change without them being clicked (e.g. when reloading the config)
+    static CHECK_ITEMS: RefCell<Vec<(BuiltInTrayItem, CheckMenuItem)>> = const {
+        RefCell::new(Vec::new())
+    };
+}
+
 pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon> {
     let icon = match Icon::from_resource(1, Some((64, 64))) {
         Ok(icon) => icon,
@@ -23,12 +158,38 @@ pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon
     // Include the application name and version number in the tray icon tooltip
     let tooltip = format!("{}{}", "tacky-borders v", env!("CARGO_PKG_VERSION"));
 
+    let tray_items = APP_STATE.config.load().tray.items.clone();
+
+    // Custom items are identified by their index into this list
+    let mut custom_commands: Vec<String> = Vec::new();
+
     let tray_menu = Menu::new();
-    tray_menu.append_items(&[
-        &MenuItem::with_id("0", "Show Config", true, None),
-        &MenuItem::with_id("1", "Reload", true, None),
-        &MenuItem::with_id("2", "Close", true, None),
-    ])?;
+    for item in tray_items.iter() {
+        match item {
+            TrayItemConfig::BuiltIn(BuiltInTrayItem::Separator) => {
+                tray_menu.append(&PredefinedMenuItem::separator())?;
+            }
+            TrayItemConfig::BuiltIn(built_in) => {
+                let id = built_in.get_id();
+                let label = built_in.get_string_id().map(get_string).unwrap_or_default();
+                match built_in.get_checked_state() {
+                    Some(is_checked) => {
+                        let check_item = CheckMenuItem::with_id(id, label, true, is_checked, None);
+                        tray_menu.append(&check_item)?;
+                        CHECK_ITEMS.with_borrow_mut(|items| items.push((*built_in, check_item)));
+                    }
+                    None => tray_menu.append(&MenuItem::with_id(id, label, true, None))?,
+                }
+            }
+            TrayItemConfig::Custom(custom) => {
+                let id = format!("{CUSTOM_ITEM_ID_PREFIX}{}", custom_commands.len());
+                tray_menu.append(&MenuItem::with_id(id, &custom.label, true, None))?;
+                custom_commands.push(custom.command.clone());
+            }
+        }
+    }
+
+    TRAY_THREAD_ID.store(unsafe { GetCurrentThreadId() }, Ordering::SeqCst);
 
     let tray_icon = TrayIconBuilder::new()
         .with_menu(Box::new(tray_menu))
@@ -52,6 +213,57 @@ pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon
                 Err(e) => error!("{e}"),
             }
         }
+        // Open Config File
+        "4" => match Config::get_path() {
+            Ok(config_path) => open_in_editor(&config_path).log_if_err(),
+            Err(e) => error!("{e}"),
+        },
+        // Open Log File
+        "5" => match Config::get_log_path() {
+            Ok(log_path) => open_in_editor(&log_path).log_if_err(),
+            Err(e) => error!("{e}"),
+        },
+        // Save Diagnostics
+        "6" => save_diagnostics(),
+        // Disable Animations
+        "7" => {
+            let are_animations_disabled = !APP_STATE.are_animations_disabled();
+            info!("animations disabled: {are_animations_disabled}");
+
+            // Borders drop (or restore) their animations and timers when reloading their appearance
+            APP_STATE.set_animations_disabled(are_animations_disabled);
+            post_message_to_all_borders(AppMessage::ReloadAppearance);
+        }
+        // Debug Overlay
+        "8" => {
+            let is_debug_overlay_enabled = !APP_STATE.is_debug_overlay_enabled();
+            info!("debug overlay enabled: {is_debug_overlay_enabled}");
+
+            // Borders create (or drop) the overlay along with their other render resources
+            APP_STATE.set_debug_overlay_enabled(is_debug_overlay_enabled);
+            post_message_to_all_borders(AppMessage::ReloadAppearance);
+        }
+        // Color-Blind Mode
+        "9" => {
+            // Turning it on uses the configured mode, or deuteranopia (the most common type) if the
+            // config leaves it off
+            let color_blind_mode = match APP_STATE.get_color_blind_mode() {
+                ColorBlindMode::Off => match APP_STATE.config.load().color_blind_mode {
+                    ColorBlindMode::Off => ColorBlindMode::Deuteranopia,
+                    configured_mode => configured_mode,
+                },
+                _ => ColorBlindMode::Off,
+            };
+            info!("color-blind mode: {color_blind_mode:?}");
+
+            APP_STATE.set_color_blind_mode(color_blind_mode);
+            post_message_to_all_borders(AppMessage::ReloadAppearance);
+        }
+        // Toggle Border for Active Window
+        "3" => {
+            let active_window = HWND(*APP_STATE.active_window.lock().unwrap() as _);
+            toggle_border_for_window(active_window);
+        }
         // Reload
         "1" => {
             Config::reload();
@@ -71,8 +283,49 @@ pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon
                 error!("attempt to unhook win event: {unhook_bool:?}; attempt to stop config watcher: {stop_res:?}");
             }
         },
-        _ => {}
+        // Custom items
+        id => {
+            let command = id
+                .strip_prefix(CUSTOM_ITEM_ID_PREFIX)
+                .and_then(|index| index.parse::<usize>().ok())
+                .and_then(|index| custom_commands.get(index));
+
+            if let Some(command) = command {
+                spawn_shell_command(command).log_if_err();
+            }
+        }
     }));
 
     tray_icon.map_err(anyhow::Error::new)
 }
+
+// Ask the tray thread to refresh the checkbox items from the current app state. This can be called
+// from any thread.
+pub fn refresh_check_items() {
+    let thread_id = TRAY_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id == 0 {
+        return;
+    }
+
+    unsafe { PostThreadMessageW(thread_id, WM_REFRESH_CHECK_ITEMS, WPARAM(0), LPARAM(0)) }
+        .context("could not post WM_REFRESH_CHECK_ITEMS")
+        .log_if_err();
+}
+
+// Handle a message posted to the tray thread itself rather than to a window. Returns false if the
+// message isn't one of ours.
+pub fn handle_thread_message(message: &MSG) -> bool {
+    if message.message != WM_REFRESH_CHECK_ITEMS {
+        return false;
+    }
+
+    CHECK_ITEMS.with_borrow(|items| {
+        for (item, check_item) in items {
+            if let Some(is_checked) = item.get_checked_state() {
+                check_item.set_checked(is_checked);
+            }
+        }
+    });
+
+    true
+}
@@ -0,0 +1,353 @@
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::ffi::c_void;
+use std::sync::Mutex;
+use std::thread;
+use std::time;
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Networking::WinHttp::{
+    WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest, WinHttpQueryDataAvailable,
+    WinHttpQueryHeaders, WinHttpReadData, WinHttpReceiveResponse, WinHttpSendRequest,
+    WinHttpSetTimeouts, INTERNET_DEFAULT_HTTPS_PORT, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+    WINHTTP_FLAG_SECURE, WINHTTP_QUERY_FLAG_NUMBER, WINHTTP_QUERY_STATUS_CODE,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Shell::{
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIIF_INFO, NIM_ADD, NIM_DELETE,
+    NIM_SETVERSION, NIN_BALLOONHIDE, NIN_BALLOONTIMEOUT, NIN_BALLOONUSERCLICK, NOTIFYICONDATAW,
+    NOTIFYICONDATAW_0, NOTIFYICON_VERSION_4,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, LoadIconW,
+    PostQuitMessage, RegisterClassExW, TranslateMessage, MSG, WINDOW_EX_STYLE, WM_APP, WM_DESTROY,
+    WNDCLASSEXW, WS_POPUP,
+};
+
+use crate::APP_STATE;
+
+// How often we ask GitHub for the latest release
+const CHECK_INTERVAL: time::Duration = time::Duration::from_secs(24 * 60 * 60);
+// How often we check whether 'check_updates' has been turned on (or off) in the config
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(60);
+const RELEASES_HOST: PCWSTR = w!("api.github.com");
+const LATEST_RELEASE_PATH: PCWSTR = w!("/repos/lukeyou05/tacky-borders/releases/latest");
+// We only ever open release pages from our own repository, whatever the API response contains
+const RELEASE_URL_PREFIX: &str = "https://github.com/lukeyou05/tacky-borders/";
+// Sent to the notification window when the user interacts with the notification
+const WM_NOTIFY_ICON: u32 = WM_APP + 1;
+
+// Release page that clicking the current notification opens
+static RELEASE_URL: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+}
+
+// Check GitHub for a newer release once per day while 'check_updates' is enabled, and show a
+// notification for each new version we find. We never download anything.
+pub fn start_update_checker() {
+    let _ = thread::spawn(|| {
+        let mut last_check: Option<time::Instant> = None;
+        let mut notified_version: Option<String> = None;
+
+        loop {
+            let is_due = last_check.is_none_or(|instant| instant.elapsed() >= CHECK_INTERVAL);
+            if APP_STATE.config.load().check_updates && is_due {
+                last_check = Some(time::Instant::now());
+
+                match get_latest_release() {
+                    Ok(release) => {
+                        let is_newer =
+                            is_newer_version(&release.tag_name, env!("CARGO_PKG_VERSION"));
+                        if is_newer && notified_version.as_ref() != Some(&release.tag_name) {
+                            info!("a new version is available: {}", release.tag_name);
+                            notified_version = Some(release.tag_name.clone());
+                            show_update_notification(&release);
+                        }
+                    }
+                    Err(err) => warn!("could not check for updates: {err:#}"),
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+fn get_latest_release() -> anyhow::Result<Release> {
+    let body = http_get(RELEASES_HOST, LATEST_RELEASE_PATH)?;
+    serde_json::from_slice(&body).context("could not parse release info")
+}
+
+// Compare versions like "v1.2.3" and "1.2.0". Pre-release suffixes (e.g. "-beta") are ignored.
+fn is_newer_version(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u32> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+
+    let (mut latest, mut current) = (parse(latest), parse(current));
+
+    // Pad the shorter version with zeros so that e.g. "1.0" and "1.0.0" compare as equal
+    let len = latest.len().max(current.len());
+    latest.resize(len, 0);
+    current.resize(len, 0);
+
+    latest > current
+}
+
+struct HttpHandle(*mut c_void);
+
+impl Drop for HttpHandle {
+    fn drop(&mut self) {
+        let _ = unsafe { WinHttpCloseHandle(self.0) };
+    }
+}
+
+fn open_handle(handle: *mut c_void, name: &str) -> anyhow::Result<HttpHandle> {
+    match handle.is_null() {
+        true => Err(windows::core::Error::from_win32()).context(format!("could not open {name}")),
+        false => Ok(HttpHandle(handle)),
+    }
+}
+
+fn http_get(host: PCWSTR, path: PCWSTR) -> anyhow::Result<Vec<u8>> {
+    unsafe {
+        // GitHub's API rejects requests without a user agent
+        let session = open_handle(
+            WinHttpOpen(
+                w!("tacky-borders"),
+                WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                0,
+            ),
+            "http session",
+        )?;
+        WinHttpSetTimeouts(session.0, 10_000, 10_000, 10_000, 10_000)?;
+
+        let connection = open_handle(
+            WinHttpConnect(session.0, host, INTERNET_DEFAULT_HTTPS_PORT, 0),
+            "http connection",
+        )?;
+        let request = open_handle(
+            WinHttpOpenRequest(
+                connection.0,
+                w!("GET"),
+                path,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                std::ptr::null(),
+                WINHTTP_FLAG_SECURE,
+            ),
+            "http request",
+        )?;
+
+        WinHttpSendRequest(request.0, None, None, 0, 0, 0).context("could not send request")?;
+        WinHttpReceiveResponse(request.0, std::ptr::null_mut())
+            .context("could not receive response")?;
+
+        let mut status_code = 0u32;
+        let mut status_code_size = size_of::<u32>() as u32;
+        WinHttpQueryHeaders(
+            request.0,
+            WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+            PCWSTR::null(),
+            Some(&mut status_code as *mut u32 as _),
+            &mut status_code_size,
+            std::ptr::null_mut(),
+        )
+        .context("could not query status code")?;
+        if status_code != 200 {
+            return Err(anyhow!("server responded with status {status_code}"));
+        }
+
+        let mut body = Vec::new();
+        loop {
+            let mut available = 0u32;
+            WinHttpQueryDataAvailable(request.0, &mut available)?;
+            if available == 0 {
+                break;
+            }
+
+            let start = body.len();
+            body.resize(start + available as usize, 0);
+            let mut read = 0u32;
+            WinHttpReadData(
+                request.0,
+                body[start..].as_mut_ptr() as _,
+                available,
+                &mut read,
+            )?;
+            body.truncate(start + read as usize);
+        }
+
+        Ok(body)
+    }
+}
+
+// Show a notification using a temporary notification area icon. It stays around until the
+// notification is clicked or dismissed, and clicking it opens the release page.
+fn show_update_notification(release: &Release) {
+    *RELEASE_URL.lock().unwrap() = release.html_url.clone();
+
+    let title = "tacky-borders update available";
+    let text = format!(
+        "{} is available (you have v{}). Click to see what's new.",
+        release.tag_name,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let _ = thread::spawn(move || {
+        if let Err(err) = run_notification(title, &text) {
+            error!("could not show update notification: {err:#}");
+        }
+    });
+}
+
+fn run_notification(title: &str, text: &str) -> anyhow::Result<()> {
+    unsafe {
+        let hinstance = GetModuleHandleW(None)?;
+
+        let window_class = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(notification_wnd_proc),
+            hInstance: hinstance.into(),
+            lpszClassName: w!("tacky-borders-update"),
+            ..Default::default()
+        };
+        // This fails harmlessly if the class was registered by an earlier notification
+        RegisterClassExW(&window_class);
+
+        let window = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("tacky-borders-update"),
+            w!("tacky-borders-update"),
+            WS_POPUP,
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            hinstance,
+            None,
+        )
+        .context("could not create notification window")?;
+
+        let mut notify_icon_data = get_notify_icon_data(window);
+        notify_icon_data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_INFO;
+        notify_icon_data.uCallbackMessage = WM_NOTIFY_ICON;
+        // The exe's icon, which is also used by the tray icon
+        notify_icon_data.hIcon = LoadIconW(hinstance, PCWSTR(1 as _)).unwrap_or_default();
+        notify_icon_data.dwInfoFlags = NIIF_INFO;
+        copy_to_wide(&mut notify_icon_data.szInfoTitle, title);
+        copy_to_wide(&mut notify_icon_data.szInfo, text);
+
+        if !Shell_NotifyIconW(NIM_ADD, &notify_icon_data).as_bool() {
+            let _ = DestroyWindow(window);
+            return Err(anyhow!("could not add notification icon"));
+        }
+        // Version 4 tells us when the notification is clicked or dismissed
+        notify_icon_data.Anonymous = NOTIFYICONDATAW_0 {
+            uVersion: NOTIFYICON_VERSION_4,
+        };
+        let _ = Shell_NotifyIconW(NIM_SETVERSION, &notify_icon_data);
+
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, HWND::default(), 0, 0).into() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn notification_wnd_proc(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match message {
+        // With NOTIFYICON_VERSION_4, the event is in the low word of lparam
+        WM_NOTIFY_ICON => match lparam.0 as u32 & 0xffff {
+            NIN_BALLOONUSERCLICK => {
+                let release_url = RELEASE_URL.lock().unwrap().clone();
+                if !release_url.starts_with(RELEASE_URL_PREFIX) {
+                    error!("refusing to open unexpected release url: {release_url}");
+                } else if let Err(err) = open::that(&release_url) {
+                    error!("could not open {release_url}: {err}");
+                }
+                let _ = DestroyWindow(window);
+            }
+            NIN_BALLOONTIMEOUT | NIN_BALLOONHIDE => {
+                let _ = DestroyWindow(window);
+            }
+            _ => {}
+        },
+        WM_DESTROY => {
+            let _ = Shell_NotifyIconW(NIM_DELETE, &get_notify_icon_data(window));
+            PostQuitMessage(0);
+        }
+        _ => return DefWindowProcW(window, message, wparam, lparam),
+    }
+
+    LRESULT(0)
+}
+
+fn get_notify_icon_data(window: HWND) -> NOTIFYICONDATAW {
+    NOTIFYICONDATAW {
+        cbSize: size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: window,
+        uID: 1,
+        ..Default::default()
+    }
+}
+
+// Copy a string into a fixed-size, null-terminated UTF-16 buffer, truncating it if necessary
+fn copy_to_wide(buffer: &mut [u16], string: &str) {
+    let max_len = buffer.len() - 1;
+    for (dst, src) in buffer.iter_mut().zip(string.encode_utf16().take(max_len)) {
+        *dst = src;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(is_newer_version("v1.0.1", "1.0.0"));
+        assert!(is_newer_version("v1.10.0", "1.9.3"));
+        assert!(is_newer_version("2.0", "1.9.9"));
+        assert!(!is_newer_version("v1.0.0", "1.0.0"));
+        assert!(!is_newer_version("v0.9.12", "1.0.0"));
+    }
+
+    #[test]
+    fn ignores_prerelease_suffixes() {
+        assert!(!is_newer_version("v1.0.0-beta.2", "1.0.0"));
+        assert!(is_newer_version("v1.1.0-rc1", "1.0.0"));
+    }
+
+    #[test]
+    fn treats_missing_parts_as_zero() {
+        assert!(!is_newer_version("1.0.0", "1.0"));
+        assert!(!is_newer_version("v1.0.0", "1.0"));
+        assert!(!is_newer_version("1.0", "1.0.0"));
+        assert!(is_newer_version("1.0.1", "1.0"));
+        assert!(!is_newer_version("1.0", "1.0.1"));
+    }
+}
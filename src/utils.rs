@@ -1,39 +1,141 @@
+use windows::core::{w, PCWSTR, PWSTR};
+use windows::Wdk::System::Threading::{NtQueryInformationProcess, ProcessCommandLineInformation};
 use windows::Win32::Foundation::{
-    GetLastError, SetLastError, BOOL, ERROR_ENVVAR_NOT_FOUND, ERROR_INVALID_WINDOW_HANDLE,
-    ERROR_SUCCESS, FALSE, HWND, LPARAM, RECT, WPARAM,
+    CloseHandle, GetLastError, SetLastError, BOOL, COLORREF, ERROR_ENVVAR_NOT_FOUND,
+    ERROR_INVALID_WINDOW_HANDLE, ERROR_SUCCESS, E_ACCESSDENIED, FALSE, HANDLE, HWND, LPARAM, POINT,
+    RECT, STATUS_INFO_LENGTH_MISMATCH, TRUE, UNICODE_STRING, WPARAM,
 };
+use windows::Win32::Graphics::Direct2D::Common::D2D_RECT_F;
 use windows::Win32::Graphics::Dwm::{
-    DwmGetWindowAttribute, DWMWA_CLOAKED, DWMWA_WINDOW_CORNER_PREFERENCE,
+    DwmGetWindowAttribute, DwmIsCompositionEnabled, DwmSetWindowAttribute, DWMWA_BORDER_COLOR,
+    DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS, DWMWA_WINDOW_CORNER_PREFERENCE,
     DWM_WINDOW_CORNER_PREFERENCE,
 };
+use windows::Win32::Graphics::Gdi::{
+    CreateRectRgn, DeleteObject, GetMonitorInfoW, GetWindowRgn, MonitorFromWindow, PtInRegion,
+    COMPLEXREGION, HMONITOR, MONITORINFO, MONITOR_DEFAULTTONEAREST, SIMPLEREGION,
+};
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, CREATE_NO_WINDOW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+};
 use windows::Win32::UI::HiDpi::{
     GetDpiForWindow, SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT,
 };
 use windows::Win32::UI::Input::Ime::ImmDisableIME;
 use windows::Win32::UI::WindowsAndMessaging::{
-    GetForegroundWindow, GetWindowLongW, GetWindowTextW, IsIconic, IsWindowVisible, PostMessageW,
-    RealGetWindowClassW, SendNotifyMessageW, GWL_EXSTYLE, GWL_STYLE, WINDOW_EX_STYLE, WINDOW_STYLE,
-    WM_APP, WM_NCDESTROY, WS_CHILD, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_WINDOWEDGE,
-    WS_MAXIMIZE,
+    GetCursorPos, GetForegroundWindow, GetGUIThreadInfo, GetLayeredWindowAttributes,
+    GetShellWindow, GetSystemMetrics, GetWindow, GetWindowLongW, GetWindowRect, GetWindowTextW,
+    GetWindowThreadProcessId, IsIconic, IsWindow, IsWindowVisible, PostMessageW,
+    RealGetWindowClassW, SendMessageTimeoutW, SendNotifyMessageW, SystemParametersInfoW,
+    GUITHREADINFO, GUI_INMOVESIZE, GWL_EXSTYLE, GWL_STYLE, GW_HWNDPREV, GW_OWNER, HTMAXBUTTON,
+    LAYERED_WINDOW_ATTRIBUTES_FLAGS, LWA_ALPHA, SMTO_ABORTIFHUNG, SM_REMOTESESSION,
+    SPI_GETDRAGFULLWINDOWS, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WINDOW_EX_STYLE, WINDOW_STYLE,
+    WM_APP, WM_NCHITTEST, WS_CHILD, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW,
+    WS_EX_WINDOWEDGE, WS_MAXIMIZE,
 };
 
 use anyhow::{anyhow, Context};
 use regex::Regex;
+use std::env;
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::os::windows::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
 use std::ptr;
 use std::thread;
+use std::time;
 
-use crate::border_config::{EnableMode, MatchKind, MatchStrategy, WindowRule};
+use crate::border_config::{Config, EnableMode, MatchKind, MatchStrategy, WindowRule};
 use crate::window_border::WindowBorder;
+use crate::window_system::{Win32WindowSystem, WindowSystem};
 use crate::APP_STATE;
 
-pub const WM_APP_LOCATIONCHANGE: u32 = WM_APP;
-pub const WM_APP_REORDER: u32 = WM_APP + 1;
-pub const WM_APP_FOREGROUND: u32 = WM_APP + 2;
-pub const WM_APP_SHOWUNCLOAKED: u32 = WM_APP + 3;
-pub const WM_APP_HIDECLOAKED: u32 = WM_APP + 4;
-pub const WM_APP_MINIMIZESTART: u32 = WM_APP + 5;
-pub const WM_APP_MINIMIZEEND: u32 = WM_APP + 6;
-pub const WM_APP_ANIMATE: u32 = WM_APP + 7;
+// Custom messages posted to border windows. Each variant maps to WM_APP + n, so new messages
+// should only ever be appended to the end of this list to avoid changing existing values.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppMessage {
+    // EVENT_OBJECT_LOCATIONCHANGE
+    LocationChange = WM_APP,
+    // EVENT_OBJECT_REORDER
+    Reorder,
+    // EVENT_SYSTEM_FOREGROUND
+    Foreground,
+    // EVENT_OBJECT_SHOW / EVENT_OBJECT_UNCLOAKED
+    ShowUncloaked,
+    // EVENT_OBJECT_HIDE / EVENT_OBJECT_CLOAKED
+    HideCloaked,
+    // EVENT_SYSTEM_MINIMIZESTART
+    MinimizeStart,
+    // EVENT_SYSTEM_MINIMIZEEND
+    MinimizeEnd,
+    // Posted by the animation timer
+    Animate,
+    // Hide the border and stop animations until Resume (e.g. for game mode)
+    Suspend,
+    Resume,
+    // Force the border's active state regardless of the foreground window. WPARAM is non-zero for
+    // active and zero for inactive. This lasts until the next foreground change.
+    SetColor,
+    // Re-read the window rule and config for this border without recreating it
+    ReloadAppearance,
+    // Destroy the border (e.g. when its window closes or the borders are reloaded). This is also
+    // checked after the initialize delay, so borders for windows that close while the border is
+    // still initializing are never shown.
+    Destroy,
+    // EVENT_OBJECT_NAMECHANGE. Only sent if a window rule matches on titles.
+    NameChange,
+    // EVENT_SYSTEM_MOVESIZESTART
+    MoveSizeStart,
+    // EVENT_SYSTEM_MOVESIZEEND
+    MoveSizeEnd,
+    // Re-render the border as is. Sent for 'extra_update_events' with the Render action.
+    Render,
+    // Sent by the audio watcher when the tracking window's process starts or stops playing audio.
+    // WPARAM is non-zero if audio is playing.
+    SetAudioPlaying,
+    // Sent periodically by the watchdog to check that the border thread isn't stuck
+    Heartbeat,
+}
+
+impl From<AppMessage> for u32 {
+    fn from(message: AppMessage) -> Self {
+        message as u32
+    }
+}
+
+impl TryFrom<u32> for AppMessage {
+    type Error = u32;
+
+    fn try_from(message: u32) -> Result<Self, Self::Error> {
+        let app_message = match message.wrapping_sub(WM_APP) {
+            0 => AppMessage::LocationChange,
+            1 => AppMessage::Reorder,
+            2 => AppMessage::Foreground,
+            3 => AppMessage::ShowUncloaked,
+            4 => AppMessage::HideCloaked,
+            5 => AppMessage::MinimizeStart,
+            6 => AppMessage::MinimizeEnd,
+            7 => AppMessage::Animate,
+            8 => AppMessage::Suspend,
+            9 => AppMessage::Resume,
+            10 => AppMessage::SetColor,
+            11 => AppMessage::ReloadAppearance,
+            12 => AppMessage::Destroy,
+            13 => AppMessage::NameChange,
+            14 => AppMessage::MoveSizeStart,
+            15 => AppMessage::MoveSizeEnd,
+            16 => AppMessage::Render,
+            17 => AppMessage::SetAudioPlaying,
+            18 => AppMessage::Heartbeat,
+            _ => return Err(message),
+        };
+
+        Ok(app_message)
+    }
+}
 
 pub trait LogIfErr {
     fn log_if_err(&self);
@@ -75,6 +177,153 @@ pub fn has_filtered_style(hwnd: HWND) -> bool {
     ex_style.contains(WS_EX_TOOLWINDOW) || ex_style.contains(WS_EX_NOACTIVATE)
 }
 
+// Check if the window is completely covered by a single window above it. This is a cheap
+// approximation that ignores windows only covered by a combination of other windows.
+pub fn is_window_occluded(hwnd: HWND) -> bool {
+    match get_visible_window_rect(hwnd) {
+        Some(window_rect) => is_rect_occluded(hwnd, &window_rect),
+        None => false,
+    }
+}
+
+// Check if the given rect is completely covered by a single window above the given window. Like
+// is_window_occluded, this ignores rects only covered by a combination of other windows.
+pub fn is_rect_occluded(hwnd: HWND, rect: &RECT) -> bool {
+    let mut hwnd_above = unsafe { GetWindow(hwnd, GW_HWNDPREV) };
+    while let Ok(above) = hwnd_above {
+        // Only count regular app windows; tool windows include overlays and our own borders
+        if is_window_visible(above)
+            && !is_window_cloaked(above)
+            && !is_window_minimized(above)
+            && !has_filtered_style(above)
+            && get_visible_window_rect(above)
+                .is_some_and(|above_rect| does_rect_contain(&above_rect, rect))
+        {
+            return true;
+        }
+
+        hwnd_above = unsafe { GetWindow(above, GW_HWNDPREV) };
+    }
+
+    false
+}
+
+pub fn get_visible_window_rect(hwnd: HWND) -> Option<RECT> {
+    let mut rect = RECT::default();
+
+    unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            ptr::addr_of_mut!(rect) as _,
+            size_of::<RECT>() as u32,
+        )
+    }
+    .ok()
+    .map(|_| rect)
+}
+
+fn does_rect_contain(outer: &RECT, inner: &RECT) -> bool {
+    outer.left <= inner.left
+        && outer.top <= inner.top
+        && outer.right >= inner.right
+        && outer.bottom >= inner.bottom
+}
+
+// How long after a window closes we consider a new, matching window to be its replacement, and how
+// far (in pixels) each edge of the replacement's rect may be from the closed window's
+const REPLACEMENT_WINDOW_MS: u128 = 500;
+const REPLACEMENT_RECT_TOLERANCE: i32 = 16;
+
+// A window that recently closed, kept around to detect windows that replace themselves (e.g.
+// legacy apps that recreate their window when the dpi changes)
+#[derive(Debug, Clone)]
+pub struct ClosedWindow {
+    pub process_id: u32,
+    pub class: String,
+    pub rect: RECT,
+    pub time: time::Instant,
+}
+
+pub fn record_closed_window(closed_window: ClosedWindow) {
+    let mut recently_closed = APP_STATE.recently_closed.lock().unwrap();
+    recently_closed.retain(|closed| closed.time.elapsed().as_millis() <= REPLACEMENT_WINDOW_MS);
+    recently_closed.push(closed_window);
+}
+
+// Check whether the window replaces one that just closed (same process and class at nearly the
+// same position). If so, the closed window's entry is consumed so it only matches once.
+pub fn take_replaced_window(hwnd: HWND) -> bool {
+    let mut recently_closed = APP_STATE.recently_closed.lock().unwrap();
+    recently_closed.retain(|closed| closed.time.elapsed().as_millis() <= REPLACEMENT_WINDOW_MS);
+
+    if recently_closed.is_empty() {
+        return false;
+    }
+
+    let process_id = get_window_process_id(hwnd);
+    let (Ok(class), Some(rect)) = (get_window_class(hwnd), get_visible_window_rect(hwnd)) else {
+        return false;
+    };
+
+    let is_near = |a: i32, b: i32| (a - b).abs() <= REPLACEMENT_RECT_TOLERANCE;
+    let index = recently_closed.iter().position(|closed| {
+        closed.process_id == process_id
+            && closed.class == class
+            && is_near(closed.rect.left, rect.left)
+            && is_near(closed.rect.top, rect.top)
+            && is_near(closed.rect.right, rect.right)
+            && is_near(closed.rect.bottom, rect.bottom)
+    });
+
+    match index {
+        Some(index) => {
+            debug!("{hwnd:?} replaced a window that just closed; skipping its initialize delay");
+            recently_closed.swap_remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn get_window_process_id(hwnd: HWND) -> u32 {
+    let mut process_id = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut process_id)) };
+    process_id
+}
+
+// Create the border for a window that was skipped at startup (see Config::lazy_startup), if any
+pub fn create_deferred_border(hwnd: HWND) {
+    let was_deferred = APP_STATE
+        .deferred_windows
+        .lock()
+        .unwrap()
+        .remove(&(hwnd.0 as isize));
+
+    if was_deferred {
+        debug!("creating deferred border for {hwnd:?}");
+        show_border_for_window(hwnd);
+    }
+}
+
+// Create borders for deferred windows that are no longer covered by another window
+pub fn create_uncovered_deferred_borders() {
+    // Copy the windows out first so we don't hold the lock during the z-order walks below
+    let deferred: Vec<isize> = APP_STATE
+        .deferred_windows
+        .lock()
+        .unwrap()
+        .iter()
+        .copied()
+        .collect();
+
+    for hwnd_isize in deferred {
+        if !is_window_occluded(HWND(hwnd_isize as _)) {
+            create_deferred_border(HWND(hwnd_isize as _));
+        }
+    }
+}
+
 pub fn get_window_title(hwnd: HWND) -> anyhow::Result<String> {
     let mut title_arr: [u16; 256] = [0; 256];
 
@@ -119,30 +368,339 @@ pub fn get_window_class(hwnd: HWND) -> anyhow::Result<String> {
     Ok(class_binding.split_once("\0").unwrap().0.to_string())
 }
 
-// Get the window rule from 'window_rules' in the config
-pub fn get_window_rule(hwnd: HWND) -> WindowRule {
-    let title = match get_window_title(hwnd) {
-        Ok(val) => val,
+// Open the window's process with limited query access. The caller is responsible for closing the
+// returned handle.
+fn open_window_process(hwnd: HWND) -> anyhow::Result<(HANDLE, u32)> {
+    // Don't keep retrying windows we already know we can't open
+    if is_window_access_denied(hwnd, ProcessAccess::Query) {
+        return Err(windows::core::Error::from(E_ACCESSDENIED)).context(format!(
+            "access to the process of {hwnd:?} was previously denied"
+        ));
+    }
+
+    let mut process_id = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut process_id)) };
+
+    match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id) } {
+        Ok(process_handle) => Ok((process_handle, process_id)),
         Err(err) => {
-            error!("could not retrieve window title for {hwnd:?}: {err}");
-            "".to_string()
+            if err.code() == E_ACCESSDENIED {
+                record_access_denied_window(hwnd, ProcessAccess::Query);
+            }
+            Err(err).context(format!("could not open process {process_id}"))
         }
+    }
+}
+
+pub fn get_process_path(hwnd: HWND) -> anyhow::Result<String> {
+    let (process_handle, process_id) = open_window_process(hwnd)?;
+    query_process_path(process_handle, process_id)
+}
+
+// Like get_process_path, but for processes that don't necessarily own a window (e.g. a browser's
+// audio process)
+pub fn get_process_path_by_id(process_id: u32) -> anyhow::Result<String> {
+    let process_handle =
+        unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id) }
+            .context(format!("could not open process {process_id}"))?;
+    query_process_path(process_handle, process_id)
+}
+
+// Query the process's image path and close the handle afterwards
+fn query_process_path(process_handle: HANDLE, process_id: u32) -> anyhow::Result<String> {
+    let mut path_arr: [u16; 1024] = [0; 1024];
+    let mut path_len = path_arr.len() as u32;
+
+    let query_res = unsafe {
+        QueryFullProcessImageNameW(
+            process_handle,
+            PROCESS_NAME_WIN32,
+            PWSTR(path_arr.as_mut_ptr()),
+            &mut path_len,
+        )
     };
+    unsafe { CloseHandle(process_handle) }.log_if_err();
+    query_res.context(format!(
+        "could not query image name of process {process_id}"
+    ))?;
 
-    let class = match get_window_class(hwnd) {
-        Ok(val) => val,
-        Err(err) => {
-            error!("could not retrieve window class for {hwnd:?}: {err}");
-            "".to_string()
+    Ok(String::from_utf16_lossy(&path_arr[..path_len as usize]))
+}
+
+pub fn get_process_command_line(hwnd: HWND) -> anyhow::Result<String> {
+    let (process_handle, process_id) = open_window_process(hwnd)?;
+    let query_res = query_command_line(process_handle);
+    unsafe { CloseHandle(process_handle) }.log_if_err();
+
+    query_res.context(format!(
+        "could not query command line of process {process_id}"
+    ))
+}
+
+// ProcessCommandLineInformation only needs limited query access (unlike reading the command line
+// out of the process's memory), so it works for most processes we can get the path of
+fn query_command_line(process_handle: HANDLE) -> windows::core::Result<String> {
+    unsafe {
+        // The first call fails, but tells us how big of a buffer we need
+        let mut buffer_len = 0u32;
+        let status = NtQueryInformationProcess(
+            process_handle,
+            ProcessCommandLineInformation,
+            ptr::null_mut(),
+            0,
+            &mut buffer_len,
+        );
+        if status != STATUS_INFO_LENGTH_MISMATCH {
+            status.ok()?;
         }
-    };
 
-    let config = APP_STATE.config.read().unwrap();
+        // The buffer holds a UNICODE_STRING followed by the string it points to. We use u64s to
+        // make sure it's aligned for the UNICODE_STRING.
+        let mut buffer = vec![0u64; (buffer_len as usize).div_ceil(size_of::<u64>())];
+        NtQueryInformationProcess(
+            process_handle,
+            ProcessCommandLineInformation,
+            buffer.as_mut_ptr() as _,
+            (buffer.len() * size_of::<u64>()) as u32,
+            &mut buffer_len,
+        )
+        .ok()?;
+
+        // Processes without a command line (e.g. some system processes) return an empty string
+        // whose buffer may be null, which from_raw_parts doesn't allow
+        let command_line = &*(buffer.as_ptr() as *const UNICODE_STRING);
+        if command_line.Length == 0 || command_line.Buffer.is_null() {
+            return Ok(String::new());
+        }
+
+        let command_line_wide = std::slice::from_raw_parts(
+            command_line.Buffer.0,
+            command_line.Length as usize / size_of::<u16>(),
+        );
 
-    for rule in config.window_rules.iter() {
-        let window_name = match rule.kind {
-            Some(MatchKind::Title) => &title,
-            Some(MatchKind::Class) => &class,
+        Ok(String::from_utf16_lossy(command_line_wide))
+    }
+}
+
+// Windows belonging to processes at a higher integrity level (e.g. elevated apps, UAC prompts, or the
+// secure desktop) can't be queried because of UIPI. Those failures are expected, so callers can use
+// this to avoid logging them as errors.
+pub fn is_access_denied(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<windows::core::Error>()
+        .is_some_and(|err| err.code() == E_ACCESSDENIED)
+}
+
+// The kinds of access to a window's process that we ask for. Elevated processes usually still grant
+// Query but not ReadModules, so being denied one doesn't mean being denied the other. Being denied
+// Query does imply being denied ReadModules though, which is what the ordering reflects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProcessAccess {
+    // PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, used to read the process's loaded modules
+    ReadModules,
+    // PROCESS_QUERY_LIMITED_INFORMATION, used to get the process's image name and command line
+    Query,
+}
+
+pub fn is_window_access_denied(hwnd: HWND, access: ProcessAccess) -> bool {
+    APP_STATE
+        .access_denied_windows
+        .lock()
+        .unwrap()
+        .get(&(hwnd.0 as isize))
+        .is_some_and(|&denied| denied >= access)
+}
+
+pub fn record_access_denied_window(hwnd: HWND, access: ProcessAccess) {
+    let mut access_denied_windows = APP_STATE.access_denied_windows.lock().unwrap();
+
+    // Forget about windows that have since been destroyed so that reused handles aren't affected
+    access_denied_windows.retain(|&window, _| unsafe { IsWindow(HWND(window as _)) }.as_bool());
+
+    let window = hwnd.0 as isize;
+    if access_denied_windows
+        .get(&window)
+        .is_some_and(|&denied| denied >= access)
+    {
+        return;
+    }
+    access_denied_windows.insert(window, access);
+
+    debug!("{access:?} access denied to the process of {hwnd:?}; it likely runs elevated");
+}
+
+// Return the number of existing windows whose processes denied us the given access
+pub fn count_access_denied_windows(access: ProcessAccess) -> usize {
+    APP_STATE
+        .access_denied_windows
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, &denied)| denied >= access)
+        .filter(|(&window, _)| unsafe { IsWindow(HWND(window as _)) }.as_bool())
+        .count()
+}
+
+// Run a command through cmd.exe without blocking or showing a console window
+pub fn spawn_shell_command(command: &str) -> anyhow::Result<()> {
+    Command::new("cmd")
+        .arg("/C")
+        .raw_arg(command)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .spawn()
+        .context(format!("could not run command: {command}"))?;
+
+    Ok(())
+}
+
+// Open a file in the user's editor without blocking. The editor is chosen from the `editor` config
+// option, then the EDITOR/VISUAL environment variables, and finally the default app for the file.
+pub fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = APP_STATE
+        .config
+        .load()
+        .editor
+        .clone()
+        .or_else(|| env::var("EDITOR").ok())
+        .or_else(|| env::var("VISUAL").ok())
+        .filter(|editor| !editor.trim().is_empty());
+
+    match editor {
+        // Use 'start' so console editors (e.g. vim) get their own window
+        Some(editor) => spawn_shell_command(&format!("start \"\" {editor} \"{}\"", path.display())),
+        None => open::that_detached(path)
+            .or_else(|_| spawn_shell_command(&format!("notepad \"{}\"", path.display())))
+            .context(format!("could not open {}", path.display())),
+    }
+}
+
+// Get the process name without its extension (e.g. "firefox" instead of "firefox.exe")
+pub fn get_process_name(hwnd: HWND) -> anyhow::Result<String> {
+    let process_path = get_process_path(hwnd)?;
+
+    Path::new(&process_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.to_string())
+        .context(format!("could not get process name from {process_path}"))
+}
+
+// Check if the window's process or class is in the config's exclusion lists. This is checked
+// before window rules, so excluded windows never get a border.
+pub fn is_window_excluded(hwnd: HWND) -> bool {
+    let config = APP_STATE.config.load();
+
+    if !config.exclude_processes.is_empty() {
+        match get_process_name(hwnd) {
+            Ok(process) if config.exclude_processes.contains(&process.to_lowercase()) => {
+                return true
+            }
+            Ok(_) => {}
+            Err(err) if is_access_denied(&err) => {}
+            Err(err) => error!("could not retrieve window process for {hwnd:?}: {err}"),
+        }
+    }
+
+    if !config.exclude_classes.is_empty() {
+        match get_window_class(hwnd) {
+            Ok(class) if config.exclude_classes.contains(&class.to_lowercase()) => return true,
+            Ok(_) => {}
+            Err(err) => error!("could not retrieve window class for {hwnd:?}: {err}"),
+        }
+    }
+
+    false
+}
+
+// Get the window rule from 'window_rules' in the config
+pub fn get_window_rule(hwnd: HWND) -> WindowRule {
+    let config = APP_STATE.config.load();
+
+    // Earlier rules take precedence, with later matching rules filling in unspecified fields
+    get_window_rule_indices(hwnd, &config).into_iter().fold(
+        WindowRule::default(),
+        |mut window_rule, index| {
+            window_rule.merge_from(&config.window_rules[index]);
+            window_rule
+        },
+    )
+}
+
+// Get the index of the first window rule in the config that matches the window
+pub fn get_window_rule_index(hwnd: HWND, config: &Config) -> Option<usize> {
+    get_window_rule_indices(hwnd, config).first().copied()
+}
+
+// Get the indices of the window rules that apply to the window. Matching stops at the first rule
+// that doesn't have 'merge' set (or that has 'stop' set).
+pub fn get_window_rule_indices(hwnd: HWND, config: &Config) -> Vec<usize> {
+    get_window_rule_indices_with(&Win32WindowSystem, hwnd, config)
+}
+
+pub fn get_window_rule_indices_with(
+    system: &impl WindowSystem,
+    hwnd: HWND,
+    config: &Config,
+) -> Vec<usize> {
+    let plan = &config.window_rule_plan;
+
+    // Only retrieve the window properties that the rules actually match on
+    let get_property =
+        |is_needed: bool, kind: &str, get_fn: &dyn Fn() -> anyhow::Result<String>| {
+            if !is_needed {
+                return String::new();
+            }
+
+            match get_fn() {
+                Ok(val) => val,
+                Err(err) if is_access_denied(&err) => {
+                    debug!("could not retrieve window {kind} for {hwnd:?}: {err}");
+                    String::new()
+                }
+                Err(err) => {
+                    error!("could not retrieve window {kind} for {hwnd:?}: {err}");
+                    String::new()
+                }
+            }
+        };
+
+    let process = get_property(plan.needs_process, "process", &|| {
+        system.get_process_name(hwnd)
+    });
+
+    // Fast path for when every rule is an exact process name match
+    if let Some(process_lookup) = &plan.process_lookup {
+        return process_lookup
+            .get(&process.to_lowercase())
+            .copied()
+            .into_iter()
+            .collect();
+    }
+
+    let title = get_property(plan.needs_title, "title", &|| system.get_window_title(hwnd));
+    let class = get_property(plan.needs_class, "class", &|| system.get_window_class(hwnd));
+    let path = get_property(plan.needs_path, "process path", &|| {
+        system.get_process_path(hwnd)
+    });
+    let command_line = get_property(plan.needs_command_line, "command line", &|| {
+        system.get_process_command_line(hwnd)
+    });
+
+    // Lowercase these once up front rather than for every rule
+    let title_lower = title.to_lowercase();
+    let class_lower = class.to_lowercase();
+    let process_lower = process.to_lowercase();
+    let path_lower = path.to_lowercase();
+    let command_line_lower = command_line.to_lowercase();
+
+    let mut indices = Vec::new();
+
+    for (index, rule) in config.window_rules.iter().enumerate() {
+        let (window_name, window_name_lower) = match rule.kind {
+            Some(MatchKind::Title) => (&title, &title_lower),
+            Some(MatchKind::Class) => (&class, &class_lower),
+            Some(MatchKind::Process) => (&process, &process_lower),
+            Some(MatchKind::Path) => (&path, &path_lower),
+            Some(MatchKind::CommandLine) => (&command_line, &command_line_lower),
             None => {
                 error!("expected 'match' for window rule but none found!");
                 continue;
@@ -156,25 +714,24 @@ pub fn get_window_rule(hwnd: HWND) -> WindowRule {
 
         // Check if the window rule matches the window
         let has_match = match rule.strategy {
-            Some(MatchStrategy::Equals) | None => {
-                window_name.to_lowercase().eq(&match_name.to_lowercase())
-            }
-            Some(MatchStrategy::Contains) => window_name
-                .to_lowercase()
-                .contains(&match_name.to_lowercase()),
+            Some(MatchStrategy::Equals) | None => window_name_lower.eq(&match_name.to_lowercase()),
+            Some(MatchStrategy::Contains) => window_name_lower.contains(&match_name.to_lowercase()),
             Some(MatchStrategy::Regex) => Regex::new(match_name)
                 .unwrap()
                 .captures(window_name)
                 .is_some(),
         };
 
-        // Return the first match
         if has_match {
-            return rule.clone();
+            indices.push(index);
+
+            if !rule.is_fallthrough() {
+                break;
+            }
         }
     }
 
-    WindowRule::default()
+    indices
 }
 
 pub fn is_window_visible(hwnd: HWND) -> bool {
@@ -190,6 +747,48 @@ pub fn are_rects_same_size(rect1: &RECT, rect2: &RECT) -> bool {
         && rect1.bottom - rect1.top == rect2.bottom - rect2.top
 }
 
+// Linearly extrapolate the next rect using the change between the previous and current rects
+pub fn predict_next_rect(prev_rect: &RECT, curr_rect: &RECT) -> RECT {
+    RECT {
+        left: curr_rect.left + (curr_rect.left - prev_rect.left),
+        top: curr_rect.top + (curr_rect.top - prev_rect.top),
+        right: curr_rect.right + (curr_rect.right - prev_rect.right),
+        bottom: curr_rect.bottom + (curr_rect.bottom - prev_rect.bottom),
+    }
+}
+
+// Align a stroke's edges to whole pixels so it isn't antialiased across pixel boundaries. The
+// rect's coordinates are the center of the stroke, so odd widths end up centered on half pixels.
+// Ties always round up (f32::round rounds them away from zero), so opposite edges that are the same
+// distance from a pixel move the same way and the rect keeps its size.
+pub fn snap_stroke_rect(rect: &D2D_RECT_F, stroke_width: i32) -> D2D_RECT_F {
+    let half_width = stroke_width as f32 / 2.0;
+    let snap = |coord: f32| (coord - half_width + 0.5).floor() + half_width;
+
+    D2D_RECT_F {
+        left: snap(rect.left),
+        top: snap(rect.top),
+        right: snap(rect.right),
+        bottom: snap(rect.bottom),
+    }
+}
+
+// Check whether the window is currently being moved or resized by the user (i.e. dragging)
+pub fn is_window_in_move_size(hwnd: HWND) -> bool {
+    let mut gui_thread_info = GUITHREADINFO {
+        cbSize: size_of::<GUITHREADINFO>() as u32,
+        ..Default::default()
+    };
+
+    let thread_id = unsafe { GetWindowThreadProcessId(hwnd, None) };
+    if let Err(e) = unsafe { GetGUIThreadInfo(thread_id, &mut gui_thread_info) } {
+        debug!("could not retrieve gui thread info for {hwnd:?}: {e}");
+        return false;
+    }
+
+    gui_thread_info.flags.contains(GUI_INMOVESIZE)
+}
+
 pub fn is_window_cloaked(hwnd: HWND) -> bool {
     let mut is_cloaked = FALSE;
     if let Err(e) = unsafe {
@@ -206,6 +805,68 @@ pub fn is_window_cloaked(hwnd: HWND) -> bool {
     is_cloaked.as_bool()
 }
 
+pub fn get_monitor_rect(hwnd: HWND) -> Option<RECT> {
+    get_monitor_info(hwnd).map(|monitor_info| monitor_info.rcMonitor)
+}
+
+// Get the monitor's rect excluding the taskbar and any docked app bars
+pub fn get_monitor_work_area(hwnd: HWND) -> Option<RECT> {
+    get_monitor_info(hwnd).map(|monitor_info| monitor_info.rcWork)
+}
+
+fn get_monitor_info(hwnd: HWND) -> Option<MONITORINFO> {
+    let hmonitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+
+    let mut monitor_info = MONITORINFO {
+        cbSize: size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    if !unsafe { GetMonitorInfoW(hmonitor, &mut monitor_info) }.as_bool() {
+        error!("could not retrieve monitor info for {hwnd:?}");
+        return None;
+    }
+
+    Some(monitor_info)
+}
+
+// Windows 10's Task View has its own window class. Windows 11 hosts Task View and the task
+// switcher (Alt+Tab) in XamlExplorerHostIslandWindow along with other shell flyouts, so there we
+// also have to check the title.
+// NOTE: the titles are localized, so Windows 11's Task View is only detected in English
+const TASK_VIEW_CLASS: &str = "MultitaskingViewFrame";
+const TASK_VIEW_TITLES: &[&str] = &["Task View", "Task Switching"];
+
+pub fn is_task_view_window(hwnd: HWND) -> bool {
+    match get_window_class(hwnd) {
+        Ok(class) if class == TASK_VIEW_CLASS => true,
+        Ok(class) if class == XAML_HOST_CLASS => {
+            get_window_title(hwnd).is_ok_and(|title| TASK_VIEW_TITLES.contains(&title.as_str()))
+        }
+        _ => false,
+    }
+}
+
+// Check if the window covers its entire monitor (e.g. a fullscreen game or video)
+pub fn is_window_fullscreen(hwnd: HWND) -> bool {
+    // The desktop window also covers the entire monitor, so we have to ignore it
+    if hwnd.is_invalid() || hwnd == unsafe { GetShellWindow() } {
+        return false;
+    }
+
+    let mut window_rect = RECT::default();
+    if let Err(e) = unsafe { GetWindowRect(hwnd, &mut window_rect) } {
+        error!("could not retrieve window rect for {hwnd:?}: {e}");
+        return false;
+    }
+
+    get_monitor_rect(hwnd).is_some_and(|monitor_rect| {
+        window_rect.left <= monitor_rect.left
+            && window_rect.top <= monitor_rect.top
+            && window_rect.right >= monitor_rect.right
+            && window_rect.bottom >= monitor_rect.bottom
+    })
+}
+
 pub fn get_foreground_window() -> HWND {
     unsafe { GetForegroundWindow() }
 }
@@ -214,6 +875,26 @@ pub fn is_window_minimized(hwnd: HWND) -> bool {
     unsafe { IsIconic(hwnd).as_bool() }
 }
 
+// Whether "Show window contents while dragging" is enabled. When it's off, windows only move as an
+// outline during drags and jump to their final position once the drag ends.
+pub fn is_drag_full_windows_enabled() -> bool {
+    let mut is_enabled = TRUE;
+    match unsafe {
+        SystemParametersInfoW(
+            SPI_GETDRAGFULLWINDOWS,
+            0,
+            Some(ptr::addr_of_mut!(is_enabled) as _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    } {
+        Ok(_) => is_enabled.as_bool(),
+        Err(err) => {
+            error!("could not retrieve drag full windows setting: {err}");
+            true
+        }
+    }
+}
+
 pub fn post_message_w(
     hwnd: HWND,
     msg: u32,
@@ -242,6 +923,24 @@ pub fn set_process_dpi_awareness_context(
     unsafe { SetProcessDpiAwarenessContext(value) }
 }
 
+// Get the window's own alpha if it's a layered window (e.g. made translucent by a tool like
+// Vitrite or Glass2k). Otherwise, the window is fully opaque.
+pub fn get_window_alpha(hwnd: HWND) -> u8 {
+    if !get_window_ex_style(hwnd).contains(WS_EX_LAYERED) {
+        return 255;
+    }
+
+    let mut alpha = 255u8;
+    let mut flags = LAYERED_WINDOW_ATTRIBUTES_FLAGS::default();
+    let res = unsafe { GetLayeredWindowAttributes(hwnd, None, Some(&mut alpha), Some(&mut flags)) };
+
+    // Layered windows that use UpdateLayeredWindow instead don't have any attributes to retrieve
+    match res.is_ok() && flags.contains(LWA_ALPHA) {
+        true => alpha,
+        false => 255,
+    }
+}
+
 pub fn has_native_border(hwnd: HWND) -> bool {
     let style = get_window_style(hwnd);
     let ex_style = get_window_ex_style(hwnd);
@@ -256,6 +955,21 @@ pub fn create_border_for_window(tracking_window: HWND, window_rule: WindowRule)
     let _ = thread::spawn(move || {
         let tracking_window = HWND(tracking_window_isize as _);
 
+        // Give transient windows a chance to disappear before we create a border for them. If one
+        // sticks around, it'll get a border like any other window.
+        let suppress_transient_ms = window_rule
+            .suppress_transient_ms
+            .unwrap_or(APP_STATE.config.load().global.suppress_transient_ms);
+        if suppress_transient_ms > 0 && is_transient_host(tracking_window) {
+            debug!("delaying border for transient window {tracking_window:?}");
+            thread::sleep(time::Duration::from_millis(suppress_transient_ms));
+
+            if !is_window_visible(tracking_window) || is_window_cloaked(tracking_window) {
+                debug!("transient window {tracking_window:?} went away; skipping its border");
+                return;
+            }
+        }
+
         // Note: 'key' for the hashmap is the tracking window, 'value' is the border window
         let mut borders_hashmap = APP_STATE.borders.lock().unwrap();
 
@@ -285,8 +999,75 @@ pub fn create_border_for_window(tracking_window: HWND, window_rule: WindowRule)
     });
 }
 
-pub fn get_adjusted_radius(radius: f32, dpi: f32, border_width: i32) -> f32 {
-    radius * dpi / 96.0 + (border_width as f32 / 2.0)
+// Create a new border for the window after the given delay, e.g. once its previous border thread
+// exited because rendering failed
+pub fn recreate_border_after(tracking_window: HWND, delay: time::Duration) {
+    let tracking_window_isize = tracking_window.0 as isize;
+
+    let _ = thread::spawn(move || {
+        thread::sleep(delay);
+
+        let tracking_window = HWND(tracking_window_isize as _);
+        if !unsafe { IsWindow(tracking_window) }.as_bool() {
+            return;
+        }
+
+        debug!("recreating border for {tracking_window:?}");
+        show_border_for_window(tracking_window);
+    });
+}
+
+// Tabbed apps like Edge or File Explorer briefly create tab-host windows (e.g. while dragging a
+// tab), which are owned by the window they came from and share its class. While the owner already
+// has a border, there's no need to flash another one around the transient host.
+fn is_transient_host(hwnd: HWND) -> bool {
+    let Ok(class) = get_window_class(hwnd) else {
+        return false;
+    };
+
+    let mut owner = unsafe { GetWindow(hwnd, GW_OWNER) };
+    while let Ok(owner_hwnd) = owner {
+        if get_border_for_window(owner_hwnd).is_some() {
+            return get_window_class(owner_hwnd).is_ok_and(|owner_class| owner_class == class);
+        }
+
+        owner = unsafe { GetWindow(owner_hwnd, GW_OWNER) };
+    }
+
+    false
+}
+
+pub fn get_adjusted_radius(radius: f32, dpi: f32, stroke_center: f32) -> f32 {
+    (radius * dpi / 96.0 + stroke_center).max(0.0)
+}
+
+// The largest corner radius (in pixels) we look for in a window's region
+const MAX_REGION_RADIUS: i32 = 64;
+
+// Estimate the corner radius (in pixels) of a window that shapes itself with a window region, as
+// some apps do to round their corners on Windows 10. Returns None if the window has no region.
+pub fn get_window_region_radius(hwnd: HWND) -> Option<f32> {
+    unsafe {
+        let hrgn = CreateRectRgn(0, 0, 0, 0);
+
+        let radius = match GetWindowRgn(hwnd, hrgn) {
+            SIMPLEREGION => Some(0.0),
+            COMPLEXREGION => {
+                // Walk diagonally inwards from the top-left corner until we're inside the region.
+                // For a circular corner of radius r, this happens at r * (1 - 1/sqrt(2)).
+                let inset = (0..MAX_REGION_RADIUS)
+                    .find(|&i| PtInRegion(hrgn, i, i).as_bool())
+                    .unwrap_or(MAX_REGION_RADIUS);
+
+                Some(inset as f32 / (1.0 - FRAC_1_SQRT_2))
+            }
+            _ => None,
+        };
+
+        let _ = DeleteObject(hrgn);
+
+        radius
+    }
 }
 
 pub fn get_window_corner_preference(tracking_window: HWND) -> DWM_WINDOW_CORNER_PREFERENCE {
@@ -306,6 +1087,137 @@ pub fn get_window_corner_preference(tracking_window: HWND) -> DWM_WINDOW_CORNER_
     corner_preference
 }
 
+// Set the color of the window's own DWM border (Windows 11 only). Because this is part of the
+// window itself, it also shows up in Alt-Tab and Task View previews.
+pub fn set_window_border_color(hwnd: HWND, color: COLORREF) -> windows::core::Result<()> {
+    unsafe {
+        DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_BORDER_COLOR,
+            ptr::addr_of!(color) as _,
+            size_of::<COLORREF>() as u32,
+        )
+    }
+}
+
+pub fn get_monitor_for_window(hwnd: HWND) -> HMONITOR {
+    unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) }
+}
+
+// Windows 11 hosts shell flyouts like the snap layouts flyout in windows of this class
+const XAML_HOST_CLASS: &str = "XamlExplorerHostIslandWindow";
+
+pub fn is_xaml_host_window(hwnd: HWND) -> bool {
+    get_window_class(hwnd).is_ok_and(|class| class == XAML_HOST_CLASS)
+}
+
+// On Windows 11, hovering over a window's maximize button opens the snap layouts flyout, which
+// briefly takes the foreground. This checks if the cursor is over the window's maximize button.
+pub fn is_hovering_maximize_button(hwnd: HWND) -> bool {
+    let mut cursor_pos = POINT::default();
+    if unsafe { GetCursorPos(&mut cursor_pos) }.is_err() {
+        return false;
+    }
+
+    // WM_NCHITTEST takes screen coordinates packed into the LPARAM
+    let lparam =
+        LPARAM((((cursor_pos.y as u16 as u32) << 16) | cursor_pos.x as u16 as u32) as isize);
+    let mut hit_test = 0usize;
+
+    let res = unsafe {
+        SendMessageTimeoutW(
+            hwnd,
+            WM_NCHITTEST,
+            WPARAM(0),
+            lparam,
+            SMTO_ABORTIFHUNG,
+            50,
+            Some(&mut hit_test),
+        )
+    };
+
+    res.0 != 0 && hit_test as u32 == HTMAXBUTTON
+}
+
+// Check the "Transparency effects" toggle in Windows' personalization settings. It's enabled by
+// default, so we assume it's enabled if the value is missing.
+pub fn is_transparency_enabled() -> bool {
+    let mut value = 1u32;
+    let mut size = size_of::<u32>() as u32;
+
+    let res = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("EnableTransparency"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(ptr::addr_of_mut!(value) as _),
+            Some(&mut size),
+        )
+    };
+
+    res != ERROR_SUCCESS || value != 0
+}
+
+// Check whether apps use the light theme, per the "Choose your default app mode" setting in Windows'
+// personalization settings. Light is the default, so we assume it if the value is missing.
+pub fn is_light_theme() -> bool {
+    let mut value = 1u32;
+    let mut size = size_of::<u32>() as u32;
+
+    let res = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(ptr::addr_of_mut!(value) as _),
+            Some(&mut size),
+        )
+    };
+
+    res != ERROR_SUCCESS || value != 0
+}
+
+// WM_SETTINGCHANGE's lparam names the setting that changed, which is "ImmersiveColorSet" when the
+// light/dark app mode (or the accent color) changes
+pub fn is_theme_change(lparam: LPARAM) -> bool {
+    let setting = PCWSTR(lparam.0 as _);
+    !setting.is_null() && unsafe { setting.to_string() }.is_ok_and(|s| s == "ImmersiveColorSet")
+}
+
+// Get the colorization intensity from 0.0 to 1.0, i.e. how strongly DWM blends the accent color
+// into title bars and window frames. Returns None if it isn't set.
+pub fn get_colorization_intensity() -> Option<f32> {
+    let mut value = 0u32;
+    let mut size = size_of::<u32>() as u32;
+
+    let res = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\DWM"),
+            w!("ColorizationColorBalance"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(ptr::addr_of_mut!(value) as _),
+            Some(&mut size),
+        )
+    };
+
+    (res == ERROR_SUCCESS).then(|| value.min(100) as f32 / 100.0)
+}
+
+pub fn is_remote_session() -> bool {
+    unsafe { GetSystemMetrics(SM_REMOTESESSION) != 0 }
+}
+
+pub fn is_composition_enabled() -> bool {
+    // DWM composition can't be disabled on Windows 8 and up, so assume it's enabled on errors
+    unsafe { DwmIsCompositionEnabled() }.map_or(true, |enabled| enabled.as_bool())
+}
+
 pub fn get_dpi_for_window(hwnd: HWND) -> u32 {
     unsafe { GetDpiForWindow(hwnd) }
 }
@@ -319,9 +1231,14 @@ pub fn destroy_border_for_window(tracking_window: HWND) {
     {
         let border_window = HWND(border_isize as _);
 
-        post_message_w(border_window, WM_NCDESTROY, WPARAM(0), LPARAM(0))
-            .context("destroy_border_for_window")
-            .log_if_err();
+        post_message_w(
+            border_window,
+            AppMessage::Destroy.into(),
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context("destroy_border_for_window")
+        .log_if_err();
     }
 }
 
@@ -339,17 +1256,84 @@ pub fn get_border_for_window(hwnd: HWND) -> Option<HWND> {
     Some(border_window)
 }
 
+// A snapshot of a border's current state for consumers outside of the border threads (e.g.
+// logging and diagnostics)
+#[derive(Debug, Clone)]
+pub struct BorderInfo {
+    pub tracking_window: HWND,
+    pub border_window: HWND,
+    pub is_visible: bool,
+    pub monitor: HMONITOR,
+    pub window_rule_index: Option<usize>,
+}
+
+// Enumerate all current borders without exposing the raw borders hashmap
+pub fn list_borders() -> Vec<BorderInfo> {
+    // Copy the hashmap first so we don't hold its lock while calling into Win32
+    let borders: Vec<(isize, isize)> = APP_STATE
+        .borders
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(&tracking, &border)| (tracking, border))
+        .collect();
+
+    let config = APP_STATE.config.load();
+
+    borders
+        .into_iter()
+        .map(|(tracking_isize, border_isize)| {
+            let tracking_window = HWND(tracking_isize as _);
+            let border_window = HWND(border_isize as _);
+
+            BorderInfo {
+                tracking_window,
+                border_window,
+                is_visible: is_window_visible(border_window),
+                monitor: get_monitor_for_window(tracking_window),
+                window_rule_index: get_window_rule_index(tracking_window, &config),
+            }
+        })
+        .collect()
+}
+
+pub fn post_message_to_all_borders(message: AppMessage) {
+    for value in APP_STATE.borders.lock().unwrap().values() {
+        let border_window = HWND(*value as _);
+        post_message_w(border_window, message.into(), WPARAM(0), LPARAM(0))
+            .context(format!("could not post {message:?} to all borders"))
+            .log_if_err();
+    }
+}
+
 pub fn show_border_for_window(hwnd: HWND) {
     // If the border already exists, simply post a 'SHOW' message to its message queue. Otherwise,
     // create a new border.
     if let Some(border) = get_border_for_window(hwnd) {
-        post_message_w(border, WM_APP_SHOWUNCLOAKED, WPARAM(0), LPARAM(0))
-            .context("show_border_for_window")
-            .log_if_err();
+        post_message_w(
+            border,
+            AppMessage::ShowUncloaked.into(),
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context("show_border_for_window")
+        .log_if_err();
     } else if is_window_top_level(hwnd) && is_window_visible(hwnd) && !is_window_cloaked(hwnd) {
+        if is_window_excluded(hwnd) {
+            debug!("border is excluded for {hwnd:?}");
+            return;
+        }
+
         let window_rule = get_window_rule(hwnd);
 
-        if window_rule.enabled == Some(EnableMode::Bool(false)) {
+        if APP_STATE
+            .window_overrides
+            .lock()
+            .unwrap()
+            .is_border_disabled(hwnd)
+        {
+            info!("border is manually disabled for {hwnd:?}");
+        } else if window_rule.enabled == Some(EnableMode::Bool(false)) {
             info!("border is disabled for {hwnd:?}");
         } else if window_rule.enabled == Some(EnableMode::Bool(true)) || !has_filtered_style(hwnd) {
             create_border_for_window(hwnd, window_rule);
@@ -357,6 +1341,22 @@ pub fn show_border_for_window(hwnd: HWND) {
     }
 }
 
+// Manually enable or disable the border for the given window. This decision is persisted across
+// restarts (see WindowOverrides).
+pub fn toggle_border_for_window(hwnd: HWND) {
+    let toggle_res = APP_STATE
+        .window_overrides
+        .lock()
+        .unwrap()
+        .toggle_border(hwnd);
+
+    match toggle_res {
+        Ok(true) => show_border_for_window(hwnd),
+        Ok(false) => destroy_border_for_window(hwnd),
+        Err(err) => error!("could not toggle border for {hwnd:?}: {err:#}"),
+    }
+}
+
 pub fn hide_border_for_window(hwnd: HWND) {
     let hwnd_isize = hwnd.0 as isize;
 
@@ -366,7 +1366,7 @@ pub fn hide_border_for_window(hwnd: HWND) {
         let hwnd = HWND(hwnd_isize as _);
 
         if let Some(border) = get_border_for_window(hwnd) {
-            post_message_w(border, WM_APP_HIDECLOAKED, WPARAM(0), LPARAM(0))
+            post_message_w(border, AppMessage::HideCloaked.into(), WPARAM(0), LPARAM(0))
                 .context("hide_border_for_window")
                 .log_if_err();
         }
@@ -475,3 +1475,314 @@ pub fn cubic_bezier(control_points: &[f32; 4]) -> Result<impl Fn(f32) -> f32, Be
         de_casteljau(t, p_i.y, p1.y, p2.y, p_f.y)
     })
 }
+
+// Which points of a steps() easing function jump, following the CSS <step-position> keywords
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepPosition {
+    Start,
+    End,
+    // jump-none
+    Neither,
+    Both,
+}
+
+// Generates a CSS-style steps() easing function that jumps between 'count' evenly spaced levels
+pub fn steps(count: u32, position: StepPosition) -> impl Fn(f32) -> f32 {
+    // The number of jumps differs from the number of steps if both or neither ends jump
+    let jumps = match position {
+        StepPosition::Start | StepPosition::End => count,
+        StepPosition::Neither => count.saturating_sub(1).max(1),
+        StepPosition::Both => count + 1,
+    } as f32;
+    let jumps_at_start = matches!(position, StepPosition::Start | StepPosition::Both);
+
+    move |x: f32| {
+        let mut step = (x * count as f32).floor();
+        if jumps_at_start {
+            step += 1.0;
+        }
+
+        (step / jumps).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::border_config::WindowRulePlan;
+    use crate::window_system::mock::{MockWindow, MockWindowSystem};
+
+    fn sample(easing_fn: impl Fn(f32) -> f32) -> Vec<f32> {
+        [0.0, 0.2, 0.5, 0.8, 1.0]
+            .into_iter()
+            .map(easing_fn)
+            .collect()
+    }
+
+    #[test]
+    fn steps_jump_end_holds_until_each_step_ends() {
+        assert_eq!(
+            sample(steps(4, StepPosition::End)),
+            [0.0, 0.0, 0.5, 0.75, 1.0]
+        );
+    }
+
+    #[test]
+    fn steps_jump_start_jumps_immediately() {
+        assert_eq!(
+            sample(steps(4, StepPosition::Start)),
+            [0.25, 0.25, 0.75, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn steps_jump_none_reaches_both_ends() {
+        assert_eq!(
+            sample(steps(3, StepPosition::Neither)),
+            [0.0, 0.0, 0.5, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn steps_jump_both_skips_both_ends() {
+        assert_eq!(
+            sample(steps(3, StepPosition::Both)),
+            [0.25, 0.25, 0.5, 0.75, 1.0]
+        );
+    }
+
+    #[test]
+    fn cubic_bezier_keeps_end_points() {
+        let easing_fn = cubic_bezier(&[0.42, 0.0, 0.58, 1.0]).unwrap();
+        assert_eq!(easing_fn(0.0), 0.0);
+        assert_eq!(easing_fn(1.0), 1.0);
+        assert!((easing_fn(0.5) - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn cubic_bezier_rejects_out_of_range_x() {
+        assert!(cubic_bezier(&[1.5, 0.0, 0.5, 1.0]).is_err());
+    }
+
+    fn rule(kind: MatchKind, name: &str, strategy: MatchStrategy, merge: bool) -> WindowRule {
+        WindowRule {
+            kind: Some(kind),
+            name: Some(name.to_string()),
+            strategy: Some(strategy),
+            merge: Some(merge),
+            ..Default::default()
+        }
+    }
+
+    fn config(window_rules: Vec<WindowRule>) -> Config {
+        Config {
+            window_rule_plan: WindowRulePlan::new(&window_rules),
+            window_rules,
+            ..Default::default()
+        }
+    }
+
+    fn system(window: MockWindow) -> MockWindowSystem {
+        MockWindowSystem::default().with_window(1, window)
+    }
+
+    fn notepad() -> MockWindow {
+        MockWindow {
+            process: Some("Notepad".to_string()),
+            title: Some("notes.txt - Notepad".to_string()),
+            class: Some("Notepad".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rule_matching_stops_at_first_match_unless_merged() {
+        let config = config(vec![
+            rule(MatchKind::Title, ".txt", MatchStrategy::Contains, true),
+            rule(MatchKind::Class, "firefox", MatchStrategy::Equals, false),
+            rule(MatchKind::Process, "notepad", MatchStrategy::Equals, false),
+            rule(MatchKind::Class, "Notepad", MatchStrategy::Equals, false),
+        ]);
+
+        let indices = get_window_rule_indices_with(&system(notepad()), HWND(1 as _), &config);
+        assert_eq!(indices, [0, 2]);
+    }
+
+    #[test]
+    fn rule_matching_only_queries_needed_properties() {
+        let config = config(vec![rule(
+            MatchKind::Class,
+            "^note",
+            MatchStrategy::Regex,
+            false,
+        )]);
+        let system = system(notepad());
+
+        // Regex matching is case-sensitive, unlike the other strategies
+        assert!(get_window_rule_indices_with(&system, HWND(1 as _), &config).is_empty());
+        assert_eq!(*system.queries.borrow(), [(1, "class")]);
+    }
+
+    #[test]
+    fn rule_matching_uses_process_lookup_for_exact_process_rules() {
+        let config = config(vec![
+            rule(MatchKind::Process, "explorer", MatchStrategy::Equals, false),
+            rule(MatchKind::Process, "NOTEPAD", MatchStrategy::Equals, false),
+        ]);
+        assert!(config.window_rule_plan.process_lookup.is_some());
+
+        let system = system(notepad());
+        let indices = get_window_rule_indices_with(&system, HWND(1 as _), &config);
+        assert_eq!(indices, [1]);
+        assert_eq!(*system.queries.borrow(), [(1, "process")]);
+    }
+
+    #[test]
+    fn rule_matching_treats_inaccessible_properties_as_empty() {
+        let config = config(vec![
+            rule(
+                MatchKind::Process,
+                "notepad",
+                MatchStrategy::Contains,
+                false,
+            ),
+            rule(MatchKind::Title, "", MatchStrategy::Equals, false),
+        ]);
+
+        // The process of an elevated window can't be queried, so only the title rule can match
+        let window = MockWindow {
+            process: None,
+            ..notepad()
+        };
+        let indices = get_window_rule_indices_with(&system(window), HWND(1 as _), &config);
+        assert!(indices.is_empty());
+
+        let window = MockWindow {
+            process: None,
+            title: None,
+            ..notepad()
+        };
+        let indices = get_window_rule_indices_with(&system(window), HWND(1 as _), &config);
+        assert_eq!(indices, [1]);
+    }
+
+    #[test]
+    fn rule_matching_can_tell_apart_windows_of_the_same_executable() {
+        let config = config(vec![
+            rule(
+                MatchKind::CommandLine,
+                r"--app=\S*discord",
+                MatchStrategy::Regex,
+                false,
+            ),
+            rule(
+                MatchKind::Path,
+                r"C:\Program Files\Electron\electron.exe",
+                MatchStrategy::Equals,
+                false,
+            ),
+        ]);
+        let electron = |command_line: &str| MockWindow {
+            process: Some("electron".to_string()),
+            path: Some(r"C:\Program Files\Electron\Electron.exe".to_string()),
+            command_line: Some(command_line.to_string()),
+            ..Default::default()
+        };
+
+        let discord = system(electron(r#""electron.exe" --app=C:\apps\discord"#));
+        let indices = get_window_rule_indices_with(&discord, HWND(1 as _), &config);
+        assert_eq!(indices, [0]);
+        assert_eq!(
+            *discord.queries.borrow(),
+            [(1, "path"), (1, "command line")]
+        );
+
+        // Paths are matched case-insensitively, like process names
+        let slack = system(electron(r#""electron.exe" --app=C:\apps\slack"#));
+        let indices = get_window_rule_indices_with(&slack, HWND(1 as _), &config);
+        assert_eq!(indices, [1]);
+    }
+
+    #[test]
+    fn rule_matching_handles_unknown_windows() {
+        let config = config(vec![rule(
+            MatchKind::Title,
+            "notes",
+            MatchStrategy::Contains,
+            false,
+        )]);
+
+        let indices = get_window_rule_indices_with(&system(notepad()), HWND(2 as _), &config);
+        assert!(indices.is_empty());
+    }
+
+    fn rect(left: f32, top: f32, right: f32, bottom: f32) -> D2D_RECT_F {
+        D2D_RECT_F {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    // Check that both edges of the stroke around each snapped coordinate land on whole pixels, and
+    // that snapping moved each coordinate by at most half a pixel
+    fn assert_snapped(original: &D2D_RECT_F, stroke_width: i32) {
+        let snapped = snap_stroke_rect(original, stroke_width);
+        let half_width = stroke_width as f32 / 2.0;
+
+        let coords = [
+            (original.left, snapped.left),
+            (original.top, snapped.top),
+            (original.right, snapped.right),
+            (original.bottom, snapped.bottom),
+        ];
+        for (original_coord, snapped_coord) in coords {
+            for edge in [snapped_coord - half_width, snapped_coord + half_width] {
+                assert_eq!(edge, edge.round(), "{original:?} snapped to {snapped:?}");
+            }
+            assert!((snapped_coord - original_coord).abs() <= 0.5);
+        }
+    }
+
+    #[test]
+    fn odd_stroke_widths_snap_to_half_pixels() {
+        let snapped = snap_stroke_rect(&rect(0.5, 0.5, 99.5, 49.5), 1);
+        assert_eq!(snapped, rect(0.5, 0.5, 99.5, 49.5));
+
+        let snapped = snap_stroke_rect(&rect(1.0, 1.0, 100.0, 50.0), 3);
+        assert_eq!(snapped, rect(1.5, 1.5, 100.5, 50.5));
+    }
+
+    #[test]
+    fn even_stroke_widths_snap_to_whole_pixels() {
+        let snapped = snap_stroke_rect(&rect(1.0, 1.0, 99.0, 49.0), 2);
+        assert_eq!(snapped, rect(1.0, 1.0, 99.0, 49.0));
+
+        let snapped = snap_stroke_rect(&rect(2.5, 2.4, 97.6, 47.5), 4);
+        assert_eq!(snapped, rect(3.0, 2.0, 98.0, 48.0));
+    }
+
+    #[test]
+    fn fractional_rects_snap_to_pixel_grid() {
+        for stroke_width in 1..=6 {
+            assert_snapped(&rect(0.3, 1.7, 120.25, 80.75), stroke_width);
+            assert_snapped(&rect(-3.4, -0.6, 12.9, 7.1), stroke_width);
+        }
+    }
+
+    #[test]
+    fn zero_size_rects_stay_zero_size() {
+        for stroke_width in [1, 2, 5] {
+            let snapped = snap_stroke_rect(&rect(10.2, 20.7, 10.2, 20.7), stroke_width);
+            assert_eq!(snapped.left, snapped.right);
+            assert_eq!(snapped.top, snapped.bottom);
+            assert_snapped(&rect(10.2, 20.7, 10.2, 20.7), stroke_width);
+        }
+
+        assert_eq!(
+            snap_stroke_rect(&rect(0.0, 0.0, 0.0, 0.0), 2),
+            rect(0.0, 0.0, 0.0, 0.0)
+        );
+    }
+}
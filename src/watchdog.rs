@@ -0,0 +1,88 @@
+use anyhow::Context;
+use std::collections::HashSet;
+use std::thread;
+use std::time;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{ShowWindowAsync, SW_HIDE};
+
+use crate::utils::{post_message_w, show_border_for_window, AppMessage, LogIfErr};
+use crate::APP_STATE;
+
+// How often we ask each border thread for a heartbeat
+const HEARTBEAT_INTERVAL: time::Duration = time::Duration::from_secs(5);
+// If a border hasn't responded to a heartbeat in this long, we consider its thread stuck
+const STUCK_THRESHOLD: time::Duration = time::Duration::from_secs(15);
+
+// Periodically check that every border thread is still processing messages. Border threads record
+// a timestamp whenever they handle AppMessage::Heartbeat, so a stale timestamp means the thread is
+// likely deadlocked or stuck in a long-running call.
+pub fn start_watchdog() {
+    let _ = thread::spawn(|| {
+        // Border windows we've already reported, so we only log each stuck border once
+        let mut reported: HashSet<isize> = HashSet::new();
+
+        loop {
+            thread::sleep(HEARTBEAT_INTERVAL);
+
+            let borders = APP_STATE.borders.lock().unwrap().clone();
+            let now = time::Instant::now();
+
+            let mut stuck_borders = Vec::new();
+            {
+                let mut heartbeats = APP_STATE.heartbeats.lock().unwrap();
+
+                // Forget about borders that no longer exist
+                heartbeats.retain(|border_window, _| borders.values().any(|v| v == border_window));
+                reported.retain(|border_window| borders.values().any(|v| v == border_window));
+
+                for (&tracking_window, &border_window) in borders.iter() {
+                    // Newly created borders get a grace period starting from when we first see them
+                    let last_heartbeat = *heartbeats.entry(border_window).or_insert(now);
+
+                    if now.duration_since(last_heartbeat) >= STUCK_THRESHOLD {
+                        stuck_borders.push((tracking_window, border_window));
+                    }
+                }
+            }
+
+            for &(tracking_window, border_window) in stuck_borders.iter() {
+                if reported.insert(border_window) {
+                    error!(
+                        "border thread for {:?} has not responded in over {}s; it may be stuck",
+                        HWND(tracking_window as _),
+                        STUCK_THRESHOLD.as_secs()
+                    );
+
+                    if APP_STATE.config.load().recreate_stuck_borders {
+                        recreate_stuck_border(tracking_window, border_window);
+                    }
+                }
+            }
+
+            for border_window in borders.values() {
+                post_message_w(
+                    HWND(*border_window as _),
+                    AppMessage::Heartbeat.into(),
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("could not post heartbeat to border")
+                .log_if_err();
+            }
+        }
+    });
+}
+
+fn recreate_stuck_border(tracking_window: isize, border_window: isize) {
+    info!(
+        "recreating stuck border for {:?}",
+        HWND(tracking_window as _)
+    );
+
+    // The stuck thread can't process any messages, so we can't destroy its window. Instead, we
+    // hide it without waiting on the thread and forget about it.
+    APP_STATE.borders.lock().unwrap().remove(&tracking_window);
+    let _ = unsafe { ShowWindowAsync(HWND(border_window as _), SW_HIDE) };
+
+    show_border_for_window(HWND(tracking_window as _));
+}
@@ -1,14 +1,30 @@
 use crate::animations::{self, AnimType, AnimVec, Animations};
-use crate::border_config::WindowRule;
+use crate::border_config::{
+    BorderPlacement, EnableMode, MinWindowSize, RadiusConfig, WidthScale, WindowRule,
+};
+use crate::color_math::ColorSpace;
+use crate::color_profile::ColorTransform;
 use crate::colors::Color;
+use crate::conflicts::{
+    find_conflicting_module, get_always_on_top_frame_width, AlwaysOnTopFrame, ConflictMitigation,
+};
+use crate::debug_overlay::{DebugOverlay, RenderStats};
+use crate::etw::{write_event, TraceEvent};
+use crate::night_light::{apply_tint, get_color_temperature_tint};
 use crate::utils::{
-    are_rects_same_size, get_dpi_for_window, get_window_rule, get_window_title, has_native_border,
-    is_rect_visible, is_window_minimized, is_window_visible, post_message_w, LogIfErr,
-    WM_APP_ANIMATE, WM_APP_FOREGROUND, WM_APP_HIDECLOAKED, WM_APP_LOCATIONCHANGE,
-    WM_APP_MINIMIZEEND, WM_APP_MINIMIZESTART, WM_APP_REORDER, WM_APP_SHOWUNCLOAKED,
+    are_rects_same_size, get_dpi_for_window, get_monitor_for_window, get_monitor_rect,
+    get_monitor_work_area, get_window_alpha, get_window_class, get_window_process_id,
+    get_window_rule, get_window_title, has_native_border, is_drag_full_windows_enabled,
+    is_rect_occluded, is_rect_visible, is_theme_change, is_window_cloaked, is_window_in_move_size,
+    is_window_minimized, is_window_visible, post_message_to_all_borders, post_message_w,
+    predict_next_rect, record_closed_window, recreate_border_after, set_window_border_color,
+    snap_stroke_rect, take_replaced_window, AppMessage, ClosedWindow, LogIfErr,
 };
+use crate::window_system::{Win32WindowSystem, WindowSystem};
 use crate::APP_STATE;
 use anyhow::{anyhow, Context};
+use serde::Deserialize;
+use std::mem;
 use std::ptr;
 use std::thread;
 use std::time;
@@ -18,51 +34,155 @@ use windows::Win32::Foundation::{
     COLORREF, D2DERR_RECREATE_TARGET, FALSE, HWND, LPARAM, LRESULT, RECT, TRUE, WPARAM,
 };
 use windows::Win32::Graphics::Direct2D::Common::{
-    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_PIXEL_FORMAT, D2D_RECT_F, D2D_SIZE_U,
+    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_COLOR_F, D2D1_PIXEL_FORMAT, D2D_RECT_F, D2D_SIZE_U,
 };
 use windows::Win32::Graphics::Direct2D::{
     ID2D1Brush, ID2D1HwndRenderTarget, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE, D2D1_BRUSH_PROPERTIES,
     D2D1_HWND_RENDER_TARGET_PROPERTIES, D2D1_PRESENT_OPTIONS_IMMEDIATELY,
-    D2D1_PRESENT_OPTIONS_RETAIN_CONTENTS, D2D1_RENDER_TARGET_PROPERTIES,
-    D2D1_RENDER_TARGET_TYPE_DEFAULT, D2D1_ROUNDED_RECT,
+    D2D1_PRESENT_OPTIONS_RETAIN_CONTENTS, D2D1_RENDER_TARGET_PROPERTIES, D2D1_ROUNDED_RECT,
 };
 use windows::Win32::Graphics::Dwm::{
-    DwmEnableBlurBehindWindow, DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS,
-    DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND,
+    DwmEnableBlurBehindWindow, DwmGetWindowAttribute, DWMWA_COLOR_DEFAULT,
+    DWMWA_EXTENDED_FRAME_BOUNDS, DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND,
 };
 use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_UNKNOWN;
-use windows::Win32::Graphics::Gdi::{CreateRectRgn, ValidateRect};
+use windows::Win32::Graphics::Gdi::{CreateRectRgn, ValidateRect, HMONITOR};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::WindowsAndMessaging::{
     CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, GetSystemMetrics, GetWindow,
-    GetWindowLongPtrW, PostQuitMessage, SetLayeredWindowAttributes, SetWindowLongPtrW,
-    SetWindowPos, TranslateMessage, CREATESTRUCTW, CW_USEDEFAULT, GWLP_USERDATA, GW_HWNDPREV,
-    HWND_TOP, LWA_ALPHA, MSG, SET_WINDOW_POS_FLAGS, SM_CXVIRTUALSCREEN, SWP_HIDEWINDOW,
-    SWP_NOACTIVATE, SWP_NOREDRAW, SWP_NOSENDCHANGING, SWP_NOZORDER, SWP_SHOWWINDOW, WM_CREATE,
-    WM_NCDESTROY, WM_PAINT, WM_WINDOWPOSCHANGED, WM_WINDOWPOSCHANGING, WS_DISABLED, WS_EX_LAYERED,
-    WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_POPUP,
+    GetWindowLongPtrW, IsWindow, KillTimer, PeekMessageW, PostQuitMessage,
+    SetLayeredWindowAttributes, SetTimer, SetWindowLongPtrW, SetWindowPos, TranslateMessage,
+    CREATESTRUCTW, CW_USEDEFAULT, GWLP_USERDATA, GW_HWNDNEXT, GW_HWNDPREV, HWND_TOP, HWND_TOPMOST,
+    LWA_ALPHA, MSG, PBT_APMRESUMEAUTOMATIC, PBT_APMRESUMESUSPEND, PM_REMOVE, SET_WINDOW_POS_FLAGS,
+    SM_CXVIRTUALSCREEN, SWP_HIDEWINDOW, SWP_NOACTIVATE, SWP_NOREDRAW, SWP_NOSENDCHANGING,
+    SWP_NOZORDER, SWP_SHOWWINDOW, WINDOW_EX_STYLE, WM_CREATE, WM_DISPLAYCHANGE,
+    WM_DWMCOLORIZATIONCOLORCHANGED, WM_NCDESTROY, WM_PAINT, WM_POWERBROADCAST, WM_SETTINGCHANGE,
+    WM_TIMER, WM_WINDOWPOSCHANGED, WM_WINDOWPOSCHANGING, WS_DISABLED, WS_EX_COMPOSITED,
+    WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_POPUP,
 };
 
+// If LOCATIONCHANGE events arrive faster than this, we assume the tracking window is animating
+const RECT_PREDICTION_THRESHOLD_MS: u128 = 32;
+// Timer used to correct the border's position after the last predicted rect
+const RECT_CORRECTION_TIMER_ID: usize = 1;
+const RECT_CORRECTION_DELAY_MS: u32 = 50;
+// Timer used to retry resizing the render target, which can fail while the display configuration
+// is changing (e.g. a monitor being plugged in). Each retry waits twice as long as the last.
+const RESIZE_RETRY_TIMER_ID: usize = 2;
+const RESIZE_RETRY_BASE_DELAY_MS: u32 = 50;
+const MAX_RESIZE_RETRIES: u32 = 4;
+// Timer used to check whether an occluded border has been revealed (see pause_when_occluded). The
+// window covering it can move away without any event being sent to us, so we have to poll.
+const OCCLUSION_TIMER_ID: usize = 3;
+const OCCLUSION_CHECK_MS: u32 = 250;
+// Delay before recreating a border whose rendering failed. It doubles with each failure in a row,
+// and we give up once there have been too many.
+const RENDER_RECOVERY_BASE_DELAY_MS: u64 = 500;
+const MAX_RENDER_RECOVERY_FAILURES: u32 = 8;
+
+// Extended window styles the border window can be created with (see border_window_ex_styles)
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum BorderExStyle {
+    Layered,
+    Topmost,
+    ToolWindow,
+    Transparent,
+    NoActivate,
+    Composited,
+}
+
+impl BorderExStyle {
+    pub const DEFAULT: [BorderExStyle; 4] = [
+        BorderExStyle::Layered,
+        BorderExStyle::Topmost,
+        BorderExStyle::ToolWindow,
+        BorderExStyle::Transparent,
+    ];
+
+    fn to_ex_style(self) -> WINDOW_EX_STYLE {
+        match self {
+            BorderExStyle::Layered => WS_EX_LAYERED,
+            BorderExStyle::Topmost => WS_EX_TOPMOST,
+            BorderExStyle::ToolWindow => WS_EX_TOOLWINDOW,
+            BorderExStyle::Transparent => WS_EX_TRANSPARENT,
+            BorderExStyle::NoActivate => WS_EX_NOACTIVATE,
+            BorderExStyle::Composited => WS_EX_COMPOSITED,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct WindowBorder {
     pub border_window: HWND,
     pub tracking_window: HWND,
     pub is_active_window: bool,
     pub window_rect: RECT,
+    pub last_real_window_rect: RECT,
+    pub last_location_time: Option<time::Instant>,
     pub border_width: i32,
     pub border_offset: i32,
     pub border_radius: f32,
+    // The configured width and radius, before adjusting for dpi and width_scale
+    pub width_config: f32,
+    pub radius_config: RadiusConfig,
+    pub width_scale: Option<WidthScale>,
+    // The larger dimension of the tracking window, used for width_scale
+    pub tracking_window_size: i32,
+    pub border_placement: BorderPlacement,
+    pub pixel_snap: bool,
+    pub min_window_size: MinWindowSize,
+    pub only_on_focused_monitor: bool,
+    pub pause_when_occluded: bool,
+    // Set while the border is completely covered by another window (see update_occlusion).
+    // Rendering is skipped until it has been revealed again.
+    pub is_occluded: bool,
+    pub current_monitor: HMONITOR,
+    pub color_management: bool,
+    // The monitor whose color profile the colors were last converted for (see color_management)
+    pub color_profile_monitor: HMONITOR,
     pub current_dpi: f32,
     pub render_target: Option<ID2D1HwndRenderTarget>,
     pub rounded_rect: D2D1_ROUNDED_RECT,
     pub active_color: Color,
     pub inactive_color: Color,
+    // Drawn in place of inactive_color while this is the last active window (see
+    // get_inactive_color)
+    pub last_active_color: Option<Color>,
+    pub is_last_active_window: bool,
+    pub color_space: ColorSpace,
     pub animations: Animations,
+    pub tint_native_border: bool,
+    pub audio_indicator: bool,
+    pub is_playing_audio: bool,
+    // If true, the border frames the tracking window's monitor work area (see ambient_frame)
+    pub is_ambient_frame: bool,
+    // The tracking window's own alpha, which the border window uses too (see get_window_alpha)
+    pub window_alpha: u8,
+    // The rule this border was loaded with, so we can tell when a title change matches another
+    pub window_rule: WindowRule,
+    // Used to recognize the tracking window's replacement if it recreates itself (see ClosedWindow)
+    pub process_id: u32,
+    pub window_class: String,
+    pub conflict_mitigation: ConflictMitigation,
+    pub always_on_top_frame: AlwaysOnTopFrame,
+    // Width of the PowerToys Always On Top frame around the tracking window, if it's pinned
+    pub always_on_top_frame_width: Option<i32>,
     pub last_render_time: Option<time::Instant>,
+    // Time between the last two renders and how long the last render took, for the debug overlay
+    pub last_render_interval: Option<time::Duration>,
+    pub last_frame_duration: Option<time::Duration>,
+    pub debug_overlay: Option<DebugOverlay>,
     pub last_anim_time: Option<time::Instant>,
+    pub resize_retries: u32,
+    pub is_resize_retry_pending: bool,
     pub initialize_delay: u64,
     pub unminimize_delay: u64,
     pub is_paused: bool,
+    // Set while the tracking window is being moved or resized by the user (including Aero Shake)
+    pub is_in_move_size: bool,
+    // Set during a move/resize if "Show window contents while dragging" is off, in which case we
+    // leave the border where it is until the drag ends
+    pub is_move_size_deferred: bool,
 }
 
 impl WindowBorder {
@@ -82,9 +202,18 @@ impl WindowBorder {
         .encode_utf16()
         .collect();
 
+        let ex_style = match APP_STATE.config.load().border_window_ex_styles {
+            Some(ref ex_styles) => ex_styles.as_slice(),
+            None => BorderExStyle::DEFAULT.as_slice(),
+        }
+        .iter()
+        .fold(WINDOW_EX_STYLE(0), |ex_style, style| {
+            ex_style | style.to_ex_style()
+        });
+
         unsafe {
             self.border_window = CreateWindowExW(
-                WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_TRANSPARENT,
+                ex_style,
                 w!("border"),
                 PCWSTR(title.as_ptr()),
                 WS_POPUP | WS_DISABLED,
@@ -103,12 +232,35 @@ impl WindowBorder {
     }
 
     pub fn init(&mut self, window_rule: WindowRule) -> anyhow::Result<()> {
+        // These have to be retrieved now, since the tracking window is already gone by the time we
+        // need them in exit_border_thread()
+        self.process_id = get_window_process_id(self.tracking_window);
+        self.window_class = get_window_class(self.tracking_window).unwrap_or_default();
+
         self.load_from_config(window_rule)?;
 
         // Delay the border while the tracking window is in its creation animation
         thread::sleep(time::Duration::from_millis(self.initialize_delay));
 
         unsafe {
+            // If the tracking window was closed during the initialize delay, don't bother showing
+            // the border at all
+            let mut message = MSG::default();
+            let destroy = AppMessage::Destroy.into();
+            if PeekMessageW(
+                &mut message,
+                self.border_window,
+                destroy,
+                destroy,
+                PM_REMOVE,
+            )
+            .as_bool()
+            {
+                debug!("aborting init for {:?}", self.tracking_window);
+                self.exit_border_thread();
+                return Ok(());
+            }
+
             // Make the window transparent (stole the code from PowerToys; dunno how it works).
             let pos: i32 = -GetSystemMetrics(SM_CXVIRTUALSCREEN) - 8;
             let hrgn = CreateRectRgn(pos, 0, pos + 1, 1);
@@ -125,16 +277,24 @@ impl WindowBorder {
             DwmEnableBlurBehindWindow(self.border_window, &bh)
                 .context("could not make window transparent")?;
 
-            SetLayeredWindowAttributes(self.border_window, COLORREF(0x00000000), 255, LWA_ALPHA)
-                .context("could not set LWA_ALPHA")?;
+            self.window_alpha = get_window_alpha(self.tracking_window);
+            SetLayeredWindowAttributes(
+                self.border_window,
+                COLORREF(0x00000000),
+                self.window_alpha,
+                LWA_ALPHA,
+            )
+            .context("could not set LWA_ALPHA")?;
 
-            self.create_render_resources()
-                .context("could not create render resources in init()")?;
+            if let Err(err) = self.create_render_resources() {
+                self.exit_after_render_failure();
+                return Err(err).context("could not create render resources in init()");
+            }
 
             self.update_color(Some(self.initialize_delay)).log_if_err();
             self.update_window_rect().log_if_err();
 
-            if has_native_border(self.tracking_window) {
+            if self.should_show_border() {
                 self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
                 self.render().log_if_err();
 
@@ -148,20 +308,33 @@ impl WindowBorder {
 
             animations::set_timer_if_anims_enabled(self);
 
+            write_event(TraceEvent::BorderCreated(self.tracking_window));
+
             // Handle the case where the tracking window is already minimized
             // TODO: maybe put this in a better spot but idk where
             if is_window_minimized(self.tracking_window) {
                 post_message_w(
                     self.border_window,
-                    WM_APP_MINIMIZESTART,
+                    AppMessage::MinimizeStart.into(),
                     WPARAM(0),
                     LPARAM(0),
                 )
-                .context("could not post WM_APP_MINIMIZESTART message in init()")
+                .context("could not post AppMessage::MinimizeStart in init()")
+                .log_if_err();
+            }
+
+            // Also handle the case where borders are currently suspended (e.g. for game mode)
+            if APP_STATE.is_suspended() {
+                post_message_w(
+                    self.border_window,
+                    AppMessage::Suspend.into(),
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("could not post AppMessage::Suspend in init()")
                 .log_if_err();
             }
 
-            let mut message = MSG::default();
             while GetMessageW(&mut message, HWND::default(), 0, 0).into() {
                 let _ = TranslateMessage(&message);
                 DispatchMessageW(&message);
@@ -173,30 +346,73 @@ impl WindowBorder {
     }
 
     pub fn load_from_config(&mut self, window_rule: WindowRule) -> anyhow::Result<()> {
-        let config = APP_STATE.config.read().unwrap();
+        let config = APP_STATE.config.load();
         let global = &config.global;
 
         let width_config = window_rule.border_width.unwrap_or(global.border_width);
         let offset_config = window_rule.border_offset.unwrap_or(global.border_offset);
+        let width_scale_config = window_rule.width_scale.or(global.width_scale);
         let radius_config = window_rule
             .border_radius
             .as_ref()
             .unwrap_or(&global.border_radius);
-        let active_color_config = window_rule
-            .active_color
-            .as_ref()
+        let placement_config = window_rule
+            .border_placement
+            .unwrap_or(global.border_placement);
+        let min_window_size_config = window_rule
+            .min_window_size
+            .unwrap_or(global.min_window_size);
+        // While on air (see on_air.rs), its colors take precedence over everything else
+        let on_air_config = APP_STATE.is_on_air().then_some(&config.on_air);
+        let active_color_config = on_air_config
+            .and_then(|on_air| on_air.active_color.as_ref())
+            .or(window_rule.active_color.as_ref())
             .unwrap_or(&global.active_color);
-        let inactive_color_config = window_rule
-            .inactive_color
-            .as_ref()
+        let inactive_color_config = on_air_config
+            .and_then(|on_air| on_air.inactive_color.as_ref())
+            .or(window_rule.inactive_color.as_ref())
             .unwrap_or(&global.inactive_color);
+        // The on-air inactive color also replaces last_active_color, like it does for rules
+        let last_active_color_config =
+            match on_air_config.is_some_and(|on_air| on_air.inactive_color.is_some()) {
+                true => None,
+                false => window_rule
+                    .last_active_color
+                    .as_ref()
+                    .or(global.last_active_color.as_ref()),
+            };
         let animations_config = window_rule
             .animations
             .as_ref()
             .unwrap_or(&global.animations);
 
-        self.active_color = active_color_config.to_color(true);
-        self.inactive_color = inactive_color_config.to_color(false);
+        self.color_space = global.color_space;
+        self.active_color = active_color_config.to_color(true, self.color_space);
+        self.inactive_color = inactive_color_config.to_color(false, self.color_space);
+        self.last_active_color = last_active_color_config
+            .map(|color_config| color_config.to_color(false, self.color_space));
+        self.is_last_active_window = false;
+
+        // This comes before color management, which converts the final colors for the monitor
+        let color_blind_mode = APP_STATE.get_color_blind_mode();
+        self.active_color.apply_color_blind_mode(color_blind_mode);
+        self.inactive_color.apply_color_blind_mode(color_blind_mode);
+        if let Some(ref mut last_active_color) = self.last_active_color {
+            last_active_color.apply_color_blind_mode(color_blind_mode);
+        }
+
+        if config.night_light.enabled && APP_STATE.is_night_light_active() {
+            let tint = get_color_temperature_tint(config.night_light.temperature);
+            self.map_colors(|color| apply_tint(color, tint));
+        }
+
+        self.color_management = window_rule
+            .color_management
+            .unwrap_or(global.color_management);
+        self.color_profile_monitor = get_monitor_for_window(self.tracking_window);
+        if self.color_management {
+            self.apply_color_profile();
+        }
 
         self.current_dpi = match get_dpi_for_window(self.tracking_window) as f32 {
             0.0 => {
@@ -206,37 +422,131 @@ impl WindowBorder {
             valid_dpi => valid_dpi,
         };
 
-        // Adjust the border width and radius based on the window/monitor dpi
-        self.border_width = (width_config * self.current_dpi / 96.0).round() as i32;
+        self.width_config = width_config;
+        self.radius_config = radius_config.clone();
+        self.width_scale = width_scale_config;
         self.border_offset = offset_config;
-        self.border_radius =
-            radius_config.to_radius(self.border_width, self.current_dpi, self.tracking_window);
+        self.is_ambient_frame = window_rule.ambient_frame.unwrap_or(false);
+        // Ambient frames always draw inside the work area so that they stay on screen
+        self.border_placement = match self.is_ambient_frame {
+            true => BorderPlacement::Inside,
+            false => placement_config,
+        };
+        self.pixel_snap = window_rule.pixel_snap.unwrap_or(global.pixel_snap);
+        self.min_window_size = min_window_size_config;
+        self.only_on_focused_monitor = global.only_on_focused_monitor;
+        self.pause_when_occluded = !self.is_ambient_frame && global.pause_when_occluded;
 
-        self.animations = animations_config.to_animations();
+        // Adjust the border width and radius based on the window/monitor dpi
+        self.update_width_radius();
+
+        self.animations = match APP_STATE.are_animations_disabled() {
+            true => Animations::default(),
+            false => animations_config.to_animations(),
+        };
+        self.tint_native_border = !self.is_ambient_frame
+            && window_rule
+                .tint_native_border
+                .unwrap_or(global.tint_native_border);
+        self.audio_indicator = window_rule
+            .audio_indicator
+            .unwrap_or(global.audio_indicator);
+
+        self.always_on_top_frame = match self.is_ambient_frame {
+            true => AlwaysOnTopFrame::Ignore,
+            false => window_rule
+                .always_on_top_frame
+                .unwrap_or(global.always_on_top_frame),
+        };
+
+        // Only apply the mitigation if we actually detect a conflicting module
+        let mitigation_config = window_rule
+            .conflict_mitigation
+            .unwrap_or(global.conflict_mitigation);
+        self.conflict_mitigation = match find_conflicting_module(self.tracking_window) {
+            Some(module) => {
+                warn!(
+                    "detected potentially conflicting module {module} in {:?}; using {mitigation_config:?} mitigation",
+                    self.tracking_window
+                );
+                mitigation_config
+            }
+            None => ConflictMitigation::Ignore,
+        };
 
         // If the tracking window is part of the initial windows list (meaning it was already open when
-        // tacky-borders was launched), then there should be no initialize delay.
-        self.initialize_delay = match APP_STATE
+        // tacky-borders was launched), then there should be no initialize delay. The same goes for
+        // windows that just replaced themselves, since their border was already showing.
+        let is_initial_window = APP_STATE
             .initial_windows
             .lock()
             .unwrap()
-            .contains(&(self.tracking_window.0 as isize))
-        {
-            true => 0,
-            false => window_rule
-                .initialize_delay
-                .unwrap_or(global.initialize_delay),
-        };
+            .contains(&(self.tracking_window.0 as isize));
+        self.initialize_delay =
+            match is_initial_window || take_replaced_window(self.tracking_window) {
+                true => 0,
+                false => window_rule
+                    .initialize_delay
+                    .unwrap_or(global.initialize_delay),
+            };
         self.unminimize_delay = window_rule
             .unminimize_delay
             .unwrap_or(global.unminimize_delay);
 
+        self.window_rule = window_rule;
+
+        Ok(())
+    }
+
+    // Convert the configured sRGB colors into the color space of the tracking window's monitor
+    fn apply_color_profile(&mut self) {
+        let color_transform = match ColorTransform::for_monitor(self.color_profile_monitor) {
+            Ok(color_transform) => color_transform,
+            Err(err) => {
+                warn!(
+                    "could not apply monitor color profile for {:?}: {err:#}",
+                    self.tracking_window
+                );
+                return;
+            }
+        };
+
+        self.map_colors(|color| color_transform.apply(color));
+    }
+
+    // Apply map_fn to every color of the active, inactive, and last active colors
+    fn map_colors(&mut self, map_fn: impl Fn(D2D1_COLOR_F) -> D2D1_COLOR_F) {
+        self.active_color.map_colors(&map_fn);
+        self.inactive_color.map_colors(&map_fn);
+        if let Some(ref mut last_active_color) = self.last_active_color {
+            last_active_color.map_colors(&map_fn);
+        }
+    }
+
+    fn reload_appearance(&mut self, window_rule: WindowRule) -> anyhow::Result<()> {
+        self.load_from_config(window_rule)?;
+
+        // Brushes are tied to the render target, so we recreate both with the new colors
+        animations::destroy_timer(self);
+        self.update_window_rect().log_if_err();
+        self.create_render_resources()
+            .context("could not recreate render resources while reloading appearance")
+            .log_if_err();
+        self.update_color(Some(0)).log_if_err();
+
+        // If suspended, the border will be shown again once we receive AppMessage::Resume
+        if !self.is_paused && !APP_STATE.is_suspended() && self.should_show_border() {
+            self.update_position(None).log_if_err();
+            self.render().log_if_err();
+            animations::set_timer_if_anims_enabled(self);
+        }
+
         Ok(())
     }
 
     fn create_render_resources(&mut self) -> anyhow::Result<()> {
         let render_target_properties = D2D1_RENDER_TARGET_PROPERTIES {
-            r#type: D2D1_RENDER_TARGET_TYPE_DEFAULT,
+            r#type: APP_STATE.get_render_target_type(),
             pixelFormat: D2D1_PIXEL_FORMAT {
                 format: DXGI_FORMAT_UNKNOWN,
                 alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
@@ -262,10 +572,10 @@ impl WindowBorder {
         };
 
         unsafe {
-            let render_target = APP_STATE.render_factory.CreateHwndRenderTarget(
-                &render_target_properties,
-                &hwnd_render_target_properties,
-            )?;
+            let render_target = APP_STATE
+                .render_factory
+                .CreateHwndRenderTarget(&render_target_properties, &hwnd_render_target_properties)
+                .inspect_err(|_| APP_STATE.record_render_failure())?;
 
             render_target.SetAntialiasMode(D2D1_ANTIALIAS_MODE_PER_PRIMITIVE);
 
@@ -275,6 +585,19 @@ impl WindowBorder {
             self.inactive_color
                 .init_brush(&render_target, &self.window_rect, &brush_properties)
                 .log_if_err();
+            if let Some(ref mut last_active_color) = self.last_active_color {
+                last_active_color
+                    .init_brush(&render_target, &self.window_rect, &brush_properties)
+                    .log_if_err();
+            }
+
+            self.debug_overlay = match APP_STATE.is_debug_overlay_enabled() {
+                true => DebugOverlay::new(&render_target)
+                    .context("could not create debug overlay")
+                    .inspect_err(|err| error!("{err:#}"))
+                    .ok(),
+                false => None,
+            };
 
             self.render_target = Some(render_target);
         }
@@ -283,6 +606,10 @@ impl WindowBorder {
     }
 
     fn update_window_rect(&mut self) -> anyhow::Result<()> {
+        if self.is_ambient_frame {
+            return self.update_ambient_frame_rect();
+        }
+
         if let Err(e) = unsafe {
             DwmGetWindowAttribute(
                 self.tracking_window,
@@ -299,34 +626,79 @@ impl WindowBorder {
             return Err(e);
         }
 
+        // If the border width depends on the window's size, recalculate it before expanding
+        if self.width_scale.is_some() {
+            let window_size = (self.window_rect.right - self.window_rect.left)
+                .max(self.window_rect.bottom - self.window_rect.top);
+
+            if window_size != self.tracking_window_size {
+                self.tracking_window_size = window_size;
+                self.update_width_radius();
+            }
+        }
+
+        self.update_always_on_top_frame();
+
         // Make space for the border
-        self.window_rect.top -= self.border_width;
-        self.window_rect.left -= self.border_width;
-        self.window_rect.right += self.border_width;
-        self.window_rect.bottom += self.border_width;
+        let expansion = self.get_rect_expansion();
+        self.window_rect.top -= expansion;
+        self.window_rect.left -= expansion;
+        self.window_rect.right += expansion;
+        self.window_rect.bottom += expansion;
 
         Ok(())
     }
 
+    fn update_ambient_frame_rect(&mut self) -> anyhow::Result<()> {
+        match get_monitor_work_area(self.tracking_window) {
+            Some(work_area) => {
+                self.window_rect = work_area;
+                Ok(())
+            }
+            None => Err(anyhow!(
+                "could not get work area for {:?}",
+                self.tracking_window
+            )),
+        }
+    }
+
     fn update_position(&mut self, other_flags: Option<SET_WINDOW_POS_FLAGS>) -> anyhow::Result<()> {
         unsafe {
-            // Get the hwnd above the tracking hwnd so we can place the border window in between
-            let hwnd_above_tracking = GetWindow(self.tracking_window, GW_HWNDPREV);
+            // Get the hwnd above the tracking hwnd so we can place the border window in between.
+            // If we're mitigating a conflict, we place the border right below the tracking window.
+            // Ambient frames aren't tied to any window's z-order, so they stay above everything.
+            // While the window is being dragged, the border can lag behind it (especially when
+            // shaking it), so we also keep it below the window to avoid drawing over its content.
+            let (hwnd_insert_after, hwnd_adjacent) = match self.conflict_mitigation {
+                _ if self.is_ambient_frame => (Ok(HWND_TOPMOST), Ok(HWND_TOPMOST)),
+                _ if self.is_in_move_size => (
+                    Ok(self.tracking_window),
+                    GetWindow(self.tracking_window, GW_HWNDNEXT),
+                ),
+                ConflictMitigation::Ignore => {
+                    let hwnd_above_tracking = GetWindow(self.tracking_window, GW_HWNDPREV);
+                    (hwnd_above_tracking.clone(), hwnd_above_tracking)
+                }
+                ConflictMitigation::BelowWindow => (
+                    Ok(self.tracking_window),
+                    GetWindow(self.tracking_window, GW_HWNDNEXT),
+                ),
+            };
 
             let mut swp_flags = SWP_NOSENDCHANGING
                 | SWP_NOACTIVATE
                 | SWP_NOREDRAW
                 | other_flags.unwrap_or_default();
 
-            // If hwnd_above_tracking is the window border itself, we have what we want and there's
-            // no need to change the z-order (plus it results in an error if we try it).
-            if hwnd_above_tracking == Ok(self.border_window) {
+            // If hwnd_adjacent is the window border itself, we have what we want and there's no
+            // need to change the z-order (plus it results in an error if we try it).
+            if hwnd_adjacent == Ok(self.border_window) {
                 swp_flags |= SWP_NOZORDER;
             }
 
             if let Err(e) = SetWindowPos(
                 self.border_window,
-                hwnd_above_tracking.unwrap_or(HWND_TOP),
+                hwnd_insert_after.unwrap_or(HWND_TOP),
                 self.window_rect.left,
                 self.window_rect.top,
                 self.window_rect.right - self.window_rect.left,
@@ -344,9 +716,68 @@ impl WindowBorder {
         Ok(())
     }
 
+    // Tools that make windows translucent don't notify anyone, so this is rechecked whenever the
+    // border's color is updated and on every heartbeat
+    fn update_window_alpha(&mut self) {
+        let window_alpha = get_window_alpha(self.tracking_window);
+        if window_alpha == self.window_alpha {
+            return;
+        }
+
+        self.window_alpha = window_alpha;
+        unsafe {
+            SetLayeredWindowAttributes(
+                self.border_window,
+                COLORREF(0x00000000),
+                self.window_alpha,
+                LWA_ALPHA,
+            )
+        }
+        .context("could not update border alpha")
+        .log_if_err();
+    }
+
     fn update_color(&mut self, check_delay: Option<u64>) -> anyhow::Result<()> {
-        self.is_active_window =
-            self.tracking_window.0 as isize == *APP_STATE.active_window.lock().unwrap();
+        self.update_window_alpha();
+
+        // Ambient frames don't track focus, so they always use the active color
+        let is_active_window = self.is_ambient_frame
+            || self.tracking_window.0 as isize == *APP_STATE.active_window.lock().unwrap();
+
+        let is_last_active_window =
+            self.tracking_window.0 as isize == *APP_STATE.last_active_window.lock().unwrap();
+        self.set_last_active_state(is_last_active_window);
+
+        self.set_active_state(is_active_window, check_delay)
+    }
+
+    fn set_active_state(
+        &mut self,
+        is_active_window: bool,
+        check_delay: Option<u64>,
+    ) -> anyhow::Result<()> {
+        // Play the focus ring when the window gains focus, but not when the border is first shown
+        // or reloaded (check_delay is Some(0) then)
+        if !is_active_window {
+            animations::stop_focus_ring(self);
+        } else if !self.is_active_window
+            && check_delay != Some(0)
+            && self.animations.active.contains_type(AnimType::FocusRing)
+        {
+            animations::start_focus_ring(self);
+        }
+
+        self.is_active_window = is_active_window;
+
+        if self.tint_native_border {
+            let color = match self.is_active_window {
+                true => &self.active_color,
+                false => self.get_inactive_color(),
+            };
+            set_window_border_color(self.tracking_window, color.to_colorref())
+                .context("could not set native border color")
+                .log_if_err();
+        }
 
         match animations::get_current_anims(self).contains_type(AnimType::Fade) {
             false => self.update_brush_opacities(),
@@ -360,92 +791,378 @@ impl WindowBorder {
         Ok(())
     }
 
+    fn set_last_active_state(&mut self, is_last_active_window: bool) {
+        if is_last_active_window == self.is_last_active_window {
+            return;
+        }
+
+        // Carry the opacity over so that any ongoing fade continues from where it was
+        let previous_color = self.get_inactive_color();
+        let opacity = previous_color.get_opacity().unwrap_or(0.0);
+        previous_color.reset_brush_color();
+
+        self.is_last_active_window = is_last_active_window;
+
+        let current_color = self.get_inactive_color();
+        current_color.reset_brush_color();
+        current_color.set_opacity(opacity);
+    }
+
+    // The color to draw while the window is inactive. Both colors are kept (and their keyframes
+    // advanced), so switching between them only changes which one gets drawn.
+    pub fn get_inactive_color(&self) -> &Color {
+        match (self.is_last_active_window, self.last_active_color.as_ref()) {
+            (true, Some(last_active_color)) => last_active_color,
+            _ => &self.inactive_color,
+        }
+    }
+
+    // Extrapolate the next window rect if the tracking window is being animated (e.g. a terminal
+    // animating to a new size), so the border doesn't lag one event behind. The real rect is kept
+    // separately so any misprediction is corrected on the next event (or by the correction timer).
+    fn predict_window_rect(&mut self) {
+        let real_rect = self.window_rect;
+        let is_animating = self
+            .last_location_time
+            .is_some_and(|time| time.elapsed().as_millis() <= RECT_PREDICTION_THRESHOLD_MS);
+
+        if is_animating
+            && self.last_real_window_rect != real_rect
+            && !is_window_in_move_size(self.tracking_window)
+        {
+            self.window_rect = predict_next_rect(&self.last_real_window_rect, &real_rect);
+
+            // Snap back to the real rect if no more LOCATIONCHANGE events arrive
+            unsafe {
+                SetTimer(
+                    self.border_window,
+                    RECT_CORRECTION_TIMER_ID,
+                    RECT_CORRECTION_DELAY_MS,
+                    None,
+                )
+            };
+        }
+
+        self.last_real_window_rect = real_rect;
+        self.last_location_time = Some(time::Instant::now());
+    }
+
+    // NOTE: this relies on window_rect, so it should be called after update_window_rect()
+    fn should_show_border(&self) -> bool {
+        self.should_show_border_with(&Win32WindowSystem)
+    }
+
+    fn should_show_border_with(&self, system: &impl WindowSystem) -> bool {
+        if self.is_ambient_frame {
+            return true;
+        }
+
+        system.has_native_border(self.tracking_window)
+            && self.meets_min_window_size()
+            && self.is_on_focused_monitor(system)
+            && !self.is_hidden_by_always_on_top_frame()
+    }
+
+    // How much window_rect is expanded on each side of the tracking window. When nesting around an
+    // Always On Top frame, the extra space puts the border's stroke outside of the frame.
+    fn get_rect_expansion(&self) -> i32 {
+        let frame_width = match self.always_on_top_frame {
+            AlwaysOnTopFrame::Nest => self.always_on_top_frame_width.unwrap_or(0),
+            _ => 0,
+        };
+
+        self.border_placement.get_rect_expansion(self.border_width)
+            + self.get_focus_ring_padding()
+            + frame_width
+    }
+
+    // Extra space outside of the border's stroke for the focus ring to grow into
+    fn get_focus_ring_padding(&self) -> i32 {
+        match !self.is_ambient_frame && self.animations.active.contains_type(AnimType::FocusRing) {
+            true => animations::get_focus_ring_padding(self.border_width),
+            false => 0,
+        }
+    }
+
+    // Returns true if the Always On Top frame was added, removed, or resized
+    fn update_always_on_top_frame(&mut self) -> bool {
+        let frame_width = match self.always_on_top_frame {
+            AlwaysOnTopFrame::Ignore => None,
+            _ => get_always_on_top_frame_width(self.tracking_window),
+        };
+
+        let has_changed = frame_width != self.always_on_top_frame_width;
+        self.always_on_top_frame_width = frame_width;
+        has_changed
+    }
+
+    // Pinning or unpinning a window with Always On Top changes its z-order but not its location, so
+    // re-run the location logic to show, hide, or resize the border when the frame changes
+    fn refresh_always_on_top_frame(&mut self) {
+        if self.update_always_on_top_frame() {
+            post_message_w(
+                self.border_window,
+                AppMessage::LocationChange.into(),
+                WPARAM(0),
+                LPARAM(0),
+            )
+            .context("could not post location change after always on top change")
+            .log_if_err();
+        }
+    }
+
+    fn is_hidden_by_always_on_top_frame(&self) -> bool {
+        self.always_on_top_frame == AlwaysOnTopFrame::Hide
+            && self.always_on_top_frame_width.is_some()
+    }
+
+    // Re-check whether the border is completely covered by another window. This walks the z-order,
+    // so it only runs when the covering window may have changed instead of on every frame. Returns
+    // true if the border was just revealed and should be rendered to catch up.
+    fn update_occlusion(&mut self) -> bool {
+        let is_occluded = !self.is_paused
+            && self.pause_when_occluded
+            && is_rect_occluded(self.tracking_window, &self.window_rect);
+        if is_occluded == self.is_occluded {
+            return false;
+        }
+
+        self.is_occluded = is_occluded;
+        unsafe {
+            match is_occluded {
+                // The window covering us can move away without any event being sent, so poll
+                true => {
+                    SetTimer(
+                        self.border_window,
+                        OCCLUSION_TIMER_ID,
+                        OCCLUSION_CHECK_MS,
+                        None,
+                    );
+                }
+                false => {
+                    let _ = KillTimer(self.border_window, OCCLUSION_TIMER_ID);
+                }
+            }
+        }
+
+        !is_occluded && !self.is_paused
+    }
+
+    fn has_monitor_relative_gradient(&self) -> bool {
+        self.active_color.is_monitor_relative()
+            || self.inactive_color.is_monitor_relative()
+            || self
+                .last_active_color
+                .as_ref()
+                .is_some_and(Color::is_monitor_relative)
+    }
+
+    // Windows that are playing audio are drawn with the active color if audio_indicator is
+    // enabled, but they aren't treated as focused otherwise (no focus ring, fades, etc.)
+    fn shows_audio_indicator(&self) -> bool {
+        self.audio_indicator && self.is_playing_audio && !self.is_active_window
+    }
+
+    fn is_on_focused_monitor(&self, system: &impl WindowSystem) -> bool {
+        if !self.only_on_focused_monitor {
+            return true;
+        }
+
+        let active_window = system.get_active_window();
+        system.get_monitor_for_window(active_window)
+            == system.get_monitor_for_window(self.tracking_window)
+    }
+
+    fn meets_min_window_size(&self) -> bool {
+        // window_rect includes the space we made for the border, so we have to subtract it here
+        let expansion = self.get_rect_expansion();
+        let width = self.window_rect.right - self.window_rect.left - 2 * expansion;
+        let height = self.window_rect.bottom - self.window_rect.top - 2 * expansion;
+
+        let min_width = (self.min_window_size.width as f32 * self.current_dpi / 96.0) as i32;
+        let min_height = (self.min_window_size.height as f32 * self.current_dpi / 96.0) as i32;
+
+        width >= min_width && height >= min_height
+    }
+
     fn update_brush_opacities(&mut self) {
         let (top_color, bottom_color) = match self.is_active_window {
-            true => (&mut self.active_color, &mut self.inactive_color),
-            false => (&mut self.inactive_color, &mut self.active_color),
+            true => (&self.active_color, self.get_inactive_color()),
+            false => (self.get_inactive_color(), &self.active_color),
         };
         top_color.set_opacity(1.0);
         bottom_color.set_opacity(0.0);
     }
 
     fn update_width_radius(&mut self) {
-        let window_rule = get_window_rule(self.tracking_window);
-        let config = APP_STATE.config.read().unwrap();
-        let global = &config.global;
-
-        let width_config = window_rule.border_width.unwrap_or(global.border_width);
-        let radius_config = window_rule
-            .border_radius
-            .as_ref()
-            .unwrap_or(&global.border_radius);
+        let width_config = match self.width_scale {
+            Some(width_scale) => {
+                let window_size = self.tracking_window_size as f32 * 96.0 / self.current_dpi;
+                width_scale.scale_width(self.width_config, window_size)
+            }
+            None => self.width_config,
+        };
 
         self.border_width = (width_config * self.current_dpi / 96.0).round() as i32;
-        self.border_radius =
-            radius_config.to_radius(self.border_width, self.current_dpi, self.tracking_window);
+        self.border_radius = self.radius_config.to_radius(
+            self.border_width,
+            self.border_placement,
+            self.current_dpi,
+            self.tracking_window,
+        );
+        self.rounded_rect.radiusX = self.border_radius;
+        self.rounded_rect.radiusY = self.border_radius;
     }
 
     fn render(&mut self) -> anyhow::Result<()> {
-        self.last_render_time = Some(time::Instant::now());
+        let render_start = time::Instant::now();
 
         let Some(ref render_target) = self.render_target else {
             return Err(anyhow!("render_target has not been set yet"));
         };
 
+        // Skip rendering until the pending resize retry (see retry_resize) has run. Nothing of the
+        // border can be seen while it's covered either, so don't bother rendering it then.
+        if self.is_resize_retry_pending || self.is_occluded {
+            return Ok(());
+        }
+
         let pixel_size = D2D_SIZE_U {
             width: (self.window_rect.right - self.window_rect.left) as u32,
             height: (self.window_rect.bottom - self.window_rect.top) as u32,
         };
 
-        let border_width = self.border_width as f32;
         let border_offset = self.border_offset as f32;
 
+        // Distance from the edges of the border window to the center of the border's stroke
+        let inset = (self.border_placement.get_rect_expansion(self.border_width)
+            + self.get_focus_ring_padding()) as f32
+            - self.border_placement.get_stroke_center(self.border_width);
+
         self.rounded_rect.rect = D2D_RECT_F {
-            left: border_width / 2.0 - border_offset,
-            top: border_width / 2.0 - border_offset,
-            right: (self.window_rect.right - self.window_rect.left) as f32 - border_width / 2.0
-                + border_offset,
-            bottom: (self.window_rect.bottom - self.window_rect.top) as f32 - border_width / 2.0
-                + border_offset,
+            left: inset - border_offset,
+            top: inset - border_offset,
+            right: (self.window_rect.right - self.window_rect.left) as f32 - inset + border_offset,
+            bottom: (self.window_rect.bottom - self.window_rect.top) as f32 - inset + border_offset,
         };
 
+        // The render target uses 96 dpi, so its coordinates are already in device pixels
+        if self.pixel_snap {
+            self.rounded_rect.rect = snap_stroke_rect(&self.rounded_rect.rect, self.border_width);
+        }
+
+        // Only look up the monitor rect if a gradient actually needs it
+        let monitor_rect = self
+            .has_monitor_relative_gradient()
+            .then(|| get_monitor_rect(self.tracking_window))
+            .flatten();
+
         unsafe {
-            render_target
-                .Resize(&pixel_size)
-                .context("could not resize render_target")?;
+            if let Err(err) = render_target.Resize(&pixel_size) {
+                return self.retry_resize(err);
+            }
+            self.resize_retries = 0;
+
+            // Only count frames that are actually drawn, so skipped renders don't skew the pacing
+            self.last_render_interval = self
+                .last_render_time
+                .map(|last_render_time| render_start - last_render_time);
+            self.last_render_time = Some(render_start);
 
             // Determine which color/rectangle should be drawn on top
             let (bottom_color, top_color) = match self.is_active_window {
-                true => (&self.inactive_color, &self.active_color),
-                false => (&self.active_color, &self.inactive_color),
+                true => (self.get_inactive_color(), &self.active_color),
+                false => (&self.active_color, self.get_inactive_color()),
             };
 
             render_target.BeginDraw();
             render_target.Clear(None);
 
-            if bottom_color.get_opacity() > Some(0.0) {
-                if let Color::Gradient(gradient) = bottom_color {
-                    gradient.update_start_end_points(&self.window_rect);
+            if self.shows_audio_indicator() {
+                if let Color::Gradient(ref gradient) = self.active_color {
+                    gradient.update_geometry(&self.window_rect, monitor_rect.as_ref());
                 }
 
-                match bottom_color.get_brush() {
-                    Some(id2d1_brush) => self.draw_rectangle(render_target, id2d1_brush),
-                    None => debug!("ID2D1Brush for bottom_color has not been created yet"),
+                // Draw the active color at full opacity regardless of the inactive fade state
+                match self.active_color.get_brush() {
+                    Some(id2d1_brush) => {
+                        let opacity = id2d1_brush.GetOpacity();
+                        id2d1_brush.SetOpacity(1.0);
+                        self.draw_rectangle(render_target, id2d1_brush);
+                        id2d1_brush.SetOpacity(opacity);
+                    }
+                    None => debug!("ID2D1Brush for active_color has not been created yet"),
                 }
-            }
-            if top_color.get_opacity() > Some(0.0) {
-                if let Color::Gradient(gradient) = top_color {
-                    gradient.update_start_end_points(&self.window_rect);
+            } else {
+                if bottom_color.get_opacity() > Some(0.0) {
+                    if let Color::Gradient(gradient) = bottom_color {
+                        gradient.update_geometry(&self.window_rect, monitor_rect.as_ref());
+                    }
+
+                    match bottom_color.get_brush() {
+                        Some(id2d1_brush) => self.draw_rectangle(render_target, id2d1_brush),
+                        None => debug!("ID2D1Brush for bottom_color has not been created yet"),
+                    }
                 }
+                if top_color.get_opacity() > Some(0.0) {
+                    if let Color::Gradient(gradient) = top_color {
+                        gradient.update_geometry(&self.window_rect, monitor_rect.as_ref());
+                    }
 
-                match top_color.get_brush() {
-                    Some(id2d1_brush) => self.draw_rectangle(render_target, id2d1_brush),
-                    None => debug!("ID2D1Brush for top_color has not been created yet"),
+                    match top_color.get_brush() {
+                        Some(id2d1_brush) => self.draw_rectangle(render_target, id2d1_brush),
+                        None => debug!("ID2D1Brush for top_color has not been created yet"),
+                    }
                 }
             }
 
+            // The focus ring is only played while the window is active, so it uses the active color
+            if let (Some(focus_ring), Some(id2d1_brush)) =
+                (self.animations.focus_ring, self.active_color.get_brush())
+            {
+                // Keep the ring's inner edge on the border's inner edge so it only grows outward
+                let stroke_width = self.border_width as f32 * focus_ring.width_scale;
+                let spread = (stroke_width - self.border_width as f32) / 2.0;
+                let rect = &self.rounded_rect.rect;
+                let ring_rect = D2D1_ROUNDED_RECT {
+                    rect: D2D_RECT_F {
+                        left: rect.left - spread,
+                        top: rect.top - spread,
+                        right: rect.right + spread,
+                        bottom: rect.bottom + spread,
+                    },
+                    radiusX: self.rounded_rect.radiusX + spread,
+                    radiusY: self.rounded_rect.radiusY + spread,
+                };
+
+                let opacity = id2d1_brush.GetOpacity();
+                id2d1_brush.SetOpacity(opacity * focus_ring.opacity);
+                self.draw_rounded_rect(render_target, &ring_rect, id2d1_brush, stroke_width);
+                id2d1_brush.SetOpacity(opacity);
+            }
+
+            if let Some(ref debug_overlay) = self.debug_overlay {
+                let stats = RenderStats {
+                    fps: self
+                        .last_render_interval
+                        .map(|interval| 1.0 / interval.as_secs_f32()),
+                    frame_time: self
+                        .last_frame_duration
+                        .map(|duration| duration.as_secs_f32() * 1000.0),
+                    is_software: APP_STATE.is_software_fallback_active(),
+                    width: pixel_size.width,
+                    height: pixel_size.height,
+                };
+                debug_overlay.draw(
+                    render_target,
+                    &stats,
+                    &self.rounded_rect.rect,
+                    self.border_width as f32,
+                );
+            }
+
             match render_target.EndDraw(None, None) {
-                Ok(_) => {}
+                Ok(_) => APP_STATE.record_render_success(),
                 Err(e) if e.code() == D2DERR_RECREATE_TARGET => {
                     // D2DERR_RECREATE_TARGET is recoverable if we just recreate the render target.
                     // This error can be caused by things like waking up from sleep, updating GPU
@@ -453,53 +1170,194 @@ impl WindowBorder {
                     warn!("render_target has been lost; attempting to recreate");
 
                     match self.create_render_resources() {
-                        Ok(_) => info!("successfully recreated render_target; resuming thread"),
+                        Ok(_) => {
+                            info!("successfully recreated render_target; resuming thread");
+                            write_event(TraceEvent::RenderTargetRecreated(self.tracking_window));
+                        }
                         Err(e_2) => {
                             error!("could not recreate render_target; exiting thread: {e_2}");
-                            self.exit_border_thread();
+                            self.exit_after_render_failure();
                         }
                     }
                 }
                 Err(other) => {
                     error!("render_target.EndDraw() failed; exiting thread: {other}");
-                    self.exit_border_thread();
+                    APP_STATE.record_render_failure();
+                    self.exit_after_render_failure();
                 }
             }
         }
 
+        self.last_frame_duration = Some(render_start.elapsed());
+        write_event(TraceEvent::Render(
+            self.tracking_window,
+            render_start.elapsed(),
+        ));
+
+        Ok(())
+    }
+
+    fn retry_resize(&mut self, err: windows::core::Error) -> anyhow::Result<()> {
+        if self.resize_retries >= MAX_RESIZE_RETRIES {
+            error!(
+                "could not resize render_target after {MAX_RESIZE_RETRIES} retries; exiting thread: {err}"
+            );
+            APP_STATE.record_render_failure();
+            self.exit_after_render_failure();
+            return Ok(());
+        }
+
+        let delay = RESIZE_RETRY_BASE_DELAY_MS << self.resize_retries;
+        self.resize_retries += 1;
+        warn!("could not resize render_target; retrying in {delay}ms: {err}");
+
+        unsafe { SetTimer(self.border_window, RESIZE_RETRY_TIMER_ID, delay, None) };
+        self.is_resize_retry_pending = true;
+
         Ok(())
     }
 
+    fn refresh_ambient_frame(&mut self) {
+        let old_rect = self.window_rect;
+        self.update_window_rect().log_if_err();
+
+        let update_pos_flags = (!is_window_visible(self.border_window)).then_some(SWP_SHOWWINDOW);
+        self.update_position(update_pos_flags).log_if_err();
+
+        if self.window_rect != old_rect {
+            self.render().log_if_err();
+        }
+    }
+
+    // Recreate the render target and recalculate the border's dpi, width, and rect from scratch.
+    // Unlike the usual update paths, this doesn't skip anything that looks unchanged, because the
+    // cached values may all be stale after a display change.
+    fn resync_after_resume(&mut self) {
+        debug!(
+            "resyncing border for {:?} after resume",
+            self.tracking_window
+        );
+
+        if let Err(err) = self.create_render_resources() {
+            error!("could not recreate render_target after resume; exiting thread: {err}");
+            self.exit_after_render_failure();
+            return;
+        }
+
+        // The new brushes start out transparent, so restore their opacities right away. This also
+        // covers hidden borders, which may be shown again without updating their color.
+        self.update_color(Some(0)).log_if_err();
+
+        let dpi = get_dpi_for_window(self.tracking_window);
+        if dpi != 0 {
+            self.current_dpi = dpi as f32;
+        }
+        self.update_width_radius();
+
+        let old_rect = self.window_rect;
+        self.update_window_rect().log_if_err();
+        if !is_rect_visible(&self.window_rect) {
+            self.window_rect = old_rect;
+        }
+
+        // Hidden borders are resynced again once they're shown
+        if self.is_paused || APP_STATE.is_suspended() || !is_window_visible(self.border_window) {
+            return;
+        }
+
+        if self.should_show_border() {
+            self.update_position(None).log_if_err();
+            self.render().log_if_err();
+        } else {
+            self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+        }
+    }
+
     fn draw_rectangle(&self, render_target: &ID2D1HwndRenderTarget, brush: &ID2D1Brush) {
+        self.draw_rounded_rect(
+            render_target,
+            &self.rounded_rect,
+            brush,
+            self.border_width as f32,
+        );
+    }
+
+    fn draw_rounded_rect(
+        &self,
+        render_target: &ID2D1HwndRenderTarget,
+        rounded_rect: &D2D1_ROUNDED_RECT,
+        brush: &ID2D1Brush,
+        stroke_width: f32,
+    ) {
         unsafe {
             match self.border_radius {
-                0.0 => render_target.DrawRectangle(
-                    &self.rounded_rect.rect,
-                    brush,
-                    self.border_width as f32,
-                    None,
-                ),
-                _ => render_target.DrawRoundedRectangle(
-                    &self.rounded_rect,
-                    brush,
-                    self.border_width as f32,
-                    None,
-                ),
+                0.0 => render_target.DrawRectangle(&rounded_rect.rect, brush, stroke_width, None),
+                _ => render_target.DrawRoundedRectangle(rounded_rect, brush, stroke_width, None),
             }
         }
     }
 
     fn exit_border_thread(&mut self) {
+        write_event(TraceEvent::BorderDestroyed(self.tracking_window));
+
+        // If the tracking window closed, remember it in case it's about to be replaced by a new one
+        if !self.is_ambient_frame && !unsafe { IsWindow(self.tracking_window) }.as_bool() {
+            let expansion = self.get_rect_expansion();
+            record_closed_window(ClosedWindow {
+                process_id: self.process_id,
+                class: mem::take(&mut self.window_class),
+                rect: RECT {
+                    left: self.window_rect.left + expansion,
+                    top: self.window_rect.top + expansion,
+                    right: self.window_rect.right - expansion,
+                    bottom: self.window_rect.bottom - expansion,
+                },
+                time: time::Instant::now(),
+            });
+        }
+
         self.is_paused = true;
         animations::destroy_timer(self);
-        APP_STATE
-            .borders
-            .lock()
-            .unwrap()
-            .remove(&(self.tracking_window.0 as isize));
+
+        // Restore the tracking window's own border color if we changed it
+        if self.tint_native_border {
+            let _ = set_window_border_color(self.tracking_window, COLORREF(DWMWA_COLOR_DEFAULT));
+        }
+
+        // Only remove the entry if it's still ours (the watchdog may have replaced this border)
+        let mut borders = APP_STATE.borders.lock().unwrap();
+        let tracking_window_isize = self.tracking_window.0 as isize;
+        if borders.get(&tracking_window_isize) == Some(&(self.border_window.0 as isize)) {
+            borders.remove(&tracking_window_isize);
+        }
+        drop(borders);
+
         unsafe { PostQuitMessage(0) };
     }
 
+    // Exit the border thread and schedule a new border for the window, so it recovers once rendering
+    // works again (possibly in software mode, see AppState::record_render_failure)
+    fn exit_after_render_failure(&mut self) {
+        let failures = APP_STATE.get_render_failures().max(1);
+
+        match failures <= MAX_RENDER_RECOVERY_FAILURES {
+            true => {
+                let delay = RENDER_RECOVERY_BASE_DELAY_MS << (failures - 1);
+                debug!(
+                    "recreating border for {:?} in {delay}ms",
+                    self.tracking_window
+                );
+                recreate_border_after(self.tracking_window, time::Duration::from_millis(delay));
+            }
+            false => error!(
+                "rendering has failed {failures} times in a row; not recreating border for {:?}",
+                self.tracking_window
+            ),
+        }
+
+        self.exit_border_thread();
+    }
+
     pub unsafe extern "system" fn s_wnd_proc(
         window: HWND,
         message: u32,
@@ -530,10 +1388,124 @@ impl WindowBorder {
         wparam: WPARAM,
         lparam: LPARAM,
     ) -> LRESULT {
+        // Our own messages are dispatched separately from the standard window messages below
+        if let Ok(app_message) = AppMessage::try_from(message) {
+            return self.handle_app_message(window, app_message, wparam);
+        }
+
         match message {
+            WM_TIMER if wparam.0 == RECT_CORRECTION_TIMER_ID => {
+                let _ = KillTimer(window, RECT_CORRECTION_TIMER_ID);
+
+                if self.is_paused || !has_native_border(self.tracking_window) {
+                    return LRESULT(0);
+                }
+
+                // Replace the last predicted rect with the real one
+                let old_rect = self.window_rect;
+                self.update_window_rect().log_if_err();
+
+                if !is_rect_visible(&self.window_rect) {
+                    self.window_rect = old_rect;
+                    return LRESULT(0);
+                }
+
+                self.update_position(None).log_if_err();
+                if !are_rects_same_size(&self.window_rect, &old_rect) {
+                    self.render().log_if_err();
+                }
+            }
+            WM_TIMER if wparam.0 == RESIZE_RETRY_TIMER_ID => {
+                let _ = KillTimer(window, RESIZE_RETRY_TIMER_ID);
+                self.is_resize_retry_pending = false;
+
+                if !self.is_paused {
+                    self.render().log_if_err();
+                }
+            }
+            WM_TIMER if wparam.0 == OCCLUSION_TIMER_ID => {
+                if self.update_occlusion() && is_window_visible(self.border_window) {
+                    self.render().log_if_err();
+                }
+            }
+            // The accent color, colorization intensity, or opaque blending changed, so colors
+            // derived from the accent color need to be reloaded
+            WM_DWMCOLORIZATIONCOLORCHANGED => {
+                self.reload_appearance(self.window_rule.clone())
+                    .log_if_err();
+            }
+            // Sent when the light/dark app mode changes, so themed colors need to be reloaded
+            WM_SETTINGCHANGE if is_theme_change(lparam) => {
+                self.reload_appearance(self.window_rule.clone())
+                    .log_if_err();
+            }
+            // The work area changes when the taskbar is moved or resized or when the display
+            // settings change, neither of which sends us a LOCATIONCHANGE event
+            WM_SETTINGCHANGE | WM_DISPLAYCHANGE if self.is_ambient_frame => {
+                if !self.is_paused && !APP_STATE.is_suspended() {
+                    self.refresh_ambient_frame();
+                }
+            }
+            // The resolution or scaling may have changed while the system was asleep, in which
+            // case the border would keep its pre-sleep size until the window happens to move
+            WM_POWERBROADCAST
+                if matches!(
+                    wparam.0 as u32,
+                    PBT_APMRESUMEAUTOMATIC | PBT_APMRESUMESUSPEND
+                ) =>
+            {
+                self.resync_after_resume();
+            }
+            WM_PAINT => {
+                let _ = ValidateRect(window, None);
+            }
+            WM_NCDESTROY => {
+                // TODO not actually sure if we need to set GWLP_USERDATA to 0 here
+                SetWindowLongPtrW(window, GWLP_USERDATA, 0);
+                self.exit_border_thread();
+            }
+            // Ignore these window position messages
+            WM_WINDOWPOSCHANGING | WM_WINDOWPOSCHANGED => {}
+            _ => {
+                return DefWindowProcW(window, message, wparam, lparam);
+            }
+        }
+        LRESULT(0)
+    }
+
+    unsafe fn handle_app_message(
+        &mut self,
+        window: HWND,
+        message: AppMessage,
+        wparam: WPARAM,
+    ) -> LRESULT {
+        // Let the animation timer queue its next tick. This has to happen even if we skip the tick
+        // below, or the timer would never post another one.
+        if message == AppMessage::Animate {
+            if let Some(ref timer) = self.animations.timer {
+                timer.acknowledge_tick();
+            }
+        }
+
+        match message {
+            // Ignore these while borders are suspended (e.g. a game is in the foreground). We'll
+            // resync everything once we receive AppMessage::Resume.
+            AppMessage::LocationChange
+            | AppMessage::Reorder
+            | AppMessage::Foreground
+            | AppMessage::ShowUncloaked
+            | AppMessage::MinimizeEnd
+            | AppMessage::Animate
+            | AppMessage::Render
+                if APP_STATE.is_suspended() => {}
             // EVENT_OBJECT_LOCATIONCHANGE
-            WM_APP_LOCATIONCHANGE => {
-                if self.is_paused {
+            AppMessage::LocationChange => {
+                if self.is_paused || self.is_move_size_deferred {
+                    return LRESULT(0);
+                }
+
+                if self.is_ambient_frame {
+                    self.refresh_ambient_frame();
                     return LRESULT(0);
                 }
 
@@ -555,11 +1527,49 @@ impl WindowBorder {
                     return LRESULT(0);
                 }
 
+                self.predict_window_rect();
+
+                // If the window moves to a monitor with a different color profile, reload the
+                // colors so they get converted for the new monitor instead
+                if self.color_management
+                    && get_monitor_for_window(self.tracking_window) != self.color_profile_monitor
+                {
+                    self.reload_appearance(self.window_rule.clone())
+                        .log_if_err();
+                    return LRESULT(0);
+                }
+
+                // If the active window moves to another monitor, the other borders need to be
+                // shown or hidden accordingly, which the Foreground handler takes care of
+                if self.only_on_focused_monitor {
+                    let new_monitor = get_monitor_for_window(self.tracking_window);
+                    if self.is_active_window && new_monitor != self.current_monitor {
+                        post_message_to_all_borders(AppMessage::Foreground);
+                    }
+                    self.current_monitor = new_monitor;
+                }
+
+                // Hide the border if the window has become too small, is no longer on the focused
+                // monitor, or was pinned by Always On Top. It will automatically be shown again
+                // below once it isn't.
+                if !self.meets_min_window_size()
+                    || !self.is_on_focused_monitor(&Win32WindowSystem)
+                    || self.is_hidden_by_always_on_top_frame()
+                {
+                    self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+                    return LRESULT(0);
+                }
+
                 // If the window rect changes size, we need to re-render the border
                 if !are_rects_same_size(&self.window_rect, &old_rect) {
                     should_render |= true;
                 }
 
+                // Monitor-relative gradients also depend on the window's position
+                if self.window_rect != old_rect && self.has_monitor_relative_gradient() {
+                    should_render |= true;
+                }
+
                 let update_pos_flags =
                     (!is_window_visible(self.border_window)).then_some(SWP_SHOWWINDOW);
                 self.update_position(update_pos_flags).log_if_err();
@@ -580,24 +1590,44 @@ impl WindowBorder {
                     should_render |= true;
                 }
 
+                should_render |= self.update_occlusion();
+
                 if should_render {
                     self.render().log_if_err();
                 }
             }
             // EVENT_OBJECT_REORDER
-            WM_APP_REORDER => {
+            AppMessage::Reorder => {
                 // If something changes the z-order of windows, it may put the border window behind
                 // the tracking window, so we update the border's position here when that happens
                 self.update_position(None).log_if_err();
+
+                if self.update_occlusion() {
+                    self.render().log_if_err();
+                }
+
+                self.refresh_always_on_top_frame();
             }
             // EVENT_SYSTEM_FOREGROUND
-            WM_APP_FOREGROUND => {
+            AppMessage::Foreground => {
                 self.update_color(None).log_if_err();
-                self.update_position(None).log_if_err();
+
+                // The focused monitor may have changed, so we might need to show or hide the border
+                let update_pos_flags = match self.only_on_focused_monitor && !self.is_paused {
+                    true if self.should_show_border() => Some(SWP_SHOWWINDOW),
+                    true => {
+                        self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+                        return LRESULT(0);
+                    }
+                    false => None,
+                };
+
+                self.update_occlusion();
+                self.update_position(update_pos_flags).log_if_err();
                 self.render().log_if_err();
             }
             // EVENT_OBJECT_SHOW / EVENT_OBJECT_UNCLOAKED
-            WM_APP_SHOWUNCLOAKED => {
+            AppMessage::ShowUncloaked => {
                 // With GlazeWM, if I switch to another workspace while a window is minimized and
                 // switch back, then we will receive this message even though the window is not yet
                 // visible. And, the window rect will be all weird. So, we apply the following fix.
@@ -611,7 +1641,7 @@ impl WindowBorder {
 
                 self.update_color(None).log_if_err();
 
-                if has_native_border(self.tracking_window) {
+                if self.should_show_border() {
                     self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
                     self.render().log_if_err();
                 }
@@ -620,29 +1650,30 @@ impl WindowBorder {
                 self.is_paused = false;
             }
             // EVENT_OBJECT_HIDE / EVENT_OBJECT_CLOAKED
-            WM_APP_HIDECLOAKED => {
+            AppMessage::HideCloaked => {
                 self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
                 animations::destroy_timer(self);
                 self.is_paused = true;
             }
             // EVENT_OBJECT_MINIMIZESTART
-            WM_APP_MINIMIZESTART => {
+            AppMessage::MinimizeStart => {
                 self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
 
                 self.active_color.set_opacity(0.0);
-                self.inactive_color.set_opacity(0.0);
+                self.get_inactive_color().set_opacity(0.0);
 
                 animations::destroy_timer(self);
                 self.is_paused = true;
             }
             // EVENT_SYSTEM_MINIMIZEEND
-            WM_APP_MINIMIZEEND => {
+            AppMessage::MinimizeEnd => {
                 // Keep the border hidden while the tracking window is in its unminimize animation
                 thread::sleep(time::Duration::from_millis(self.unminimize_delay));
 
-                if has_native_border(self.tracking_window) {
+                self.update_window_rect().log_if_err();
+
+                if self.should_show_border() {
                     self.update_color(Some(self.unminimize_delay)).log_if_err();
-                    self.update_window_rect().log_if_err();
                     self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
                     self.render().log_if_err();
                 }
@@ -650,23 +1681,73 @@ impl WindowBorder {
                 animations::set_timer_if_anims_enabled(self);
                 self.is_paused = false;
             }
-            WM_APP_ANIMATE => {
+            AppMessage::MoveSizeStart => {
+                self.is_in_move_size = true;
+                self.is_move_size_deferred = !is_drag_full_windows_enabled();
+            }
+            AppMessage::MoveSizeEnd => {
+                self.is_in_move_size = false;
+
+                // Catch up on the location changes we skipped during the drag
+                if self.is_move_size_deferred {
+                    self.is_move_size_deferred = false;
+                    post_message_w(
+                        window,
+                        AppMessage::LocationChange.into(),
+                        WPARAM(0),
+                        LPARAM(0),
+                    )
+                    .context("could not post location change after move/resize")
+                    .log_if_err();
+                    return LRESULT(0);
+                }
+
+                // Restore the border's usual z-order now that the window has settled
+                if !self.is_paused && is_window_visible(self.border_window) {
+                    self.update_window_rect().log_if_err();
+                    self.update_position(None).log_if_err();
+                    self.render().log_if_err();
+                }
+            }
+            AppMessage::Suspend => {
+                self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+                animations::destroy_timer(self);
+            }
+            AppMessage::Resume => {
+                // Only show the border again if the tracking window is actually visible
+                if is_window_visible(self.tracking_window)
+                    && !is_window_cloaked(self.tracking_window)
+                    && !is_window_minimized(self.tracking_window)
+                {
+                    post_message_w(
+                        window,
+                        AppMessage::ShowUncloaked.into(),
+                        WPARAM(0),
+                        LPARAM(0),
+                    )
+                    .context("could not post AppMessage::ShowUncloaked in Resume")
+                    .log_if_err();
+                }
+            }
+            AppMessage::Animate => {
                 if self.is_paused {
                     return LRESULT(0);
                 }
 
-                let anim_elapsed = self
-                    .last_anim_time
-                    .unwrap_or(time::Instant::now())
-                    .elapsed();
+                let anim_elapsed = animations::get_anim_elapsed(self);
                 let render_elapsed = self
                     .last_render_time
                     .unwrap_or(time::Instant::now())
                     .elapsed();
 
-                self.last_anim_time = Some(time::Instant::now());
+                self.last_anim_time = Some(self.animations.clock.now());
 
-                let mut update = false;
+                // Advance keyframed colors first so that fades can still blend on top of them
+                let mut update = self.active_color.update_keyframes();
+                update |= self.inactive_color.update_keyframes();
+                if let Some(ref mut last_active_color) = self.last_active_color {
+                    update |= last_active_color.update_keyframes();
+                }
 
                 for anim_params in animations::get_current_anims(self).clone().iter() {
                     match anim_params.anim_type {
@@ -684,6 +1765,12 @@ impl WindowBorder {
                                 update = true;
                             }
                         }
+                        AnimType::FocusRing => {
+                            if self.animations.focus_ring.is_some() {
+                                animations::animate_focus_ring(self, &anim_elapsed, anim_params);
+                                update = true;
+                            }
+                        }
                     }
                 }
 
@@ -693,20 +1780,170 @@ impl WindowBorder {
                     self.render().log_if_err();
                 }
             }
-            WM_PAINT => {
-                let _ = ValidateRect(window, None);
+            AppMessage::SetColor => {
+                self.set_active_state(wparam.0 != 0, None).log_if_err();
+                self.render().log_if_err();
             }
-            WM_NCDESTROY => {
-                // TODO not actually sure if we need to set GWLP_USERDATA to 0 here
+            AppMessage::SetAudioPlaying => {
+                self.is_playing_audio = wparam.0 != 0;
+
+                // If suspended, the new state will be picked up once we receive Resume
+                if self.audio_indicator && !APP_STATE.is_suspended() {
+                    self.render().log_if_err();
+                }
+            }
+            AppMessage::ReloadAppearance => {
+                let window_rule = get_window_rule(self.tracking_window);
+                if let Err(err) = self.reload_appearance(window_rule) {
+                    error!("could not reload appearance for {window:?}: {err:#}");
+                }
+            }
+            AppMessage::NameChange => {
+                let window_rule = get_window_rule(self.tracking_window);
+                if window_rule == self.window_rule {
+                    return LRESULT(0);
+                }
+
+                if window_rule.enabled == Some(EnableMode::Bool(false)) {
+                    debug!(
+                        "border is disabled for {:?} after its title changed",
+                        self.tracking_window
+                    );
+                    SetWindowLongPtrW(window, GWLP_USERDATA, 0);
+                    self.exit_border_thread();
+                    return LRESULT(0);
+                }
+
+                debug!(
+                    "{:?} matches a different window rule after its title changed",
+                    self.tracking_window
+                );
+                if let Err(err) = self.reload_appearance(window_rule) {
+                    error!("could not reload appearance for {window:?}: {err:#}");
+                }
+            }
+            AppMessage::Render => {
+                if !self.is_paused && is_window_visible(self.border_window) {
+                    self.render().log_if_err();
+                }
+            }
+            AppMessage::Heartbeat => {
+                self.update_window_alpha();
+
+                // Hidden borders don't receive Reorder, so this is how they notice being unpinned
+                self.refresh_always_on_top_frame();
+
+                APP_STATE
+                    .heartbeats
+                    .lock()
+                    .unwrap()
+                    .insert(window.0 as isize, time::Instant::now());
+            }
+            AppMessage::Destroy => {
                 SetWindowLongPtrW(window, GWLP_USERDATA, 0);
                 self.exit_border_thread();
             }
-            // Ignore these window position messages
-            WM_WINDOWPOSCHANGING | WM_WINDOWPOSCHANGED => {}
-            _ => {
-                return DefWindowProcW(window, message, wparam, lparam);
-            }
         }
         LRESULT(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_system::mock::{MockWindow, MockWindowSystem};
+
+    const TRACKING_WINDOW: isize = 1;
+    const OTHER_WINDOW: isize = 2;
+
+    fn border(width: i32, height: i32) -> WindowBorder {
+        WindowBorder {
+            tracking_window: HWND(TRACKING_WINDOW as _),
+            window_rect: RECT {
+                left: 0,
+                top: 0,
+                right: width,
+                bottom: height,
+            },
+            min_window_size: MinWindowSize {
+                width: 50,
+                height: 50,
+            },
+            current_dpi: 96.0,
+            ..Default::default()
+        }
+    }
+
+    fn system(tracking_monitor: isize, active_monitor: isize) -> MockWindowSystem {
+        let window = |monitor| MockWindow {
+            has_native_border: true,
+            monitor,
+            ..Default::default()
+        };
+
+        MockWindowSystem {
+            active_window: OTHER_WINDOW,
+            ..Default::default()
+        }
+        .with_window(TRACKING_WINDOW, window(tracking_monitor))
+        .with_window(OTHER_WINDOW, window(active_monitor))
+    }
+
+    #[test]
+    fn shows_border_for_regular_window() {
+        assert!(border(800, 600).should_show_border_with(&system(1, 1)));
+    }
+
+    #[test]
+    fn hides_border_without_native_border() {
+        let mut system = system(1, 1);
+        system
+            .windows
+            .get_mut(&TRACKING_WINDOW)
+            .unwrap()
+            .has_native_border = false;
+
+        assert!(!border(800, 600).should_show_border_with(&system));
+    }
+
+    #[test]
+    fn hides_border_below_min_window_size() {
+        assert!(!border(40, 600).should_show_border_with(&system(1, 1)));
+
+        // The minimum size is in logical pixels, so it scales with the dpi
+        let mut border = border(80, 80);
+        assert!(border.should_show_border_with(&system(1, 1)));
+        border.current_dpi = 192.0;
+        assert!(!border.should_show_border_with(&system(1, 1)));
+    }
+
+    #[test]
+    fn only_on_focused_monitor_compares_monitors() {
+        let mut border = border(800, 600);
+        assert!(border.should_show_border_with(&system(1, 2)));
+
+        border.only_on_focused_monitor = true;
+        assert!(border.should_show_border_with(&system(1, 1)));
+        assert!(!border.should_show_border_with(&system(1, 2)));
+    }
+
+    #[test]
+    fn hides_border_for_always_on_top_frame_only_when_set_to_hide() {
+        let mut border = border(800, 600);
+        border.always_on_top_frame_width = Some(4);
+
+        border.always_on_top_frame = AlwaysOnTopFrame::Nest;
+        assert!(border.should_show_border_with(&system(1, 1)));
+        border.always_on_top_frame = AlwaysOnTopFrame::Hide;
+        assert!(!border.should_show_border_with(&system(1, 1)));
+    }
+
+    #[test]
+    fn ambient_frame_is_always_shown() {
+        let mut border = border(10, 10);
+        border.is_ambient_frame = true;
+        border.only_on_focused_monitor = true;
+
+        assert!(border.should_show_border_with(&MockWindowSystem::default()));
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use windows::Win32::Foundation::HWND;
+
+use crate::border_config::Config;
+use crate::utils::{get_process_name, get_window_title};
+
+const STATE_FILE_NAME: &str = "state.yaml";
+
+// Borders that the user has manually toggled off. This is kept in a separate state file so we
+// never have to write to the user's config.yaml.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WindowOverrides {
+    #[serde(default)]
+    disabled_windows: BTreeSet<String>,
+}
+
+impl WindowOverrides {
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(window_overrides) => window_overrides,
+            Err(err) => {
+                error!("could not read {STATE_FILE_NAME}: {err:#}");
+                Self::default()
+            }
+        }
+    }
+
+    fn read() -> anyhow::Result<Self> {
+        // Nothing could have been saved in read-only mode
+        if Config::is_read_only() {
+            return Ok(Self::default());
+        }
+
+        let state_path = Config::get_dir()?.join(STATE_FILE_NAME);
+
+        if !fs::exists(&state_path).context("could not check if state path exists")? {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&state_path).context("could not read state file")?;
+
+        serde_yml::from_str(&contents).map_err(anyhow::Error::new)
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let state_path = Config::get_dir()?.join(STATE_FILE_NAME);
+        let contents = serde_yml::to_string(self).context("could not serialize state")?;
+
+        fs::write(&state_path, contents).context("could not write state file")
+    }
+
+    pub fn is_border_disabled(&self, hwnd: HWND) -> bool {
+        // Don't bother computing the key if nothing has been disabled
+        if self.disabled_windows.is_empty() {
+            return false;
+        }
+
+        match get_window_key(hwnd) {
+            Ok(key) => self.disabled_windows.contains(&key),
+            Err(err) => {
+                debug!("could not get window key for {hwnd:?}: {err:#}");
+                false
+            }
+        }
+    }
+
+    // Returns true if the border is now enabled, or false if it is now disabled
+    pub fn toggle_border(&mut self, hwnd: HWND) -> anyhow::Result<bool> {
+        let key = get_window_key(hwnd)?;
+
+        // Apply the toggle to a copy first so that a failed save leaves the current state as is
+        let mut updated = self.clone();
+        let is_enabled = updated.disabled_windows.remove(&key);
+        if !is_enabled {
+            updated.disabled_windows.insert(key);
+        }
+
+        // In read-only mode, the toggle only lasts until tacky-borders exits
+        if !Config::is_read_only() {
+            updated.save()?;
+        }
+
+        *self = updated;
+        Ok(is_enabled)
+    }
+}
+
+// Create an hwnd-independent key (so it survives restarts) from the window's process name and
+// a hash of its title
+fn get_window_key(hwnd: HWND) -> anyhow::Result<String> {
+    let process_name = get_process_name(hwnd)?;
+    let title = get_window_title(hwnd)?;
+
+    Ok(format!(
+        "{}:{:016x}",
+        process_name.to_lowercase(),
+        fnv1a_hash(title.as_bytes())
+    ))
+}
+
+// We use FNV-1a instead of std's DefaultHasher because the hash must stay the same across
+// restarts and Rust versions
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
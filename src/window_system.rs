@@ -0,0 +1,148 @@
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Gdi::HMONITOR;
+
+use crate::utils;
+use crate::APP_STATE;
+
+// The window queries that border decisions (rule matching, whether to show a border) depend on.
+// Decision logic takes one of these instead of calling Win32 directly so it can be tested against a
+// mock without any real windows.
+pub trait WindowSystem {
+    fn get_process_name(&self, hwnd: HWND) -> anyhow::Result<String>;
+    fn get_process_path(&self, hwnd: HWND) -> anyhow::Result<String>;
+    fn get_process_command_line(&self, hwnd: HWND) -> anyhow::Result<String>;
+    fn get_window_title(&self, hwnd: HWND) -> anyhow::Result<String>;
+    fn get_window_class(&self, hwnd: HWND) -> anyhow::Result<String>;
+    fn has_native_border(&self, hwnd: HWND) -> bool;
+    fn get_monitor_for_window(&self, hwnd: HWND) -> HMONITOR;
+    // The window we're currently treating as active, which can lag behind the foreground window
+    fn get_active_window(&self) -> HWND;
+}
+
+// The real implementation, backed by Win32 and the app state
+pub struct Win32WindowSystem;
+
+impl WindowSystem for Win32WindowSystem {
+    fn get_process_name(&self, hwnd: HWND) -> anyhow::Result<String> {
+        utils::get_process_name(hwnd)
+    }
+
+    fn get_process_path(&self, hwnd: HWND) -> anyhow::Result<String> {
+        utils::get_process_path(hwnd)
+    }
+
+    fn get_process_command_line(&self, hwnd: HWND) -> anyhow::Result<String> {
+        utils::get_process_command_line(hwnd)
+    }
+
+    fn get_window_title(&self, hwnd: HWND) -> anyhow::Result<String> {
+        utils::get_window_title(hwnd)
+    }
+
+    fn get_window_class(&self, hwnd: HWND) -> anyhow::Result<String> {
+        utils::get_window_class(hwnd)
+    }
+
+    fn has_native_border(&self, hwnd: HWND) -> bool {
+        utils::has_native_border(hwnd)
+    }
+
+    fn get_monitor_for_window(&self, hwnd: HWND) -> HMONITOR {
+        utils::get_monitor_for_window(hwnd)
+    }
+
+    fn get_active_window(&self) -> HWND {
+        HWND(*APP_STATE.active_window.lock().unwrap() as _)
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::WindowSystem;
+    use anyhow::anyhow;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use windows::Win32::Foundation::{E_ACCESSDENIED, HWND};
+    use windows::Win32::Graphics::Gdi::HMONITOR;
+
+    // A fake window. Properties left as None fail to query with an access denied error, like
+    // windows belonging to elevated processes do.
+    #[derive(Clone, Debug, Default)]
+    pub struct MockWindow {
+        pub process: Option<String>,
+        pub path: Option<String>,
+        pub command_line: Option<String>,
+        pub title: Option<String>,
+        pub class: Option<String>,
+        pub has_native_border: bool,
+        pub monitor: isize,
+    }
+
+    #[derive(Debug, Default)]
+    pub struct MockWindowSystem {
+        pub windows: HashMap<isize, MockWindow>,
+        pub active_window: isize,
+        // Every property query as (hwnd, property), so tests can check what was looked up
+        pub queries: RefCell<Vec<(isize, &'static str)>>,
+    }
+
+    impl MockWindowSystem {
+        pub fn with_window(mut self, hwnd: isize, window: MockWindow) -> Self {
+            self.windows.insert(hwnd, window);
+            self
+        }
+
+        fn get_window(&self, hwnd: HWND) -> Option<&MockWindow> {
+            self.windows.get(&(hwnd.0 as isize))
+        }
+
+        fn query(
+            &self,
+            hwnd: HWND,
+            property: &'static str,
+            get_fn: impl Fn(&MockWindow) -> Option<String>,
+        ) -> anyhow::Result<String> {
+            self.queries.borrow_mut().push((hwnd.0 as isize, property));
+
+            let window = self
+                .get_window(hwnd)
+                .ok_or_else(|| anyhow!("no such window {hwnd:?}"))?;
+            get_fn(window).ok_or_else(|| windows::core::Error::from(E_ACCESSDENIED).into())
+        }
+    }
+
+    impl WindowSystem for MockWindowSystem {
+        fn get_process_name(&self, hwnd: HWND) -> anyhow::Result<String> {
+            self.query(hwnd, "process", |window| window.process.clone())
+        }
+
+        fn get_process_path(&self, hwnd: HWND) -> anyhow::Result<String> {
+            self.query(hwnd, "path", |window| window.path.clone())
+        }
+
+        fn get_process_command_line(&self, hwnd: HWND) -> anyhow::Result<String> {
+            self.query(hwnd, "command line", |window| window.command_line.clone())
+        }
+
+        fn get_window_title(&self, hwnd: HWND) -> anyhow::Result<String> {
+            self.query(hwnd, "title", |window| window.title.clone())
+        }
+
+        fn get_window_class(&self, hwnd: HWND) -> anyhow::Result<String> {
+            self.query(hwnd, "class", |window| window.class.clone())
+        }
+
+        fn has_native_border(&self, hwnd: HWND) -> bool {
+            self.get_window(hwnd)
+                .is_some_and(|window| window.has_native_border)
+        }
+
+        fn get_monitor_for_window(&self, hwnd: HWND) -> HMONITOR {
+            HMONITOR(self.get_window(hwnd).map_or(0, |window| window.monitor) as _)
+        }
+
+        fn get_active_window(&self) -> HWND {
+            HWND(self.active_window as _)
+        }
+    }
+}